@@ -0,0 +1,178 @@
+// Stable cache for icp token metadata fetched by the proxy canister
+// Entries expire after METADATA_CACHE_TTL_NS and are refetched on the next request.
+// Expired entries are evicted on insert, in fetch order through the expiry index.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::{storable::Bound, BTreeMap, DefaultMemoryImpl, Storable};
+use transaction_logger::endpoints::CandidIcpToken;
+
+// 6 Hours
+pub const METADATA_CACHE_TTL_NS: u64 = 6 * 60 * 60 * 1_000_000_000;
+
+// Maximum number of expired entries evicted per insert
+pub const MAX_EVICTIONS_PER_INSERT: usize = 20;
+
+const METADATA_CACHE_MEMORY_ID: MemoryId = MemoryId::new(0);
+const METADATA_CACHE_EXPIRY_MEMORY_ID: MemoryId = MemoryId::new(1);
+
+type StableMemory = VirtualMemory<DefaultMemoryImpl>;
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CachedIcpToken {
+    pub token: CandidIcpToken,
+    pub fetched_at: u64,
+}
+
+impl CachedIcpToken {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.fetched_at) > METADATA_CACHE_TTL_NS
+    }
+}
+
+impl Storable for CachedIcpToken {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("candid encoding should always succeed"))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("failed to decode cached icp token")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+
+    static METADATA_CACHE: RefCell<BTreeMap<Principal, CachedIcpToken, StableMemory>> = RefCell::new(
+        BTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(METADATA_CACHE_MEMORY_ID)))
+    );
+
+    // (fetched_at, ledger_id) of every cached entry, ordered by fetch time
+    static METADATA_CACHE_EXPIRY: RefCell<BTreeMap<(u64, Principal), (), StableMemory>> = RefCell::new(
+        BTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(METADATA_CACHE_EXPIRY_MEMORY_ID)))
+    );
+}
+
+// Returns the cached token if it is not expired yet
+pub fn get_cached_token(ledger_id: &Principal, now: u64) -> Option<CandidIcpToken> {
+    METADATA_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .get(ledger_id)
+            .filter(|cached| !cached.is_expired(now))
+            .map(|cached| cached.token)
+    })
+}
+
+pub fn cache_token(ledger_id: Principal, token: CandidIcpToken, now: u64) {
+    evict_expired_tokens(now);
+
+    let previous = METADATA_CACHE.with(|cache| {
+        cache.borrow_mut().insert(
+            ledger_id,
+            CachedIcpToken {
+                token,
+                fetched_at: now,
+            },
+        )
+    });
+
+    METADATA_CACHE_EXPIRY.with(|expiry| {
+        let mut expiry = expiry.borrow_mut();
+        if let Some(previous) = previous {
+            expiry.remove(&(previous.fetched_at, ledger_id));
+        }
+        expiry.insert((now, ledger_id), ());
+    });
+}
+
+// Removes up to MAX_EVICTIONS_PER_INSERT expired entries, oldest first
+pub fn evict_expired_tokens(now: u64) {
+    let expired: Vec<(u64, Principal)> = METADATA_CACHE_EXPIRY.with(|expiry| {
+        expiry
+            .borrow()
+            .keys()
+            .take_while(|(fetched_at, _)| now.saturating_sub(*fetched_at) > METADATA_CACHE_TTL_NS)
+            .take(MAX_EVICTIONS_PER_INSERT)
+            .collect()
+    });
+
+    for key in expired {
+        METADATA_CACHE_EXPIRY.with(|expiry| expiry.borrow_mut().remove(&key));
+        METADATA_CACHE.with(|cache| cache.borrow_mut().remove(&key.1));
+    }
+}
+
+pub fn cached_tokens_count() -> u64 {
+    METADATA_CACHE.with(|cache| cache.borrow().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
+    use transaction_logger::state::types::IcpTokenType;
+
+    fn token(ledger_id: Principal) -> CandidIcpToken {
+        CandidIcpToken {
+            ledger_id,
+            name: "Token".to_string(),
+            decimals: 8,
+            symbol: "TKN".to_string(),
+            token_type: IcpTokenType::ICRC2,
+            logo: "".to_string(),
+            usd_price: "0".to_string(),
+            fee: Nat::from(10_000_u64),
+            rank: None,
+            listed_on_appic_dex: None,
+            supported_standards: None,
+            verified: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_ttl_hit_and_miss() {
+        let ledger_id = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        assert!(get_cached_token(&ledger_id, 0).is_none());
+
+        cache_token(ledger_id, token(ledger_id), 100);
+
+        // Hit until the ttl passed
+        assert!(get_cached_token(&ledger_id, 100).is_some());
+        assert!(get_cached_token(&ledger_id, 100 + METADATA_CACHE_TTL_NS).is_some());
+
+        // Miss once expired
+        assert!(get_cached_token(&ledger_id, 101 + METADATA_CACHE_TTL_NS).is_none());
+    }
+
+    #[test]
+    fn test_expired_tokens_are_evicted_on_insert() {
+        let expired = Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap();
+        let refreshed = Principal::from_text("ss2fx-dyaaa-aaaar-qacoq-cai").unwrap();
+        let fresh = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+
+        cache_token(expired, token(expired), 0);
+        cache_token(refreshed, token(refreshed), 0);
+        // Refetching moves the entry in the expiry index
+        cache_token(refreshed, token(refreshed), METADATA_CACHE_TTL_NS);
+        assert_eq!(cached_tokens_count(), 2);
+
+        cache_token(fresh, token(fresh), METADATA_CACHE_TTL_NS + 1);
+
+        assert_eq!(cached_tokens_count(), 2);
+        assert!(METADATA_CACHE.with(|cache| cache.borrow().get(&expired).is_none()));
+        assert!(get_cached_token(&refreshed, METADATA_CACHE_TTL_NS + 1).is_some());
+        assert!(get_cached_token(&fresh, METADATA_CACHE_TTL_NS + 1).is_some());
+        assert_eq!(
+            METADATA_CACHE_EXPIRY.with(|expiry| expiry.borrow().len()),
+            2
+        );
+    }
+}
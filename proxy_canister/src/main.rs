@@ -1,21 +1,101 @@
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 
 use candid::{CandidType, Principal};
+use futures::future::join_all;
 use ic_cdk::update;
 use serde::de::DeserializeOwned;
 use transaction_logger::{
     endpoints::CandidIcpToken,
-    icp_tokens_service::{convert_to_icp_token, MetadataValue},
+    icp_tokens_service::{
+        convert_to_icp_token, with_supported_standards, MetadataValue, StandardRecord,
+    },
     minter_client::{CallError, Reason},
 };
 
+mod cache;
+
+// Maximum number of inter canister calls awaited at the same time
+const MAX_CONCURRENT_CALLS: usize = 50;
+
+// Maximum number of ledgers that can be requested in a single batch
+const MAX_LEDGERS_PER_BATCH: usize = 1_000;
+
 #[update]
 async fn get_icp_token(ledger_id: Principal) -> Result<CandidIcpToken, CallError> {
+    get_icp_token_cached(ledger_id).await
+}
+
+// Fetches metadata for a batch of ledgers, calls are made in parallel in chunks of MAX_CONCURRENT_CALLS.
+// The result for each ledger is returned separately so a single failing ledger does not fail the batch,
+// repeated ledgers are fetched and returned once.
+#[update]
+async fn get_icp_tokens(
+    ledger_ids: Vec<Principal>,
+) -> Vec<(Principal, Result<CandidIcpToken, CallError>)> {
+    if ledger_ids.len() > MAX_LEDGERS_PER_BATCH {
+        ic_cdk::trap(&format!(
+            "Too many ledgers requested, maximum is {}",
+            MAX_LEDGERS_PER_BATCH
+        ));
+    }
+
+    let ledger_ids = dedup_ledger_ids(ledger_ids);
+    let mut results = Vec::with_capacity(ledger_ids.len());
+
+    for chunk in ledger_ids.chunks(MAX_CONCURRENT_CALLS) {
+        let chunk_results = join_all(
+            chunk
+                .iter()
+                .map(|ledger_id| get_icp_token_cached(*ledger_id)),
+        )
+        .await;
+
+        results.extend(chunk.iter().copied().zip(chunk_results));
+    }
+
+    results
+}
+
+// Removes the repeated ledgers, keeping the order of their first occurrence
+fn dedup_ledger_ids(ledger_ids: Vec<Principal>) -> Vec<Principal> {
+    let mut seen = BTreeSet::new();
+    ledger_ids
+        .into_iter()
+        .filter(|ledger_id| seen.insert(*ledger_id))
+        .collect()
+}
+
+// Returns the token from the cache if available, otherwise fetches it from the ledger
+async fn get_icp_token_cached(ledger_id: Principal) -> Result<CandidIcpToken, CallError> {
+    if let Some(token) = cache::get_cached_token(&ledger_id, ic_cdk::api::time()) {
+        return Ok(token);
+    }
+
+    let token = fetch_icp_token(ledger_id).await?;
+    cache::cache_token(ledger_id, token.clone(), ic_cdk::api::time());
+
+    Ok(token)
+}
+
+async fn fetch_icp_token(ledger_id: Principal) -> Result<CandidIcpToken, CallError> {
     match call_canister::<(), Vec<(String, MetadataValue)>>(ledger_id, "icrc1_metadata", ()).await {
         // If error try again.
         Ok(metadata) => {
             if let Ok(icp_token) = convert_to_icp_token(ledger_id, metadata, Some(1), Some(false)) {
-                Ok(CandidIcpToken::from(icp_token))
+                let supported_standards = call_canister::<(), Vec<StandardRecord>>(
+                    ledger_id,
+                    "icrc1_supported_standards",
+                    (),
+                )
+                .await
+                .ok()
+                .map(|standards| standards.into_iter().map(|record| record.name).collect());
+
+                Ok(CandidIcpToken::from(with_supported_standards(
+                    icp_token,
+                    supported_standards,
+                )))
             } else {
                 Err(CallError {
                     method: "icrc1_metadata".to_string(),
@@ -48,3 +128,18 @@ where
 fn main() {}
 
 ic_cdk::export_candid!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_ledger_ids() {
+        let a = Principal::from_slice(&[1]);
+        let b = Principal::from_slice(&[2]);
+        let c = Principal::from_slice(&[3]);
+
+        assert_eq!(dedup_ledger_ids(vec![b, a, b, c, a]), vec![b, a, c]);
+        assert_eq!(dedup_ledger_ids(vec![]), vec![]);
+    }
+}
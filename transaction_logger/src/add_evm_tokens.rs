@@ -6,27 +6,93 @@ const SUPPORTED_EVM_TOKENS: &str = include_str!("../../evm_tokens/supported_toke
 use crate::{
     logs::INFO,
     state::{
-        mutate_state,
-        types::{Erc20Identifier, EvmToken},
+        mutate_state, read_state,
+        types::{EvmToken, EvmTokenField, EvmTokenSource},
     },
 };
 
+// Syncs the bundled token list into state
+// The list is only applied if it changed since the last sync, so tokens and fields managed at runtime
+// are not overwritten on every upgrade
 pub fn add_evm_tokens_to_state() {
+    let version = bundled_evm_tokens_version();
+
+    if read_state(|s| s.get_bundled_evm_tokens_version()).as_ref() == Some(&version) {
+        log!(
+            INFO,
+            "[Add EVM Tokens] Bundled EVM token list {} is already synced",
+            version
+        );
+        return;
+    }
+
     log!(
         INFO,
-        "[Add EVM Tokens] Adding new EVM tokens from json files",
+        "[Add EVM Tokens] Syncing bundled EVM token list {}",
+        version
     );
+
     mutate_state(|s| {
-        let tokens: Vec<(Erc20Identifier, EvmToken)> = s.evm_token_list.iter().collect();
+        s.sync_bundled_evm_tokens(deserialize_all_tokens(), version, ic_cdk::api::time())
+    });
+}
 
-        for token in tokens {
-            s.evm_token_list.remove(&token.0);
-        }
+// Hash of the bundled json file
+pub fn bundled_evm_tokens_version() -> String {
+    hex::encode(ic_sha3::Keccak256::hash(SUPPORTED_EVM_TOKENS.as_bytes()))
+}
 
-        deserialize_all_tokens()
-            .into_iter()
-            .for_each(|token| s.record_evm_token(Erc20Identifier::from(&token), token))
-    });
+// Merges a token from the bundled list with the one already in state
+// Prices, volumes and runtime managed fields are kept from the existing token,
+// and tokens imported at runtime stay runtime tokens so that they are never removed by a sync
+pub fn merge_bundled_evm_token(existing: Option<EvmToken>, bundled: EvmToken) -> EvmToken {
+    let Some(existing) = existing else {
+        return EvmToken {
+            source: Some(EvmTokenSource::Bundled),
+            ..bundled
+        };
+    };
+
+    let managed_fields = existing.runtime_managed_fields.clone().unwrap_or_default();
+    let is_managed = |field: EvmTokenField| managed_fields.contains(&field);
+
+    EvmToken {
+        chain_id: bundled.chain_id,
+        erc20_contract_address: bundled.erc20_contract_address,
+        name: if is_managed(EvmTokenField::Name) {
+            existing.name
+        } else {
+            bundled.name
+        },
+        decimals: if is_managed(EvmTokenField::Decimals) {
+            existing.decimals
+        } else {
+            bundled.decimals
+        },
+        symbol: if is_managed(EvmTokenField::Symbol) {
+            existing.symbol
+        } else {
+            bundled.symbol
+        },
+        logo: if is_managed(EvmTokenField::Logo) {
+            existing.logo
+        } else {
+            bundled.logo
+        },
+        cmc_id: if is_managed(EvmTokenField::CmcId) {
+            existing.cmc_id
+        } else {
+            bundled.cmc_id
+        },
+        is_wrapped_icrc: existing.is_wrapped_icrc || bundled.is_wrapped_icrc,
+        usd_price: existing.usd_price,
+        volume_usd_24h: existing.volume_usd_24h,
+        runtime_managed_fields: existing.runtime_managed_fields,
+        source: match existing.source {
+            Some(EvmTokenSource::Runtime) => Some(EvmTokenSource::Runtime),
+            _ => Some(EvmTokenSource::Bundled),
+        },
+    }
 }
 
 pub fn deserialize_all_tokens() -> Vec<EvmToken> {
@@ -51,10 +117,11 @@ mod tests {
 
     use crate::{
         add_evm_tokens::{
-            deserialize_all_tokens, deserialize_json_into_evm_token, SUPPORTED_EVM_TOKENS,
+            deserialize_all_tokens, deserialize_json_into_evm_token, merge_bundled_evm_token,
+            SUPPORTED_EVM_TOKENS,
         },
         scrape_events::NATIVE_ERC20_ADDRESS,
-        state::types::{ChainId, EvmToken},
+        state::types::{ChainId, EvmToken, EvmTokenField, EvmTokenSource},
     };
 
     #[test]
@@ -71,6 +138,8 @@ mod tests {
             cmc_id: Some(2),
             usd_price: None,
             volume_usd_24h: None,
+            runtime_managed_fields: None,
+            source: None,
         };
 
         assert_eq!(
@@ -103,6 +172,8 @@ mod tests {
                 cmc_id: Some(1027),
                 usd_price: None,
                 volume_usd_24h: None,
+                runtime_managed_fields: None,
+                source: None,
             },
             EvmToken {
                 chain_id: ChainId(8453),
@@ -115,6 +186,8 @@ mod tests {
                 cmc_id: Some(1027),
                 usd_price: None,
                 volume_usd_24h: None,
+                runtime_managed_fields: None,
+                source: None,
             },
             EvmToken {
                 chain_id: ChainId(56),
@@ -127,9 +200,69 @@ mod tests {
                 cmc_id: Some(1839),
                 usd_price: None,
                 volume_usd_24h: None,
+                runtime_managed_fields: None,
+                source: None,
             },
         ];
 
         assert_eq!(filtered_native_token_list, expected_native_tokens);
     }
+
+    #[test]
+    fn should_keep_runtime_managed_fields_when_merging_bundled_token() {
+        let bundled = deserialize_json_into_evm_token(SUPPORTED_EVM_TOKENS)[0].clone();
+
+        // New tokens are recorded as bundled
+        let merged = merge_bundled_evm_token(None, bundled.clone());
+        assert_eq!(merged.source, Some(EvmTokenSource::Bundled));
+        assert_eq!(merged.name, bundled.name);
+
+        let existing = EvmToken {
+            name: "Custom Name".to_string(),
+            logo: "https://example.com/logo.png".to_string(),
+            usd_price: Some("100".to_string()),
+            volume_usd_24h: Some("1000".to_string()),
+            runtime_managed_fields: Some(vec![EvmTokenField::Name]),
+            source: Some(EvmTokenSource::Bundled),
+            ..bundled.clone()
+        };
+
+        let merged = merge_bundled_evm_token(Some(existing), bundled.clone());
+
+        // Runtime managed name is kept, logo is overwritten by the bundled list
+        assert_eq!(merged.name, "Custom Name");
+        assert_eq!(merged.logo, bundled.logo);
+
+        // Prices and volumes are always kept
+        assert_eq!(merged.usd_price, Some("100".to_string()));
+        assert_eq!(merged.volume_usd_24h, Some("1000".to_string()));
+        assert_eq!(
+            merged.runtime_managed_fields,
+            Some(vec![EvmTokenField::Name])
+        );
+        assert_eq!(merged.source, Some(EvmTokenSource::Bundled));
+    }
+
+    #[test]
+    fn should_keep_runtime_source_when_merging_bundled_token() {
+        let bundled = deserialize_json_into_evm_token(SUPPORTED_EVM_TOKENS)[0].clone();
+
+        let existing = EvmToken {
+            name: "Imported Name".to_string(),
+            runtime_managed_fields: Some(vec![
+                EvmTokenField::Name,
+                EvmTokenField::Symbol,
+                EvmTokenField::Decimals,
+                EvmTokenField::Logo,
+            ]),
+            source: Some(EvmTokenSource::Runtime),
+            ..bundled.clone()
+        };
+
+        let merged = merge_bundled_evm_token(Some(existing), bundled.clone());
+
+        assert_eq!(merged.name, "Imported Name");
+        assert_eq!(merged.cmc_id, bundled.cmc_id);
+        assert_eq!(merged.source, Some(EvmTokenSource::Runtime));
+    }
 }
@@ -16,7 +16,7 @@ impl DexClient {
     }
 
     // Get total events count
-    pub async fn get_total_events_count(&self) -> u64 {
+    pub async fn get_total_events_count(&self) -> Result<u64, CallError> {
         // Get total events count
         let total_events_count = self
             .runtime
@@ -28,11 +28,10 @@ impl DexClient {
                     length: 0,
                 },
             )
-            .await
-            .expect("Call should not fail. will retry in next interval")
+            .await?
             .total_event_count;
 
-        total_events_count
+        Ok(total_events_count)
     }
 
     // scrape events
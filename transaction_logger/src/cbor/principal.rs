@@ -37,3 +37,31 @@ pub mod option {
         (*v).map(CborPrincipal).encode(e, ctx)
     }
 }
+
+pub mod vec {
+    use super::*;
+    use minicbor::{Decode, Encode};
+
+    #[derive(Encode, Decode)]
+    #[cbor(transparent)]
+    struct CborPrincipal(#[cbor(n(0), with = "crate::cbor::principal")] pub Principal);
+
+    pub fn decode<Ctx>(d: &mut Decoder<'_>, ctx: &mut Ctx) -> Result<Vec<Principal>, Error> {
+        Ok(Vec::<CborPrincipal>::decode(d, ctx)?
+            .into_iter()
+            .map(|n| n.0)
+            .collect())
+    }
+
+    pub fn encode<Ctx, W: Write>(
+        v: &[Principal],
+        e: &mut Encoder<W>,
+        ctx: &mut Ctx,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        v.iter()
+            .copied()
+            .map(CborPrincipal)
+            .collect::<Vec<_>>()
+            .encode(e, ctx)
+    }
+}
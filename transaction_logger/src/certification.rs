@@ -0,0 +1,550 @@
+// Certified data of the canister, the root hash of a tree with the following labels:
+// - bridge_pairs: hash of the listed bridge pairs
+// - bridge_routes: tree of the routes of the listed bridge pairs, the key is
+//   chain id (big endian u64) | erc20 address | operator (0 DFINITY ckETH minter, 1 Appic minter)
+// - last_block_hash and last_block_index: tip of the block log as required by ICRC-3
+//   https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-3/README.md
+// - tokens: tree of the evm and icp tokens, the key is
+//   0 | chain id (big endian u64) | erc20 address for evm tokens and 1 | ledger id for icp tokens
+// - txs: tree of the transactions of every principal, the key is
+//   principal length | principal | kind (0 evm to icp, 1 icp to evm, 2 dex action) | identifier
+//   where the identifier of a dex action is its big endian u64 index in the actions of the principal
+// Every value is the hash of the ICRC-3 value of the certified response, see values.rs.
+// The tree is kept in heap memory, the hashes of the transactions are also kept in stable memory by
+// the state and reloaded in batches after upgrades while the other leaves are certified again from
+// the state. The certified data is set at most once per message.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_certified_map::{fork, labeled, labeled_hash, leaf, Hash, HashTree, RbTree};
+use minicbor::Encode;
+use serde::Serialize;
+
+use crate::endpoints::{BridgeRoute, TokenPair};
+use crate::state::types::{Erc20Identifier, Operator};
+
+pub mod values;
+
+pub use values::{certified_hash, CertifiedValue};
+
+const BRIDGE_PAIRS_LABEL: &[u8] = b"bridge_pairs";
+const BRIDGE_ROUTES_LABEL: &[u8] = b"bridge_routes";
+const LAST_BLOCK_HASH_LABEL: &[u8] = b"last_block_hash";
+const LAST_BLOCK_INDEX_LABEL: &[u8] = b"last_block_index";
+const TOKENS_LABEL: &[u8] = b"tokens";
+const TXS_LABEL: &[u8] = b"txs";
+
+const EVM_TOKEN_KEY_PREFIX: u8 = 0;
+const ICP_TOKEN_KEY_PREFIX: u8 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CertifiedTxKind {
+    EvmToIcp = 0,
+    IcpToEvm = 1,
+    DexActions = 2,
+}
+
+#[derive(Default)]
+struct CertifiedTree {
+    tip: Option<(u64, Hash)>,
+    // Certified bridge pairs and routes are kept so that responses always match the certified hashes
+    bridge_pairs: Option<(Hash, Vec<TokenPair>)>,
+    bridge_routes: RbTree<Vec<u8>, Hash>,
+    routes: BTreeMap<Vec<u8>, BridgeRoute>,
+    tokens: RbTree<Vec<u8>, Hash>,
+    txs: RbTree<Vec<u8>, Hash>,
+    // Set when the tree changed since the certified data was last set
+    is_dirty: bool,
+    // Set while the leaves are reloaded, with the key of the last reloaded leaf
+    reload: Option<Option<Vec<u8>>>,
+}
+
+// Part of the tree revealed by a witness, the rest is pruned
+enum Reveal<'a> {
+    Nothing,
+    Tip,
+    BridgePairs,
+    BridgeRoutes(HashTree<'a>),
+    Tokens(HashTree<'a>),
+    Txs(HashTree<'a>),
+}
+
+thread_local! {
+    static CERTIFIED_TREE: RefCell<CertifiedTree> = RefCell::default();
+}
+
+impl CertifiedTree {
+    fn hash_tree<'a>(&'a self, reveal: Reveal<'a>) -> HashTree<'a> {
+        let (last_block_hash, last_block_index) = match self.tip {
+            Some((last_block_index, last_block_hash)) => (
+                labeled(LAST_BLOCK_HASH_LABEL, leaf(last_block_hash.to_vec())),
+                labeled(
+                    LAST_BLOCK_INDEX_LABEL,
+                    leaf(leb128_encode(last_block_index)),
+                ),
+            ),
+            None => (HashTree::Empty, HashTree::Empty),
+        };
+
+        let bridge_pairs = match &self.bridge_pairs {
+            Some((hash, _bridge_pairs)) => labeled(BRIDGE_PAIRS_LABEL, leaf(hash.to_vec())),
+            None => HashTree::Empty,
+        };
+
+        let (reveal_tip, reveal_bridge_pairs) = match reveal {
+            Reveal::Tip => (true, false),
+            Reveal::BridgePairs => (false, true),
+            _ => (false, false),
+        };
+        let (routes_witness, tokens_witness, txs_witness) = match reveal {
+            Reveal::BridgeRoutes(witness) => (Some(witness), None, None),
+            Reveal::Tokens(witness) => (None, Some(witness), None),
+            Reveal::Txs(witness) => (None, None, Some(witness)),
+            _ => (None, None, None),
+        };
+
+        // Labels are ordered from left to right so that clients can look them up
+        fork(
+            fork(
+                fork(
+                    prune_unless(reveal_bridge_pairs, bridge_pairs),
+                    subtree(BRIDGE_ROUTES_LABEL, &self.bridge_routes, routes_witness),
+                ),
+                prune_unless(reveal_tip, last_block_hash),
+            ),
+            fork(
+                prune_unless(reveal_tip, last_block_index),
+                fork(
+                    subtree(TOKENS_LABEL, &self.tokens, tokens_witness),
+                    subtree(TXS_LABEL, &self.txs, txs_witness),
+                ),
+            ),
+        )
+    }
+
+    fn certify(&self) {
+        // The certified data can only be set by a canister, unit tests only check the tree
+        #[cfg(target_arch = "wasm32")]
+        ic_cdk::api::set_certified_data(&self.hash_tree(Reveal::Nothing).reconstruct());
+    }
+}
+
+fn prune_unless(reveal: bool, tree: HashTree<'_>) -> HashTree<'_> {
+    if reveal {
+        tree
+    } else {
+        HashTree::Pruned(tree.reconstruct())
+    }
+}
+
+fn subtree<'a>(
+    label: &'a [u8],
+    tree: &RbTree<Vec<u8>, Hash>,
+    witness: Option<HashTree<'a>>,
+) -> HashTree<'a> {
+    match witness {
+        Some(witness) => labeled(label, witness),
+        None => HashTree::Pruned(labeled_hash(label, &tree.root_hash())),
+    }
+}
+
+// Merges two witnesses of the same tree into a witness that reveals the leaves of both
+fn merge_witnesses<'a>(left: HashTree<'a>, right: HashTree<'a>) -> HashTree<'a> {
+    match (left, right) {
+        (HashTree::Pruned(_), tree) | (tree, HashTree::Pruned(_)) => tree,
+        (HashTree::Fork(left), HashTree::Fork(right)) => {
+            let (left_left, left_right) = *left;
+            let (right_left, right_right) = *right;
+            fork(
+                merge_witnesses(left_left, right_left),
+                merge_witnesses(left_right, right_right),
+            )
+        }
+        (HashTree::Labeled(label, left), HashTree::Labeled(_, right)) => {
+            HashTree::Labeled(label, Box::new(merge_witnesses(*left, *right)))
+        }
+        (tree, _) => tree,
+    }
+}
+
+fn mutate_tree<F>(f: F)
+where
+    F: FnOnce(&mut CertifiedTree),
+{
+    CERTIFIED_TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        f(&mut tree);
+        tree.is_dirty = true;
+    })
+}
+
+// Sets the certified data if the tree changed, called once at the end of every state mutation
+pub fn certify_if_changed() {
+    CERTIFIED_TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        if tree.is_dirty {
+            tree.certify();
+            tree.is_dirty = false;
+        }
+    })
+}
+
+pub fn leb128_encode(mut value: u64) -> Vec<u8> {
+    let mut buf = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return buf;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+// Transactions of a principal are stored next to each other,
+// so that they can be witnessed with a single range
+fn principal_prefix(principal: &Principal) -> Vec<u8> {
+    let bytes = principal.as_slice();
+    let mut prefix = Vec::with_capacity(bytes.len() + 1);
+    prefix.push(bytes.len() as u8);
+    prefix.extend_from_slice(bytes);
+    prefix
+}
+
+pub fn tx_key<I: Encode<()>>(
+    principal: &Principal,
+    kind: CertifiedTxKind,
+    identifier: &I,
+) -> Vec<u8> {
+    let mut key = principal_prefix(principal);
+    key.push(kind as u8);
+    minicbor::encode(identifier, &mut key).expect("minicbor encoding should always succeed");
+    key
+}
+
+// Key of a dex action, the index of the action in the actions of the principal is big endian
+// so that the actions are ordered in the tree
+pub fn dex_action_key(principal: &Principal, index: u64) -> Vec<u8> {
+    let mut key = principal_prefix(principal);
+    key.push(CertifiedTxKind::DexActions as u8);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn erc20_key(erc20_identifier: &Erc20Identifier) -> Vec<u8> {
+    let mut key = erc20_identifier.chain_id().0.to_be_bytes().to_vec();
+    key.extend_from_slice(erc20_identifier.erc20_address().as_ref());
+    key
+}
+
+// Routes of the same erc20 token are next to each other, ordered by operator
+pub fn bridge_route_key(erc20_identifier: &Erc20Identifier, operator: Operator) -> Vec<u8> {
+    let mut key = erc20_key(erc20_identifier);
+    key.push(match operator {
+        Operator::DfinityCkEthMinter => 0,
+        Operator::AppicMinter => 1,
+    });
+    key
+}
+
+pub fn evm_token_key(erc20_identifier: &Erc20Identifier) -> Vec<u8> {
+    let mut key = vec![EVM_TOKEN_KEY_PREFIX];
+    key.extend(erc20_key(erc20_identifier));
+    key
+}
+
+pub fn icp_token_key(ledger_id: &Principal) -> Vec<u8> {
+    let mut key = vec![ICP_TOKEN_KEY_PREFIX];
+    key.extend_from_slice(ledger_id.as_slice());
+    key
+}
+
+pub fn certify_tip(tip: Option<(u64, Hash)>) {
+    mutate_tree(|tree| tree.tip = tip)
+}
+
+// Bridge pairs and their routes are certified together since routes are derived from the pairs
+pub fn certify_bridge_pairs(
+    bridge_pairs: Vec<TokenPair>,
+    routes: Vec<(Erc20Identifier, BridgeRoute)>,
+) {
+    let hash = certified_hash(&bridge_pairs);
+    let routes: BTreeMap<Vec<u8>, BridgeRoute> = routes
+        .into_iter()
+        .map(|(erc20_identifier, route)| {
+            (bridge_route_key(&erc20_identifier, route.operator), route)
+        })
+        .collect();
+
+    mutate_tree(|tree| {
+        tree.bridge_pairs = Some((hash, bridge_pairs));
+        tree.bridge_routes = RbTree::new();
+        for (key, route) in routes.iter() {
+            tree.bridge_routes
+                .insert(key.clone(), certified_hash(route));
+        }
+        tree.routes = routes;
+    })
+}
+
+// Certifies the current version of a token, None if the token was removed
+pub fn certify_token(key: Vec<u8>, hash: Option<Hash>) {
+    mutate_tree(|tree| match hash {
+        Some(hash) => tree.tokens.insert(key, hash),
+        None => tree.tokens.delete(&key),
+    })
+}
+
+// Replaces the certified tokens, used once the tree is lost on upgrades
+pub fn certify_tokens(tokens: Vec<(Vec<u8>, Hash)>) {
+    mutate_tree(|tree| {
+        tree.tokens = RbTree::new();
+        for (key, hash) in tokens {
+            tree.tokens.insert(key, hash);
+        }
+    })
+}
+
+pub fn certify_leaf(key: Vec<u8>, hash: Hash) {
+    mutate_tree(|tree| tree.txs.insert(key, hash))
+}
+
+pub fn uncertify_leaf(key: &[u8]) {
+    mutate_tree(|tree| tree.txs.delete(key))
+}
+
+// Clears the leaves before they are reloaded from stable memory
+pub fn start_leaves_reload() {
+    mutate_tree(|tree| {
+        tree.txs = RbTree::new();
+        tree.reload = Some(None);
+    })
+}
+
+// Key of the last reloaded leaf, None if no leaves are being reloaded
+pub fn leaves_reload_cursor() -> Option<Option<Vec<u8>>> {
+    CERTIFIED_TREE.with(|tree| tree.borrow().reload.clone())
+}
+
+// Inserts a batch of reloaded leaves, leaves written since the reload started are already in the tree
+// and reloaded with the same hash
+pub fn reload_leaves(leaves: Vec<(Vec<u8>, Hash)>, is_complete: bool) {
+    mutate_tree(|tree| {
+        let cursor = leaves.last().map(|(key, _hash)| key.clone());
+        for (key, hash) in leaves {
+            tree.txs.insert(key, hash);
+        }
+        tree.reload = if is_complete { None } else { Some(cursor) };
+    })
+}
+
+// Witnesses are only complete once the leaves are reloaded
+pub fn is_reloading_leaves() -> bool {
+    CERTIFIED_TREE.with(|tree| tree.borrow().reload.is_some())
+}
+
+// CBOR encoded hash tree, as expected by the clients verifying the certificate
+pub fn encode_hash_tree(tree: &HashTree) -> Vec<u8> {
+    let mut serializer = serde_cbor::ser::Serializer::new(vec![]);
+    serializer
+        .self_describe()
+        .expect("Encoding the self describe tag should not fail");
+    tree.serialize(&mut serializer)
+        .expect("Encoding the hash tree should not fail");
+    serializer.into_inner()
+}
+
+// Witness of the block log tip
+pub fn tip_witness() -> Vec<u8> {
+    CERTIFIED_TREE.with(|tree| encode_hash_tree(&tree.borrow().hash_tree(Reveal::Tip)))
+}
+
+// Last certified bridge pairs with their witness
+pub fn certified_bridge_pairs() -> (Vec<TokenPair>, Vec<u8>) {
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let bridge_pairs = tree
+            .bridge_pairs
+            .as_ref()
+            .map(|(_hash, bridge_pairs)| bridge_pairs.clone())
+            .unwrap_or_default();
+        (
+            bridge_pairs,
+            encode_hash_tree(&tree.hash_tree(Reveal::BridgePairs)),
+        )
+    })
+}
+
+// Last certified routes of an erc20 token, the witness also proves that there are no other routes
+pub fn certified_bridge_routes(erc20_identifier: &Erc20Identifier) -> (Vec<BridgeRoute>, Vec<u8>) {
+    let first = erc20_key(erc20_identifier);
+    let mut last = first.clone();
+    last.push(u8::MAX);
+
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let routes = tree
+            .routes
+            .range(first.clone()..=last.clone())
+            .map(|(_key, route)| route.clone())
+            .collect();
+        let witness = tree.bridge_routes.value_range(&first, &last);
+        (
+            routes,
+            encode_hash_tree(&tree.hash_tree(Reveal::BridgeRoutes(witness))),
+        )
+    })
+}
+
+// Last certified routes of an icrc token with a witness of each route
+pub fn certified_bridge_routes_for_icrc(ledger_id: &Principal) -> (Vec<BridgeRoute>, Vec<u8>) {
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let (keys, routes): (Vec<&Vec<u8>>, Vec<BridgeRoute>) = tree
+            .routes
+            .iter()
+            .filter(|(_key, route)| route.icp_token.ledger_id == *ledger_id)
+            .map(|(key, route)| (key, route.clone()))
+            .unzip();
+        let reveal = match keys
+            .into_iter()
+            .map(|key| tree.bridge_routes.witness(key))
+            .reduce(merge_witnesses)
+        {
+            Some(witness) => Reveal::BridgeRoutes(witness),
+            None => Reveal::Nothing,
+        };
+        (routes, encode_hash_tree(&tree.hash_tree(reveal)))
+    })
+}
+
+// Witness of a single token, an absence proof if the key is not certified
+pub fn token_witness(key: &[u8]) -> Vec<u8> {
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        encode_hash_tree(&tree.hash_tree(Reveal::Tokens(tree.tokens.witness(key))))
+    })
+}
+
+// Witness of all the icp tokens
+pub fn icp_tokens_witness() -> Vec<u8> {
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let witness = tree
+            .tokens
+            .value_range(&[ICP_TOKEN_KEY_PREFIX], &[ICP_TOKEN_KEY_PREFIX + 1]);
+        encode_hash_tree(&tree.hash_tree(Reveal::Tokens(witness)))
+    })
+}
+
+// Witness of the given transactions and, if a principal is given, of all the transactions
+// and dex actions of the principal. Reveals nothing if there is nothing to witness
+pub fn transactions_witness(principal: Option<&Principal>, keys: &[Vec<u8>]) -> Vec<u8> {
+    CERTIFIED_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let principal_witness = principal.map(|principal| {
+            let first = principal_prefix(principal);
+            let mut last = first.clone();
+            last.push(u8::MAX);
+            tree.txs.value_range(&first, &last)
+        });
+        let reveal = match keys
+            .iter()
+            .map(|key| tree.txs.witness(key))
+            .chain(principal_witness)
+            .reduce(merge_witnesses)
+        {
+            Some(witness) => Reveal::Txs(witness),
+            None => Reveal::Nothing,
+        };
+        encode_hash_tree(&tree.hash_tree(reveal))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128_encode() {
+        assert_eq!(leb128_encode(0), vec![0x00]);
+        assert_eq!(leb128_encode(127), vec![0x7f]);
+        assert_eq!(leb128_encode(128), vec![0x80, 0x01]);
+        assert_eq!(leb128_encode(624_485), vec![0xe5, 0x8e, 0x26]);
+    }
+
+    #[test]
+    fn test_witnesses_reconstruct_to_the_same_root() {
+        let principal = Principal::anonymous();
+        let mut tree = CertifiedTree {
+            tip: Some((3, [1; 32])),
+            ..Default::default()
+        };
+        tree.txs.insert(
+            tx_key(&principal, CertifiedTxKind::IcpToEvm, &7_u64),
+            [2; 32],
+        );
+        tree.txs.insert(dex_action_key(&principal, 0), [3; 32]);
+        tree.tokens.insert(icp_token_key(&principal), [4; 32]);
+        tree.bridge_routes.insert(vec![0; 29], [5; 32]);
+
+        let root = tree.hash_tree(Reveal::Nothing).reconstruct();
+        assert_eq!(tree.hash_tree(Reveal::Tip).reconstruct(), root);
+        assert_eq!(tree.hash_tree(Reveal::BridgePairs).reconstruct(), root);
+        let token = tree.tokens.witness(&icp_token_key(&principal));
+        assert_eq!(tree.hash_tree(Reveal::Tokens(token)).reconstruct(), root);
+        let route = tree.bridge_routes.witness(&[0; 29]);
+        assert_eq!(
+            tree.hash_tree(Reveal::BridgeRoutes(route)).reconstruct(),
+            root
+        );
+
+        let mut last = principal_prefix(&principal);
+        let first = last.clone();
+        last.push(u8::MAX);
+        let range = tree.txs.value_range(&first, &last);
+        assert_eq!(tree.hash_tree(Reveal::Txs(range)).reconstruct(), root);
+    }
+
+    #[test]
+    fn test_merged_witnesses_reveal_every_key() {
+        let mut txs: RbTree<Vec<u8>, Hash> = RbTree::new();
+        for index in 0..10_u8 {
+            txs.insert(vec![index], [index; 32]);
+        }
+
+        let merged = merge_witnesses(txs.witness(&[2]), txs.witness(&[7]));
+        assert_eq!(merged.reconstruct(), txs.root_hash());
+        assert_eq!(lookup(&merged, &[2]), Some([2; 32].to_vec()));
+        assert_eq!(lookup(&merged, &[7]), Some([7; 32].to_vec()));
+        assert_eq!(lookup(&merged, &[5]), None);
+    }
+
+    fn lookup(tree: &HashTree<'_>, key: &[u8]) -> Option<Vec<u8>> {
+        match tree {
+            HashTree::Fork(children) => {
+                lookup(&children.0, key).or_else(|| lookup(&children.1, key))
+            }
+            HashTree::Labeled(label, subtree) if *label == key => match subtree.as_ref() {
+                HashTree::Leaf(value) => Some(value.to_vec()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_dex_action_keys_are_ordered_by_index() {
+        let principal = Principal::anonymous();
+        assert!(dex_action_key(&principal, 1) < dex_action_key(&principal, 2));
+        assert!(dex_action_key(&principal, 255) < dex_action_key(&principal, 256));
+
+        // Dex actions are in the range of the transactions of the principal
+        let mut last = principal_prefix(&principal);
+        last.push(u8::MAX);
+        assert!(dex_action_key(&principal, u64::MAX) < last);
+    }
+}
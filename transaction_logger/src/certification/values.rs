@@ -0,0 +1,654 @@
+// Canonical form of the certified responses, the leaves of the certified tree are the hashes of
+// their ICRC-3 values so that clients can verify a response independently of its candid encoding:
+// - records are maps keyed by field name, null optional fields are left out
+// - variants without payload are the text of their name, other variants are maps with
+//   a single entry keyed by the variant name
+// - nat, nat8 to nat64 and bool (0 or 1) are nats, int is an int, principals and fixed size blobs
+//   are blobs and vectors are arrays
+
+use std::collections::BTreeMap;
+
+use candid::{Int, Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use serde_bytes::ByteBuf;
+
+use crate::endpoints::{
+    BridgeRoute, CandidDexAction, CandidEvmToIcp, CandidEvmToIcpTxIdentifier, CandidEvmToken,
+    CandidIcpToEvm, CandidIcpToEvmReimbursement, CandidIcpToken, CandidPoolId, CandidPositionKey,
+    CandidSwapType, TokenPair, Transaction,
+};
+use crate::state::types::{
+    EvmToIcpStatus, IcpToEvmReimbursementReason, IcpToEvmStatus, IcpTokenType, Operator,
+};
+
+pub trait CertifiedValue {
+    fn to_certified_value(&self) -> ICRC3Value;
+}
+
+pub fn certified_hash<T: CertifiedValue + ?Sized>(value: &T) -> [u8; 32] {
+    value.to_certified_value().hash()
+}
+
+#[derive(Default)]
+struct Record(BTreeMap<String, ICRC3Value>);
+
+impl Record {
+    fn field<T: CertifiedValue + ?Sized>(mut self, name: &str, value: &T) -> Self {
+        self.0.insert(name.to_string(), value.to_certified_value());
+        self
+    }
+
+    fn optional_field<T: CertifiedValue>(self, name: &str, value: &Option<T>) -> Self {
+        match value {
+            Some(value) => self.field(name, value),
+            None => self,
+        }
+    }
+
+    fn build(self) -> ICRC3Value {
+        ICRC3Value::Map(self.0)
+    }
+}
+
+fn unit_variant(name: &str) -> ICRC3Value {
+    ICRC3Value::Text(name.to_string())
+}
+
+fn variant(name: &str, payload: ICRC3Value) -> ICRC3Value {
+    ICRC3Value::Map(BTreeMap::from([(name.to_string(), payload)]))
+}
+
+impl CertifiedValue for str {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Text(self.to_string())
+    }
+}
+
+impl CertifiedValue for String {
+    fn to_certified_value(&self) -> ICRC3Value {
+        self.as_str().to_certified_value()
+    }
+}
+
+impl CertifiedValue for Nat {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Nat(self.clone())
+    }
+}
+
+impl CertifiedValue for Int {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Int(self.clone())
+    }
+}
+
+impl CertifiedValue for u8 {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Nat(Nat::from(*self))
+    }
+}
+
+impl CertifiedValue for u32 {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Nat(Nat::from(*self))
+    }
+}
+
+impl CertifiedValue for u64 {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Nat(Nat::from(*self))
+    }
+}
+
+impl CertifiedValue for bool {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Nat(Nat::from(*self as u8))
+    }
+}
+
+impl CertifiedValue for Principal {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Blob(ByteBuf::from(self.as_slice().to_vec()))
+    }
+}
+
+impl CertifiedValue for [u8; 32] {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Blob(ByteBuf::from(self.to_vec()))
+    }
+}
+
+impl<T: CertifiedValue> CertifiedValue for Vec<T> {
+    fn to_certified_value(&self) -> ICRC3Value {
+        ICRC3Value::Array(self.iter().map(T::to_certified_value).collect())
+    }
+}
+
+impl CertifiedValue for Operator {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            Operator::DfinityCkEthMinter => unit_variant("DfinityCkEthMinter"),
+            Operator::AppicMinter => unit_variant("AppicMinter"),
+        }
+    }
+}
+
+impl CertifiedValue for Transaction {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            Transaction::IcpToEvm(tx) => variant("IcpToEvm", tx.to_certified_value()),
+            Transaction::EvmToIcp(tx) => variant("EvmToIcp", tx.to_certified_value()),
+            Transaction::DexAction(action) => variant("DexAction", action.to_certified_value()),
+        }
+    }
+}
+
+impl CertifiedValue for EvmToIcpStatus {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            EvmToIcpStatus::PendingVerification => unit_variant("PendingVerification"),
+            EvmToIcpStatus::Accepted => unit_variant("Accepted"),
+            EvmToIcpStatus::Minted => unit_variant("Minted"),
+            EvmToIcpStatus::Invalid(reason) => variant("Invalid", reason.to_certified_value()),
+            EvmToIcpStatus::Quarantined => unit_variant("Quarantined"),
+            EvmToIcpStatus::Expired => unit_variant("Expired"),
+        }
+    }
+}
+
+impl CertifiedValue for CandidEvmToIcp {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidEvmToIcp {
+            from_address,
+            transaction_hash,
+            value,
+            block_number,
+            ledger_mint_index,
+            actual_received,
+            principal,
+            subaccount,
+            chain_id,
+            total_gas_spent,
+            erc20_contract_address,
+            icrc_ledger_id,
+            status,
+            verified,
+            time,
+            operator,
+        } = self;
+
+        Record::default()
+            .field("from_address", from_address)
+            .field("transaction_hash", transaction_hash)
+            .field("value", value)
+            .optional_field("block_number", block_number)
+            .optional_field("ledger_mint_index", ledger_mint_index)
+            .optional_field("actual_received", actual_received)
+            .field("principal", principal)
+            .optional_field("subaccount", subaccount)
+            .field("chain_id", chain_id)
+            .optional_field("total_gas_spent", total_gas_spent)
+            .field("erc20_contract_address", erc20_contract_address)
+            .optional_field("icrc_ledger_id", icrc_ledger_id)
+            .field("status", status)
+            .field("verified", verified)
+            .field("time", time)
+            .field("operator", operator)
+            .build()
+    }
+}
+
+impl CertifiedValue for IcpToEvmStatus {
+    fn to_certified_value(&self) -> ICRC3Value {
+        unit_variant(match self {
+            IcpToEvmStatus::PendingVerification => "PendingVerification",
+            IcpToEvmStatus::Accepted => "Accepted",
+            IcpToEvmStatus::Created => "Created",
+            IcpToEvmStatus::SignedTransaction => "SignedTransaction",
+            IcpToEvmStatus::ReplacedTransaction => "ReplacedTransaction",
+            IcpToEvmStatus::Reimbursed => "Reimbursed",
+            IcpToEvmStatus::QuarantinedReimbursement => "QuarantinedReimbursement",
+            IcpToEvmStatus::Successful => "Successful",
+            IcpToEvmStatus::Failed => "Failed",
+            IcpToEvmStatus::Expired => "Expired",
+        })
+    }
+}
+
+impl CertifiedValue for IcpToEvmReimbursementReason {
+    fn to_certified_value(&self) -> ICRC3Value {
+        unit_variant(match self {
+            IcpToEvmReimbursementReason::FailedTransaction => "FailedTransaction",
+            IcpToEvmReimbursementReason::FailedErc20Burn => "FailedErc20Burn",
+            IcpToEvmReimbursementReason::FailedIcrcLock => "FailedIcrcLock",
+        })
+    }
+}
+
+impl CertifiedValue for CandidIcpToEvmReimbursement {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidIcpToEvmReimbursement {
+            reason,
+            reimbursed_amount,
+            ledger_id,
+            reimbursed_in_block,
+        } = self;
+
+        Record::default()
+            .field("reason", reason)
+            .field("reimbursed_amount", reimbursed_amount)
+            .optional_field("ledger_id", ledger_id)
+            .optional_field("reimbursed_in_block", reimbursed_in_block)
+            .build()
+    }
+}
+
+impl CertifiedValue for CandidIcpToEvm {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidIcpToEvm {
+            transaction_hash,
+            native_ledger_burn_index,
+            withdrawal_amount,
+            actual_received,
+            destination,
+            from,
+            from_subaccount,
+            time,
+            max_transaction_fee,
+            effective_gas_price,
+            gas_used,
+            total_gas_spent,
+            erc20_ledger_burn_index,
+            erc20_contract_address,
+            icrc_ledger_id,
+            verified,
+            status,
+            operator,
+            chain_id,
+            reimbursement,
+        } = self;
+
+        Record::default()
+            .optional_field("transaction_hash", transaction_hash)
+            .field("native_ledger_burn_index", native_ledger_burn_index)
+            .field("withdrawal_amount", withdrawal_amount)
+            .optional_field("actual_received", actual_received)
+            .field("destination", destination)
+            .field("from", from)
+            .optional_field("from_subaccount", from_subaccount)
+            .field("time", time)
+            .optional_field("max_transaction_fee", max_transaction_fee)
+            .optional_field("effective_gas_price", effective_gas_price)
+            .optional_field("gas_used", gas_used)
+            .optional_field("total_gas_spent", total_gas_spent)
+            .optional_field("erc20_ledger_burn_index", erc20_ledger_burn_index)
+            .field("erc20_contract_address", erc20_contract_address)
+            .optional_field("icrc_ledger_id", icrc_ledger_id)
+            .field("verified", verified)
+            .field("status", status)
+            .field("operator", operator)
+            .field("chain_id", chain_id)
+            .optional_field("reimbursement", reimbursement)
+            .build()
+    }
+}
+
+impl CertifiedValue for CandidPoolId {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidPoolId {
+            token0,
+            token1,
+            fee,
+        } = self;
+
+        Record::default()
+            .field("token0", token0)
+            .field("token1", token1)
+            .field("fee", fee)
+            .build()
+    }
+}
+
+impl CertifiedValue for CandidPositionKey {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidPositionKey {
+            owner,
+            pool_id,
+            tick_lower,
+            tick_upper,
+        } = self;
+
+        Record::default()
+            .field("owner", owner)
+            .field("pool_id", pool_id)
+            .field("tick_lower", tick_lower)
+            .field("tick_upper", tick_upper)
+            .build()
+    }
+}
+
+impl CertifiedValue for CandidSwapType {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            CandidSwapType::ExactOutput(pool_ids) => {
+                variant("ExactOutput", pool_ids.to_certified_value())
+            }
+            CandidSwapType::ExactInput(pool_ids) => {
+                variant("ExactInput", pool_ids.to_certified_value())
+            }
+            CandidSwapType::ExactOutputSingle(pool_id) => {
+                variant("ExactOutputSingle", pool_id.to_certified_value())
+            }
+            CandidSwapType::ExactInputSingle(pool_id) => {
+                variant("ExactInputSingle", pool_id.to_certified_value())
+            }
+        }
+    }
+}
+
+impl CertifiedValue for CandidEvmToIcpTxIdentifier {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidEvmToIcpTxIdentifier {
+            transaction_hash,
+            chain_id,
+        } = self;
+
+        Record::default()
+            .field("transaction_hash", transaction_hash)
+            .field("chain_id", chain_id)
+            .build()
+    }
+}
+
+impl CertifiedValue for CandidDexAction {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            CandidDexAction::CreatedPool {
+                token0,
+                token1,
+                pool_fee,
+                timestamp,
+                dex_id,
+            } => variant(
+                "CreatedPool",
+                Record::default()
+                    .field("token0", token0)
+                    .field("token1", token1)
+                    .field("pool_fee", pool_fee)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::MintedPosition {
+                created_position,
+                liquidity,
+                amount0_paid,
+                amount1_paid,
+                timestamp,
+                dex_id,
+            } => variant(
+                "MintedPosition",
+                Record::default()
+                    .field("created_position", created_position)
+                    .field("liquidity", liquidity)
+                    .field("amount0_paid", amount0_paid)
+                    .field("amount1_paid", amount1_paid)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::IncreasedLiquidity {
+                modified_position,
+                liquidity_delta,
+                amount0_paid,
+                amount1_paid,
+                timestamp,
+                dex_id,
+            } => variant(
+                "IncreasedLiquidity",
+                Record::default()
+                    .field("modified_position", modified_position)
+                    .field("liquidity_delta", liquidity_delta)
+                    .field("amount0_paid", amount0_paid)
+                    .field("amount1_paid", amount1_paid)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::BurntPosition {
+                burnt_position,
+                liquidity,
+                amount0_received,
+                amount1_received,
+                timestamp,
+                dex_id,
+            } => variant(
+                "BurntPosition",
+                Record::default()
+                    .field("burnt_position", burnt_position)
+                    .field("liquidity", liquidity)
+                    .field("amount0_received", amount0_received)
+                    .field("amount1_received", amount1_received)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::DecreasedLiquidity {
+                modified_position,
+                liquidity_delta,
+                amount0_received,
+                amount1_received,
+                timestamp,
+                dex_id,
+            } => variant(
+                "DecreasedLiquidity",
+                Record::default()
+                    .field("modified_position", modified_position)
+                    .field("liquidity_delta", liquidity_delta)
+                    .field("amount0_received", amount0_received)
+                    .field("amount1_received", amount1_received)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::CollectedFees {
+                position,
+                amount0_collected,
+                amount1_collected,
+                timestamp,
+                dex_id,
+            } => variant(
+                "CollectedFees",
+                Record::default()
+                    .field("position", position)
+                    .field("amount0_collected", amount0_collected)
+                    .field("amount1_collected", amount1_collected)
+                    .field("timestamp", timestamp)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+            CandidDexAction::Swap {
+                final_amount_in,
+                final_amount_out,
+                swap_type,
+                timestamp,
+                token_in,
+                token_out,
+                funded_by,
+                dex_id,
+            } => variant(
+                "Swap",
+                Record::default()
+                    .field("final_amount_in", final_amount_in)
+                    .field("final_amount_out", final_amount_out)
+                    .field("swap_type", swap_type)
+                    .field("timestamp", timestamp)
+                    .field("token_in", token_in)
+                    .field("token_out", token_out)
+                    .optional_field("funded_by", funded_by)
+                    .optional_field("dex_id", dex_id)
+                    .build(),
+            ),
+        }
+    }
+}
+
+impl CertifiedValue for CandidEvmToken {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidEvmToken {
+            chain_id,
+            erc20_contract_address,
+            name,
+            decimals,
+            symbol,
+            logo,
+            is_wrapped_icrc,
+            usd_price,
+            cmc_id,
+            volume_usd_24h,
+        } = self;
+
+        Record::default()
+            .field("chain_id", chain_id)
+            .field("erc20_contract_address", erc20_contract_address)
+            .field("name", name)
+            .field("decimals", decimals)
+            .field("symbol", symbol)
+            .field("logo", logo)
+            .field("is_wrapped_icrc", is_wrapped_icrc)
+            .optional_field("usd_price", usd_price)
+            .optional_field("cmc_id", cmc_id)
+            .optional_field("volume_usd_24h", volume_usd_24h)
+            .build()
+    }
+}
+
+impl CertifiedValue for IcpTokenType {
+    fn to_certified_value(&self) -> ICRC3Value {
+        match self {
+            IcpTokenType::ICRC1 => unit_variant("ICRC1"),
+            IcpTokenType::ICRC2 => unit_variant("ICRC2"),
+            IcpTokenType::ICRC3 => unit_variant("ICRC3"),
+            IcpTokenType::DIP20 => unit_variant("DIP20"),
+            IcpTokenType::Other(token_type) => variant("Other", token_type.to_certified_value()),
+        }
+    }
+}
+
+impl CertifiedValue for CandidIcpToken {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let CandidIcpToken {
+            ledger_id,
+            name,
+            decimals,
+            symbol,
+            token_type,
+            logo,
+            usd_price,
+            fee,
+            rank,
+            listed_on_appic_dex,
+            supported_standards,
+            verified,
+        } = self;
+
+        Record::default()
+            .field("ledger_id", ledger_id)
+            .field("name", name)
+            .field("decimals", decimals)
+            .field("symbol", symbol)
+            .field("token_type", token_type)
+            .field("logo", logo)
+            .field("usd_price", usd_price)
+            .field("fee", fee)
+            .optional_field("rank", rank)
+            .optional_field("listed_on_appic_dex", listed_on_appic_dex)
+            .optional_field("supported_standards", supported_standards)
+            .optional_field("verified", verified)
+            .build()
+    }
+}
+
+impl CertifiedValue for TokenPair {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let TokenPair {
+            evm_token,
+            icp_token,
+            operator,
+        } = self;
+
+        Record::default()
+            .field("evm_token", evm_token)
+            .field("icp_token", icp_token)
+            .field("operator", operator)
+            .build()
+    }
+}
+
+impl CertifiedValue for BridgeRoute {
+    fn to_certified_value(&self) -> ICRC3Value {
+        let BridgeRoute {
+            operator,
+            evm_token,
+            icp_token,
+            is_wrapped_icrc,
+            minter_id,
+            minter_enabled,
+            icp_to_evm_fee,
+        } = self;
+
+        Record::default()
+            .field("operator", operator)
+            .field("evm_token", evm_token)
+            .field("icp_token", icp_token)
+            .field("is_wrapped_icrc", is_wrapped_icrc)
+            .optional_field("minter_id", minter_id)
+            .field("minter_enabled", minter_enabled)
+            .optional_field("icp_to_evm_fee", icp_to_evm_fee)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optional_fields_are_left_out() {
+        let reimbursement = CandidIcpToEvmReimbursement {
+            reason: IcpToEvmReimbursementReason::FailedTransaction,
+            reimbursed_amount: Nat::from(10_u64),
+            ledger_id: None,
+            reimbursed_in_block: None,
+        };
+
+        assert_eq!(
+            reimbursement.to_certified_value(),
+            ICRC3Value::Map(BTreeMap::from([
+                (
+                    "reason".to_string(),
+                    ICRC3Value::Text("FailedTransaction".to_string())
+                ),
+                (
+                    "reimbursed_amount".to_string(),
+                    ICRC3Value::Nat(Nat::from(10_u64))
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_variants_with_payload_are_maps() {
+        assert_eq!(
+            EvmToIcpStatus::Invalid("Wrong amount".to_string()).to_certified_value(),
+            ICRC3Value::Map(BTreeMap::from([(
+                "Invalid".to_string(),
+                ICRC3Value::Text("Wrong amount".to_string())
+            )]))
+        );
+        assert_eq!(
+            EvmToIcpStatus::Minted.to_certified_value(),
+            ICRC3Value::Text("Minted".to_string())
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use ic_canister_log::log;
+
+use crate::{
+    guard::TimerGuard,
+    logs::INFO,
+    state::{mutate_state, read_state, types::StuckTx},
+};
+
+// Flags transactions that spent more time in a non-terminal status than the sla of their chain,
+// flagged transactions are removed from the stuck set once they move to another status
+pub fn detect_stuck_transactions() {
+    // Issue a timer guard
+    let _guard = match TimerGuard::new(crate::guard::TaskType::DetectStuckTransactions) {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    // The sla tracked transactions are incomplete until the indexes are rebuilt
+    if read_state(|s| s.is_rebuilding_indexes()) {
+        return;
+    }
+
+    let newly_stuck = mutate_state(|s| s.update_stuck_transactions(ic_cdk::api::time()));
+
+    for key in newly_stuck {
+        log!(
+            INFO,
+            "[Detect Stuck Tx] Transaction {:?} exceeded the sla of its current status",
+            key
+        );
+    }
+}
+
+pub fn exceeds_threshold(status_since: u64, threshold: u64, now: u64) -> bool {
+    now.saturating_sub(status_since) > threshold
+}
+
+// Stuck entry of a transaction that exceeded its threshold, the detection time is kept
+// as long as the transaction stays in the status it was detected in
+pub fn stuck_tx(
+    previous: Option<&StuckTx>,
+    status_since: u64,
+    threshold: u64,
+    now: u64,
+) -> StuckTx {
+    let detected_at = match previous {
+        Some(previous) if previous.status_since == status_since => previous.detected_at,
+        _ => now,
+    };
+
+    StuckTx {
+        status_since,
+        threshold,
+        detected_at,
+        last_checked_at: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::types::{EvmToIcpStatus, IcpToEvmStatus, SlaThresholds};
+
+    const HOUR: u64 = 60 * 60 * 1_000_000_000;
+
+    #[test]
+    fn test_sla_thresholds() {
+        let thresholds = SlaThresholds {
+            icp_to_evm_accepted: HOUR,
+            icp_to_evm_created: 2 * HOUR,
+            icp_to_evm_signed: 3 * HOUR,
+            evm_to_icp_accepted: 4 * HOUR,
+        };
+
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::Accepted),
+            Some(HOUR)
+        );
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::Created),
+            Some(2 * HOUR)
+        );
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::SignedTransaction),
+            Some(3 * HOUR)
+        );
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::ReplacedTransaction),
+            Some(3 * HOUR)
+        );
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::Successful),
+            None
+        );
+        assert_eq!(
+            thresholds.icp_to_evm_threshold(&IcpToEvmStatus::PendingVerification),
+            None
+        );
+
+        assert_eq!(
+            thresholds.evm_to_icp_threshold(&EvmToIcpStatus::Accepted),
+            Some(4 * HOUR)
+        );
+        assert_eq!(
+            thresholds.evm_to_icp_threshold(&EvmToIcpStatus::Minted),
+            None
+        );
+        assert_eq!(
+            thresholds.evm_to_icp_threshold(&EvmToIcpStatus::Invalid("reason".to_string())),
+            None
+        );
+
+        assert!(SlaThresholds::tracks_icp_to_evm(&IcpToEvmStatus::Created));
+        assert!(!SlaThresholds::tracks_icp_to_evm(&IcpToEvmStatus::Failed));
+        assert!(SlaThresholds::tracks_evm_to_icp(&EvmToIcpStatus::Accepted));
+        assert!(!SlaThresholds::tracks_evm_to_icp(&EvmToIcpStatus::Expired));
+    }
+
+    #[test]
+    fn test_stuck_and_resolved_transitions() {
+        let threshold = HOUR;
+
+        // Not stuck until the threshold is exceeded
+        assert!(!exceeds_threshold(0, threshold, HOUR));
+        assert!(exceeds_threshold(0, threshold, HOUR + 1));
+
+        // Newly stuck
+        let detected = stuck_tx(None, 0, threshold, 2 * HOUR);
+        assert_eq!(detected.detected_at, 2 * HOUR);
+        assert_eq!(detected.last_checked_at, 2 * HOUR);
+
+        // Still stuck in the same status, the detection time is kept across runs
+        let still_stuck = stuck_tx(Some(&detected), 0, threshold, 3 * HOUR);
+        assert_eq!(still_stuck.detected_at, 2 * HOUR);
+        assert_eq!(still_stuck.last_checked_at, 3 * HOUR);
+
+        // Resolved once the status changed, a later stuck status is detected again
+        let status_since = 3 * HOUR;
+        assert!(!exceeds_threshold(status_since, threshold, 4 * HOUR));
+        let stuck_again = stuck_tx(Some(&still_stuck), status_since, threshold, 5 * HOUR);
+        assert_eq!(stuck_again.detected_at, 5 * HOUR);
+        assert_eq!(stuck_again.status_since, status_since);
+    }
+}
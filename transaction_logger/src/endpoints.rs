@@ -1,17 +1,28 @@
 use std::str::FromStr;
 
 use crate::address::Address;
+use crate::state::blocklist::{BlockedToken, BlocklistEntry};
 use crate::state::dex::types::{DexAction, PoolId, PositionKey, SwapType};
 use crate::state::nat_to_u64;
+use crate::state::search::{SearchedToken, TokenSearchFilter};
 use crate::state::{
     checked_nat_to_erc20_amount, nat_to_u128,
     types::{
-        ChainId, Erc20TwinLedgerSuiteFee, Erc20TwinLedgerSuiteStatus, EvmToIcpStatus, EvmToIcpTx,
-        EvmToken, IcpToEvmStatus, IcpToEvmTx, IcpToken, IcpTokenType, Operator,
+        BridgePairChange, BridgePairChangeId, BridgePairChangeKind, ChainId, DexAdapterKind,
+        DexSource, Erc20TwinLedgerSuiteFee, Erc20TwinLedgerSuiteRequest,
+        Erc20TwinLedgerSuiteStatus, EvmToIcpStatus, EvmToIcpTx, EvmToIcpTxIdentifier, EvmToken,
+        EvmTokenMetadataUpdate, EvmTokenSource, IcpToEvmReimbursement, IcpToEvmReimbursementReason,
+        IcpToEvmStatus, IcpToEvmTx, IcpToken, IcpTokenType, LedgerAccount, LedgerReconciliation,
+        Operator, PendingBridgePair, PendingBridgePairKey, PendingBridgePairReason,
+        ReconciliationMismatch, ReconciliationStatus, Subscription, SubscriptionFilter,
+        TokenValidationConfig, TokenValidationFailure,
     },
 };
+use crate::submission_guard::SubmissionRejection;
 use candid::{CandidType, Deserialize, Int, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
+use serde_bytes::ByteBuf;
 
 #[derive(Debug, CandidType, Deserialize)]
 pub struct Icrc28TrustedOriginsResponse {
@@ -42,6 +53,19 @@ pub enum AddEvmToIcpTxError {
     ChainNotSupported,
     InvalidTokenContract,
     InvalidAddress,
+    UnauthorizedCaller,
+    RateLimited,
+    TooManyPendingTransactions,
+}
+
+impl From<SubmissionRejection> for AddEvmToIcpTxError {
+    fn from(value: SubmissionRejection) -> Self {
+        match value {
+            SubmissionRejection::UnauthorizedCaller => Self::UnauthorizedCaller,
+            SubmissionRejection::RateLimited => Self::RateLimited,
+            SubmissionRejection::TooManyPendingTransactions => Self::TooManyPendingTransactions,
+        }
+    }
 }
 
 // Transactions for icp to evm
@@ -67,6 +91,19 @@ pub enum AddIcpToEvmTxError {
     ChainNotSupported,
     InvalidDestination,
     InvalidTokenContract,
+    UnauthorizedCaller,
+    RateLimited,
+    TooManyPendingTransactions,
+}
+
+impl From<SubmissionRejection> for AddIcpToEvmTxError {
+    fn from(value: SubmissionRejection) -> Self {
+        match value {
+            SubmissionRejection::UnauthorizedCaller => Self::UnauthorizedCaller,
+            SubmissionRejection::RateLimited => Self::RateLimited,
+            SubmissionRejection::TooManyPendingTransactions => Self::TooManyPendingTransactions,
+        }
+    }
 }
 
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
@@ -75,6 +112,7 @@ pub struct MinterArgs {
     pub minter_id: Principal,
     pub operator: Operator,
     pub last_observed_event: Nat,
+    // Scraping starts from this event, 0 for a minter that was never scraped
     pub last_scraped_event: Nat,
 }
 
@@ -87,6 +125,73 @@ pub struct UpdateMinterArgs {
     pub last_scraped_event: Option<Nat>,
 }
 
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct UpdateTokenValidationConfig {
+    // Number of strikes after which an invalid icp token is soft disabled
+    pub max_strikes: Option<u32>,
+    // Seconds since the first failure after which a disabled icp token is removed
+    pub removal_grace_period_secs: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct UpdateUnverifiedTxConfig {
+    // Seconds since submission after which an unverified transaction expires
+    pub expiry_timeout_secs: Option<u64>,
+    // Seconds since expiry after which an expired transaction is removed
+    pub retention_period_secs: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct UpdateSlaThresholdsArgs {
+    pub chain_id: Nat,
+    // Seconds a transaction can spend in each status before it is flagged as stuck
+    pub icp_to_evm_accepted_secs: Option<u64>,
+    pub icp_to_evm_created_secs: Option<u64>,
+    // Applies to signed and replaced transactions
+    pub icp_to_evm_signed_secs: Option<u64>,
+    pub evm_to_icp_accepted_secs: Option<u64>,
+}
+
+// New dex canister to scrape, scraping starts from next_event_to_scrape (0 if not provided)
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct DexSourceArgs {
+    pub id: Principal,
+    pub adapter: DexAdapterKind,
+    pub label: Option<String>,
+    pub next_event_to_scrape: Option<Nat>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct UpdateDexSourceArgs {
+    pub id: Principal,
+    pub enabled: Option<bool>,
+    pub last_observed_event: Option<Nat>,
+    pub next_event_to_scrape: Option<Nat>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CandidDexSource {
+    pub id: Principal,
+    pub adapter: DexAdapterKind,
+    pub enabled: bool,
+    pub last_observed_event: u64,
+    pub next_event_to_scrape: u64,
+    pub label: Option<String>,
+}
+
+impl From<DexSource> for CandidDexSource {
+    fn from(value: DexSource) -> Self {
+        Self {
+            id: value.id,
+            adapter: value.adapter,
+            enabled: value.enabled,
+            last_observed_event: value.last_observed_event,
+            next_event_to_scrape: value.next_event_to_scrape,
+            label: value.label,
+        }
+    }
+}
+
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct InitArgs {
     pub minters: Vec<MinterArgs>,
@@ -98,6 +203,17 @@ pub struct UpgradeArg {
     pub update_minters: Option<Vec<UpdateMinterArgs>>,
     pub update_latest_observed_dex_event: Option<Nat>,
     pub update_latest_scraped_dex_event: Option<Nat>,
+    pub update_token_validation_config: Option<UpdateTokenValidationConfig>,
+    pub add_dex_sources: Option<Vec<DexSourceArgs>>,
+    pub update_dex_sources: Option<Vec<UpdateDexSourceArgs>>,
+    pub update_sla_thresholds: Option<Vec<UpdateSlaThresholdsArgs>>,
+    pub update_unverified_tx_config: Option<UpdateUnverifiedTxConfig>,
+    // Principals allowed to submit transactions on behalf of users
+    pub add_relayers: Option<Vec<Principal>>,
+    pub remove_relayers: Option<Vec<Principal>>,
+    // Canisters allowed to subscribe to transaction changes
+    pub add_subscribers: Option<Vec<Principal>>,
+    pub remove_subscribers: Option<Vec<Principal>>,
 }
 
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
@@ -113,6 +229,267 @@ pub enum Transaction {
     DexAction(CandidDexAction),
 }
 
+// Transaction that spent more time in its current status than the sla of its chain,
+// all times are in nanoseconds
+#[derive(CandidType, Deserialize, Debug, Clone, Hash, Eq, PartialEq)]
+pub struct CandidStuckTransaction {
+    // Last known state of the transaction
+    pub transaction: Transaction,
+    // Time of the last known event of the transaction
+    pub status_since: u64,
+    pub time_in_state: u64,
+    pub threshold: u64,
+    pub detected_at: u64,
+}
+
+impl From<LedgerAccount> for Account {
+    fn from(value: LedgerAccount) -> Self {
+        Self {
+            owner: value.owner,
+            subaccount: value.subaccount,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CandidReconciliationMismatch {
+    Operation { expected: String, found: String },
+    Amount { expected: Nat, found: Nat },
+    Account { expected: Account, found: Account },
+}
+
+impl From<ReconciliationMismatch> for CandidReconciliationMismatch {
+    fn from(value: ReconciliationMismatch) -> Self {
+        match value {
+            ReconciliationMismatch::Operation { expected, found } => Self::Operation {
+                expected: expected.kind().to_string(),
+                found,
+            },
+            ReconciliationMismatch::Amount { expected, found } => Self::Amount {
+                expected: expected.into(),
+                found: found.into(),
+            },
+            ReconciliationMismatch::Account { expected, found } => Self::Account {
+                expected: expected.into(),
+                found: found.into(),
+            },
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CandidReconciliationStatus {
+    Matched,
+    Mismatch(Vec<CandidReconciliationMismatch>),
+    BlockNotFound,
+}
+
+impl From<ReconciliationStatus> for CandidReconciliationStatus {
+    fn from(value: ReconciliationStatus) -> Self {
+        match value {
+            ReconciliationStatus::Matched => Self::Matched,
+            ReconciliationStatus::Mismatch(mismatches) => Self::Mismatch(
+                mismatches
+                    .into_iter()
+                    .map(CandidReconciliationMismatch::from)
+                    .collect(),
+            ),
+            ReconciliationStatus::BlockNotFound => Self::BlockNotFound,
+        }
+    }
+}
+
+// Result of checking a transaction against the block of its ledger
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CandidLedgerReconciliation {
+    pub transaction: Transaction,
+    pub ledger_id: Principal,
+    pub block_index: Nat,
+    pub status: CandidReconciliationStatus,
+    pub checked_at: u64,
+}
+
+impl CandidLedgerReconciliation {
+    pub fn new(transaction: Transaction, reconciliation: LedgerReconciliation) -> Self {
+        Self {
+            transaction,
+            ledger_id: reconciliation.expected.ledger_id,
+            block_index: reconciliation.expected.block_index.into(),
+            status: reconciliation.status.into(),
+            checked_at: reconciliation.checked_at,
+        }
+    }
+}
+
+// Certified responses carry the certificate of the canister, None if not called as a query,
+// and a cbor encoded witness of the certified tree described in certification.rs
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedTransaction {
+    pub transaction: Option<Transaction>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedTransactions {
+    pub transactions: Vec<Transaction>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedBridgePairs {
+    pub bridge_pairs: Vec<TokenPair>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedBridgeRoutes {
+    pub routes: Vec<BridgeRoute>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedEvmToken {
+    pub token: Option<CandidEvmToken>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedIcpToken {
+    pub token: Option<CandidIcpToken>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedIcpTokens {
+    pub tokens: Vec<CandidIcpToken>,
+    pub certificate: Option<ByteBuf>,
+    pub witness: ByteBuf,
+}
+
+// Subscriptions of canisters to transaction changes, the method is an update method of the
+// caller that takes a vec of TxNotification.
+// Empty principals or chain ids match every principal or chain, statuses are the ones notified.
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubscribeArgs {
+    pub method: String,
+    pub principals: Vec<Principal>,
+    pub chain_ids: Vec<Nat>,
+    pub evm_to_icp_statuses: Vec<EvmToIcpStatus>,
+    pub icp_to_evm_statuses: Vec<IcpToEvmStatus>,
+    pub dex_actions: bool,
+}
+
+impl SubscribeArgs {
+    pub fn into_subscription(self, subscribed_at: u64) -> Subscription {
+        Subscription {
+            method: self.method,
+            filter: SubscriptionFilter {
+                principals: self.principals,
+                chain_ids: self.chain_ids.iter().map(ChainId::from).collect(),
+                evm_to_icp_statuses: self.evm_to_icp_statuses,
+                icp_to_evm_statuses: self.icp_to_evm_statuses,
+                dex_actions: self.dex_actions,
+            },
+            subscribed_at,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SubscribeError {
+    CallerNotACanister,
+    InvalidMethod,
+    // The filter does not match any change
+    EmptyFilter,
+    TooManySubscriptions,
+    // The caller is not in the allowed subscribers
+    CallerNotAllowed,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CandidSubscription {
+    pub subscriber: Principal,
+    pub method: String,
+    pub principals: Vec<Principal>,
+    pub chain_ids: Vec<Nat>,
+    pub evm_to_icp_statuses: Vec<EvmToIcpStatus>,
+    pub icp_to_evm_statuses: Vec<IcpToEvmStatus>,
+    pub dex_actions: bool,
+    pub subscribed_at: u64,
+    pub queued_notifications: u64,
+}
+
+impl CandidSubscription {
+    pub fn new(
+        subscriber: Principal,
+        subscription: Subscription,
+        queued_notifications: u64,
+    ) -> Self {
+        let filter = subscription.filter;
+        Self {
+            subscriber,
+            method: subscription.method,
+            principals: filter.principals,
+            chain_ids: filter
+                .chain_ids
+                .into_iter()
+                .map(|chain_id| Nat::from(chain_id.0))
+                .collect(),
+            evm_to_icp_statuses: filter.evm_to_icp_statuses,
+            icp_to_evm_statuses: filter.icp_to_evm_statuses,
+            dex_actions: filter.dex_actions,
+            subscribed_at: subscription.subscribed_at,
+            queued_notifications,
+        }
+    }
+}
+
+// Change of a transaction delivered to subscribed canisters,
+// for dex actions the transaction is the new action
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxNotification {
+    pub id: u64,
+    pub transaction: Transaction,
+    pub timestamp: u64,
+}
+
+// Minicbor encoded entries of a state section, next_cursor is None once the section is exported
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateChunk {
+    pub entries: ByteBuf,
+    pub count: u64,
+    pub next_cursor: Option<ByteBuf>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ExportStateChunkError {
+    // The entry is larger than a chunk, size is the size of its key and value in bytes
+    EntryTooLarge { key: ByteBuf, size: u64 },
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ImportStateChunkError {
+    NotInMaintenanceMode,
+    InvalidChunk(String),
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    // Transactions waiting for their first or next check
+    pub pending: u64,
+    pub matched: u64,
+    pub mismatched: u64,
+    pub block_not_found: u64,
+    // Mismatched transactions and transactions whose block was not found
+    pub flagged: Vec<CandidLedgerReconciliation>,
+}
+
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct GetTxParams {
     pub chain_id: CandidChainId,
@@ -161,6 +538,28 @@ pub struct CandidIcpToEvm {
     pub status: IcpToEvmStatus,
     pub operator: Operator,
     pub chain_id: Nat,
+    pub reimbursement: Option<CandidIcpToEvmReimbursement>,
+}
+
+#[derive(
+    CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Hash,
+)]
+pub struct CandidIcpToEvmReimbursement {
+    pub reason: IcpToEvmReimbursementReason,
+    pub reimbursed_amount: Nat,
+    pub ledger_id: Option<Principal>,
+    pub reimbursed_in_block: Option<Nat>,
+}
+
+impl From<IcpToEvmReimbursement> for CandidIcpToEvmReimbursement {
+    fn from(value: IcpToEvmReimbursement) -> Self {
+        Self {
+            reason: value.reason,
+            reimbursed_amount: value.reimbursed_amount.into(),
+            ledger_id: value.ledger_id,
+            reimbursed_in_block: value.reimbursed_in_block.map(|index| index.into()),
+        }
+    }
 }
 
 impl From<IcpToEvmTx> for CandidIcpToEvm {
@@ -185,6 +584,8 @@ impl From<IcpToEvmTx> for CandidIcpToEvm {
             status,
             operator,
             chain_id,
+            reimbursement,
+            status_updated_at: _,
         } = value;
 
         Self {
@@ -209,6 +610,7 @@ impl From<IcpToEvmTx> for CandidIcpToEvm {
             status,
             operator,
             chain_id: Nat::from(chain_id),
+            reimbursement: reimbursement.map(CandidIcpToEvmReimbursement::from),
         }
     }
 }
@@ -254,6 +656,7 @@ impl From<EvmToIcpTx> for CandidEvmToIcp {
             time,
             operator,
             ledger_mint_index,
+            status_updated_at: _,
         } = value;
         Self {
             from_address: from_address.to_string(),
@@ -340,6 +743,55 @@ impl From<CandidEvmToken> for EvmToken {
             usd_price: value.usd_price,
             cmc_id: value.cmc_id.map(|id| nat_to_u64(&id)),
             volume_usd_24h: value.volume_usd_24h,
+            runtime_managed_fields: None,
+            source: Some(EvmTokenSource::Runtime),
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct SearchTokensArgs {
+    // symbol, name, contract address or ledger id
+    pub query: String,
+    pub filter: Option<TokenSearchFilter>,
+    // Defaults to DEFAULT_SEARCH_RESULTS, capped at MAX_SEARCH_RESULTS
+    pub limit: Option<u32>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CandidSearchedToken {
+    Evm(CandidEvmToken),
+    Icp(CandidIcpToken),
+}
+
+impl From<SearchedToken> for CandidSearchedToken {
+    fn from(value: SearchedToken) -> Self {
+        match value {
+            SearchedToken::Evm(token) => Self::Evm(token.into()),
+            SearchedToken::Icp(token) => Self::Icp(token.into()),
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct UpdateEvmTokenMetadataArgs {
+    pub chain_id: CandidChainId,
+    pub address: String,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub logo: Option<String>,
+    pub cmc_id: Option<Nat>,
+}
+
+impl From<UpdateEvmTokenMetadataArgs> for EvmTokenMetadataUpdate {
+    fn from(value: UpdateEvmTokenMetadataArgs) -> Self {
+        Self {
+            name: value.name,
+            symbol: value.symbol,
+            decimals: value.decimals,
+            logo: value.logo,
+            cmc_id: value.cmc_id.map(|id| nat_to_u64(&id)),
         }
     }
 }
@@ -361,6 +813,8 @@ pub struct CandidIcpToken {
     pub fee: Nat,
     pub rank: Option<u32>,
     pub listed_on_appic_dex: Option<bool>,
+    pub supported_standards: Option<Vec<String>>,
+    pub verified: Option<bool>,
 }
 
 impl From<IcpToken> for CandidIcpToken {
@@ -384,10 +838,60 @@ impl From<IcpToken> for CandidIcpToken {
             fee: value.fee.into(),
             rank: value.rank,
             listed_on_appic_dex: value.listed_on_appic_dex,
+            supported_standards: value.supported_standards,
+            verified: value.verified,
         }
     }
 }
 
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CandidBlocklistEntry {
+    pub token: BlockedToken,
+    pub reason: Option<String>,
+    pub added_at: u64,
+}
+
+impl From<(BlockedToken, BlocklistEntry)> for CandidBlocklistEntry {
+    fn from((token, entry): (BlockedToken, BlocklistEntry)) -> Self {
+        Self {
+            token,
+            reason: entry.reason,
+            added_at: entry.added_at,
+        }
+    }
+}
+
+// Icp token that failed its last validations and might get disabled or removed
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CandidIcpTokenAtRisk {
+    pub ledger_id: Principal,
+    pub symbol: String,
+    pub strikes: u32,
+    pub first_failed_at: u64,
+    pub last_failed_at: u64,
+    pub last_failure: TokenValidationFailure,
+    pub disabled: bool,
+    // Time after which the token gets removed if it is disabled and keeps failing
+    pub removal_time: u64,
+}
+
+impl CandidIcpTokenAtRisk {
+    pub fn from_icp_token(token: IcpToken, config: &TokenValidationConfig) -> Option<Self> {
+        let failures = token.validation_failures?;
+
+        Some(Self {
+            ledger_id: token.ledger_id,
+            symbol: token.symbol,
+            strikes: failures.strikes,
+            first_failed_at: failures.first_failed_at,
+            last_failed_at: failures.last_failed_at,
+            removal_time: failures.removal_time(config),
+            last_failure: failures.last_failure,
+            disabled: failures.disabled,
+        })
+    }
+}
+
 impl From<CandidIcpToken> for IcpToken {
     fn from(value: CandidIcpToken) -> Self {
         Self {
@@ -401,6 +905,9 @@ impl From<CandidIcpToken> for IcpToken {
             fee: checked_nat_to_erc20_amount(value.fee).unwrap(),
             rank: value.rank,
             listed_on_appic_dex: value.listed_on_appic_dex,
+            supported_standards: value.supported_standards,
+            validation_failures: None,
+            verified: value.verified,
         }
     }
 }
@@ -412,6 +919,46 @@ pub struct TokenPair {
     pub operator: Operator,
 }
 
+// A way of bridging a token pair, there can be multiple routes for the same token
+// e.g. through the DFINITY ckERC20 minter and the Appic twin token minter
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct BridgeRoute {
+    pub operator: Operator,
+    pub evm_token: CandidEvmToken,
+    pub icp_token: CandidIcpToken,
+    pub is_wrapped_icrc: bool,
+    // None if no minter is registered for the chain and operator
+    pub minter_id: Option<Principal>,
+    pub minter_enabled: bool,
+    pub icp_to_evm_fee: Option<Nat>,
+}
+
+// Amount in the native token of the chain, and its value in usd if the price is known
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CandidFeeAmount {
+    pub amount: Nat,
+    pub usd: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CandidMinterFee {
+    pub operator: Operator,
+    pub minter_id: Principal,
+    pub enabled: bool,
+    pub icp_to_evm_fee: CandidFeeAmount,
+}
+
+// Gas fees are computed from the most recent finalized withdrawals of the token
+// None if no withdrawal was finalized yet
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct WithdrawalCostEstimate {
+    pub sample_size: u64,
+    pub median_gas_fee: Option<CandidFeeAmount>,
+    pub p90_gas_fee: Option<CandidFeeAmount>,
+    pub max_gas_fee: Option<CandidFeeAmount>,
+    pub minter_fees: Vec<CandidMinterFee>,
+}
+
 #[derive(Clone, CandidType, PartialEq, Eq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
 pub enum CandidErc20TwinLedgerSuiteStatus {
     PendingApproval,
@@ -462,6 +1009,102 @@ impl From<Erc20TwinLedgerSuiteFee> for CandidErc20TwinLedgerSuiteFee {
     }
 }
 
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CandidBridgePairChange {
+    pub kind: BridgePairChangeKind,
+    pub operator: Operator,
+    pub chain_id: Nat,
+    pub erc20_contract_address: String,
+    pub icp_ledger_id: Principal,
+    // Time of the event that changed the pair
+    pub timestamp: u64,
+    // Time the change was recorded, used as `since` to poll the next changes
+    pub recorded_at: u64,
+}
+
+impl CandidBridgePairChange {
+    pub fn new(id: BridgePairChangeId, change: BridgePairChange) -> Self {
+        Self {
+            kind: change.kind,
+            operator: change.operator,
+            chain_id: Nat::from(change.erc20_identifier.chain_id()),
+            erc20_contract_address: change.erc20_identifier.erc20_address().to_string(),
+            icp_ledger_id: change.icp_ledger_id,
+            timestamp: change.timestamp,
+            recorded_at: id.0,
+        }
+    }
+}
+
+// Bridge pair that is not recorded yet because of missing token metadata
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CandidPendingBridgePair {
+    pub operator: Operator,
+    pub chain_id: Nat,
+    pub erc20_contract_address: String,
+    pub icp_ledger_id: Principal,
+    pub reason: PendingBridgePairReason,
+    pub first_seen_at: u64,
+    pub last_seen_at: u64,
+    pub last_error: Option<String>,
+}
+
+impl From<(PendingBridgePairKey, PendingBridgePair)> for CandidPendingBridgePair {
+    fn from((key, pending_pair): (PendingBridgePairKey, PendingBridgePair)) -> Self {
+        let PendingBridgePairKey(operator, erc20_identifier) = key;
+
+        Self {
+            operator,
+            chain_id: Nat::from(erc20_identifier.chain_id()),
+            erc20_contract_address: erc20_identifier.erc20_address().to_string(),
+            icp_ledger_id: pending_pair.icp_ledger_id,
+            reason: pending_pair.reason,
+            first_seen_at: pending_pair.first_seen_at,
+            last_seen_at: pending_pair.last_seen_at,
+            last_error: pending_pair.last_error,
+        }
+    }
+}
+
+// Submitted by the ledger suite manager once the creation fee is paid
+#[derive(Clone, CandidType, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct AddErc20TwinLedgerSuiteRequest {
+    pub creator: Principal,
+    pub chain_id: Nat,
+    pub erc20_contract_address: String,
+    pub icp_token_symbol: String,
+    pub fee_charged: CandidErc20TwinLedgerSuiteFee,
+}
+
+#[derive(Clone, CandidType, PartialEq, Eq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
+pub struct CandidErc20TwinLedgerSuiteRequest {
+    pub creator: Option<Principal>,
+    pub chain_id: Nat,
+    pub erc20_contract_address: String,
+    pub icp_token_symbol: Option<String>,
+    pub status: CandidErc20TwinLedgerSuiteStatus,
+    pub fee_charged: Option<CandidErc20TwinLedgerSuiteFee>,
+    pub icp_ledger_id: Option<Principal>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl From<Erc20TwinLedgerSuiteRequest> for CandidErc20TwinLedgerSuiteRequest {
+    fn from(value: Erc20TwinLedgerSuiteRequest) -> Self {
+        Self {
+            creator: value.creator,
+            chain_id: Nat::from(value.evm_token_chain_id),
+            erc20_contract_address: value.evm_token_contract.to_string(),
+            icp_token_symbol: value.icp_token_symbol,
+            status: value.status.into(),
+            fee_charged: value.fee_charged.map(CandidErc20TwinLedgerSuiteFee::from),
+            icp_ledger_id: value.icp_ledger_id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
 #[derive(CandidType, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub struct CandidPoolId {
     pub token0: Principal, // Token0 identifier
@@ -485,6 +1128,22 @@ pub enum CandidSwapType {
     ExactInputSingle(CandidPoolId),
 }
 
+// Identifies the evm_to_icp transaction that funded a swap
+#[derive(CandidType, Hash, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CandidEvmToIcpTxIdentifier {
+    pub transaction_hash: String,
+    pub chain_id: Nat,
+}
+
+impl From<EvmToIcpTxIdentifier> for CandidEvmToIcpTxIdentifier {
+    fn from(value: EvmToIcpTxIdentifier) -> Self {
+        Self {
+            transaction_hash: value.0,
+            chain_id: value.1.into(),
+        }
+    }
+}
+
 /// The event describing the  minter state transition.
 #[derive(CandidType, Hash, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum CandidDexAction {
@@ -493,6 +1152,7 @@ pub enum CandidDexAction {
         token1: Principal,
         pool_fee: u32,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     MintedPosition {
         created_position: CandidPositionKey,
@@ -500,6 +1160,7 @@ pub enum CandidDexAction {
         amount0_paid: Nat,
         amount1_paid: Nat,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     IncreasedLiquidity {
         modified_position: CandidPositionKey,
@@ -507,6 +1168,7 @@ pub enum CandidDexAction {
         amount0_paid: Nat,
         amount1_paid: Nat,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     BurntPosition {
         burnt_position: CandidPositionKey,
@@ -514,6 +1176,7 @@ pub enum CandidDexAction {
         amount0_received: Nat,
         amount1_received: Nat,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     DecreasedLiquidity {
         modified_position: CandidPositionKey,
@@ -521,12 +1184,14 @@ pub enum CandidDexAction {
         amount0_received: Nat,
         amount1_received: Nat,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     CollectedFees {
         position: CandidPositionKey,
         amount0_collected: Nat,
         amount1_collected: Nat,
         timestamp: u64,
+        dex_id: Option<Principal>,
     },
     Swap {
         final_amount_in: Nat,
@@ -535,6 +1200,8 @@ pub enum CandidDexAction {
         timestamp: u64,
         token_in: Principal,
         token_out: Principal,
+        funded_by: Option<CandidEvmToIcpTxIdentifier>,
+        dex_id: Option<Principal>,
     },
 }
 
@@ -591,11 +1258,13 @@ impl From<DexAction> for CandidDexAction {
                 token1,
                 pool_fee,
                 timestamp,
+                dex_id,
             } => CandidDexAction::CreatedPool {
                 token0,
                 token1,
                 pool_fee,
                 timestamp,
+                dex_id,
             },
             DexAction::MintedPosition {
                 created_position,
@@ -603,12 +1272,14 @@ impl From<DexAction> for CandidDexAction {
                 amount0_paid,
                 amount1_paid,
                 timestamp,
+                dex_id,
             } => CandidDexAction::MintedPosition {
                 created_position: created_position.into(),
                 liquidity: liquidity.into(),
                 amount0_paid: amount0_paid.into(),
                 amount1_paid: amount1_paid.into(),
                 timestamp,
+                dex_id,
             },
             DexAction::IncreasedLiquidity {
                 modified_position,
@@ -616,12 +1287,14 @@ impl From<DexAction> for CandidDexAction {
                 amount0_paid,
                 amount1_paid,
                 timestamp,
+                dex_id,
             } => CandidDexAction::IncreasedLiquidity {
                 modified_position: modified_position.into(),
                 liquidity_delta: liquidity_delta.into(),
                 amount0_paid: amount0_paid.into(),
                 amount1_paid: amount1_paid.into(),
                 timestamp,
+                dex_id,
             },
             DexAction::BurntPosition {
                 burnt_position,
@@ -629,12 +1302,14 @@ impl From<DexAction> for CandidDexAction {
                 amount0_received,
                 amount1_received,
                 timestamp,
+                dex_id,
             } => CandidDexAction::BurntPosition {
                 burnt_position: burnt_position.into(),
                 liquidity: liquidity.into(),
                 amount0_received: amount0_received.into(),
                 amount1_received: amount1_received.into(),
                 timestamp,
+                dex_id,
             },
             DexAction::DecreasedLiquidity {
                 modified_position,
@@ -642,23 +1317,27 @@ impl From<DexAction> for CandidDexAction {
                 amount0_received,
                 amount1_received,
                 timestamp,
+                dex_id,
             } => CandidDexAction::DecreasedLiquidity {
                 modified_position: modified_position.into(),
                 liquidity_delta: liquidity_delta.into(),
                 amount0_received: amount0_received.into(),
                 amount1_received: amount1_received.into(),
                 timestamp,
+                dex_id,
             },
             DexAction::CollectedFees {
                 position,
                 amount0_collected,
                 amount1_collected,
                 timestamp,
+                dex_id,
             } => CandidDexAction::CollectedFees {
                 position: position.into(),
                 amount0_collected: amount0_collected.into(),
                 amount1_collected: amount1_collected.into(),
                 timestamp,
+                dex_id,
             },
             DexAction::Swap {
                 final_amount_in,
@@ -667,6 +1346,8 @@ impl From<DexAction> for CandidDexAction {
                 timestamp,
                 token_in,
                 token_out,
+                funded_by,
+                dex_id,
             } => CandidDexAction::Swap {
                 final_amount_in: final_amount_in.into(),
                 final_amount_out: final_amount_out.into(),
@@ -674,6 +1355,8 @@ impl From<DexAction> for CandidDexAction {
                 timestamp,
                 token_in,
                 token_out,
+                funded_by: funded_by.map(CandidEvmToIcpTxIdentifier::from),
+                dex_id,
             },
         }
     }
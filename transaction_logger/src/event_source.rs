@@ -0,0 +1,378 @@
+// Event scraping shared by the minter and dex scrapers.
+// Every source exposes its events as a log indexed from 0 and keeps its own cursors
+// (last observed event and next event to scrape). The engine fetches new events in batches,
+// applies them, backs off a failing source and keeps track of the health of every source.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use candid::{CandidType, Deserialize};
+use ic_canister_log::log;
+
+use crate::logs::{DEBUG, INFO};
+use crate::minter_client::CallError;
+
+pub const DEFAULT_BATCH_SIZE: u64 = 100;
+
+// Maximum number of batches fetched from a single source in one tick,
+// the remaining events are scraped in the next ticks
+pub const MAX_BATCHES_PER_TICK: u64 = 10;
+
+// 1 Minute, same as the scraping interval
+pub const BASE_BACKOFF_NS: u64 = 60 * 1_000_000_000;
+
+// 1 Hour
+pub const MAX_BACKOFF_NS: u64 = 60 * 60 * 1_000_000_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventCursor {
+    pub last_observed_event: u64,
+    // Every event before it is already applied, 0 if nothing was scraped yet
+    pub next_event_to_scrape: u64,
+}
+
+#[async_trait]
+pub trait EventSource {
+    type Page: Debug + Send;
+
+    // Unique name of the source, used for logs and health reporting
+    fn name(&self) -> String;
+
+    fn batch_size(&self) -> u64 {
+        DEFAULT_BATCH_SIZE
+    }
+
+    // Used for backoff and health reporting
+    fn now(&self) -> u64 {
+        ic_cdk::api::time()
+    }
+
+    // Cursors stored in state, None if the source does not exist anymore
+    fn cursor(&self) -> Option<EventCursor>;
+
+    fn set_last_observed_event(&self, event: u64);
+
+    fn set_next_event_to_scrape(&self, event: u64);
+
+    async fn total_events_count(&self) -> Result<u64, CallError>;
+
+    async fn fetch_page(&self, start: u64, length: u64) -> Result<Self::Page, CallError>;
+
+    fn apply_page(&self, page: Self::Page);
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceHealth {
+    pub source: String,
+    pub last_observed_event: u64,
+    pub next_event_to_scrape: u64,
+    pub consecutive_failures: u32,
+    pub last_success_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<u64>,
+    // The source is skipped until this time after failures
+    pub retry_after: Option<u64>,
+}
+
+thread_local! {
+    static SOURCES_HEALTH: RefCell<BTreeMap<String, SourceHealth>> = RefCell::default();
+}
+
+pub fn get_sources_health() -> Vec<SourceHealth> {
+    SOURCES_HEALTH.with(|health| health.borrow().values().cloned().collect())
+}
+
+fn mutate_source_health<F>(source: &str, f: F)
+where
+    F: FnOnce(&mut SourceHealth),
+{
+    SOURCES_HEALTH.with(|health| {
+        let mut health = health.borrow_mut();
+        let source_health = health
+            .entry(source.to_string())
+            .or_insert_with(|| SourceHealth {
+                source: source.to_string(),
+                ..Default::default()
+            });
+        f(source_health)
+    })
+}
+
+fn is_backing_off(source: &str, now: u64) -> bool {
+    SOURCES_HEALTH.with(|health| {
+        health
+            .borrow()
+            .get(source)
+            .and_then(|source_health| source_health.retry_after)
+            .is_some_and(|retry_after| now < retry_after)
+    })
+}
+
+fn record_success(source: &str, cursor: EventCursor, now: u64) {
+    mutate_source_health(source, |health| {
+        health.last_observed_event = cursor.last_observed_event;
+        health.next_event_to_scrape = cursor.next_event_to_scrape;
+        health.consecutive_failures = 0;
+        health.last_success_at = Some(now);
+        health.retry_after = None;
+    })
+}
+
+fn record_failure(source: &str, error: &CallError, now: u64) {
+    mutate_source_health(source, |health| {
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        health.last_error = Some(error.to_string());
+        health.last_error_at = Some(now);
+        health.retry_after = Some(now.saturating_add(backoff_delay(health.consecutive_failures)));
+    })
+}
+
+// Exponential backoff starting at BASE_BACKOFF_NS and capped at MAX_BACKOFF_NS
+pub fn backoff_delay(consecutive_failures: u32) -> u64 {
+    if consecutive_failures == 0 {
+        return 0;
+    }
+
+    let exponent = (consecutive_failures - 1).min(16);
+    BASE_BACKOFF_NS
+        .saturating_mul(1_u64 << exponent)
+        .min(MAX_BACKOFF_NS)
+}
+
+// Range of the next batch, both ends included
+pub fn next_batch(start: u64, latest_event: u64, batch_size: u64) -> Option<(u64, u64)> {
+    if start > latest_event || batch_size == 0 {
+        return None;
+    }
+
+    Some((
+        start,
+        std::cmp::min(start.saturating_add(batch_size - 1), latest_event),
+    ))
+}
+
+pub async fn scrape_source<S: EventSource + Sync>(source: &S) {
+    let name = source.name();
+
+    if is_backing_off(&name, source.now()) {
+        log!(
+            DEBUG,
+            "[Scraping Events] Skipping {} after repeated failures",
+            name
+        );
+        return;
+    }
+
+    let Some(cursor) = source.cursor() else {
+        return;
+    };
+
+    let total_events_count = match source.total_events_count().await {
+        Ok(total_events_count) => total_events_count,
+        Err(err) => {
+            log!(
+                DEBUG,
+                "[Scraping Events] Failed to get the events count of {}: {:?}",
+                name,
+                err
+            );
+            record_failure(&name, &err, source.now());
+            return;
+        }
+    };
+
+    // -1 since the starting index in 0 not 1
+    let Some(latest_event) = total_events_count.checked_sub(1) else {
+        record_success(&name, cursor, source.now());
+        return;
+    };
+
+    let last_observed_event = cursor.last_observed_event.max(latest_event);
+    if last_observed_event > cursor.last_observed_event {
+        source.set_last_observed_event(last_observed_event);
+    }
+
+    let mut next_event_to_scrape = cursor.next_event_to_scrape;
+
+    for _ in 0..MAX_BATCHES_PER_TICK {
+        let Some((batch_start, batch_end)) =
+            next_batch(next_event_to_scrape, latest_event, source.batch_size())
+        else {
+            break;
+        };
+
+        log!(
+            INFO,
+            "[Scraping Events] Scraping events from {} to {} of {}",
+            batch_start,
+            batch_end,
+            name
+        );
+
+        match source
+            .fetch_page(batch_start, batch_end - batch_start + 1)
+            .await
+        {
+            Ok(page) => {
+                log!(INFO, "[Scraping Events] Received Event {:?}", page);
+
+                source.apply_page(page);
+                next_event_to_scrape = batch_end + 1;
+                source.set_next_event_to_scrape(next_event_to_scrape);
+            }
+            Err(err) => {
+                log!(
+                    DEBUG,
+                    "[Scraping Events] Error scraping events from {} to {} of {}: {:?}",
+                    batch_start,
+                    batch_end,
+                    name,
+                    err
+                );
+                record_failure(&name, &err, source.now());
+                return;
+            }
+        }
+    }
+
+    record_success(
+        &name,
+        EventCursor {
+            last_observed_event,
+            next_event_to_scrape,
+        },
+        source.now(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0), 0);
+        assert_eq!(backoff_delay(1), BASE_BACKOFF_NS);
+        assert_eq!(backoff_delay(2), 2 * BASE_BACKOFF_NS);
+        assert_eq!(backoff_delay(3), 4 * BASE_BACKOFF_NS);
+        assert_eq!(backoff_delay(10), MAX_BACKOFF_NS);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF_NS);
+    }
+
+    #[test]
+    fn test_next_batch() {
+        assert_eq!(next_batch(0, 250, 100), Some((0, 99)));
+        assert_eq!(next_batch(200, 250, 100), Some((200, 250)));
+        assert_eq!(next_batch(250, 250, 100), Some((250, 250)));
+        assert_eq!(next_batch(251, 250, 100), None);
+        assert_eq!(next_batch(0, 250, 0), None);
+    }
+
+    struct MockSource {
+        total_events: u64,
+        cursor: Mutex<EventCursor>,
+        fetched_pages: Mutex<Vec<(u64, u64)>>,
+        applied_events: Mutex<Vec<u64>>,
+    }
+
+    impl MockSource {
+        fn new(total_events: u64, cursor: EventCursor) -> Self {
+            Self {
+                total_events,
+                cursor: Mutex::new(cursor),
+                fetched_pages: Mutex::default(),
+                applied_events: Mutex::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EventSource for MockSource {
+        type Page = Vec<u64>;
+
+        fn name(&self) -> String {
+            "mock".to_string()
+        }
+
+        fn batch_size(&self) -> u64 {
+            2
+        }
+
+        fn now(&self) -> u64 {
+            0
+        }
+
+        fn cursor(&self) -> Option<EventCursor> {
+            Some(*self.cursor.lock().unwrap())
+        }
+
+        fn set_last_observed_event(&self, event: u64) {
+            self.cursor.lock().unwrap().last_observed_event = event;
+        }
+
+        fn set_next_event_to_scrape(&self, event: u64) {
+            self.cursor.lock().unwrap().next_event_to_scrape = event;
+        }
+
+        async fn total_events_count(&self) -> Result<u64, CallError> {
+            Ok(self.total_events)
+        }
+
+        async fn fetch_page(&self, start: u64, length: u64) -> Result<Vec<u64>, CallError> {
+            self.fetched_pages.lock().unwrap().push((start, length));
+            Ok((start..start + length).collect())
+        }
+
+        fn apply_page(&self, page: Vec<u64>) {
+            self.applied_events.lock().unwrap().extend(page);
+        }
+    }
+
+    #[test]
+    fn test_fresh_cursor_scrapes_first_event() {
+        let source = MockSource::new(
+            3,
+            EventCursor {
+                last_observed_event: 0,
+                next_event_to_scrape: 0,
+            },
+        );
+
+        futures::executor::block_on(scrape_source(&source));
+
+        assert_eq!(*source.fetched_pages.lock().unwrap(), vec![(0, 2), (2, 1)]);
+        assert_eq!(*source.applied_events.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(
+            source.cursor(),
+            Some(EventCursor {
+                last_observed_event: 2,
+                next_event_to_scrape: 3,
+            })
+        );
+
+        // Nothing left once the latest event is scraped
+        futures::executor::block_on(scrape_source(&source));
+        assert_eq!(source.fetched_pages.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_resume_from_next_event_to_scrape() {
+        let source = MockSource::new(
+            5,
+            EventCursor {
+                last_observed_event: 4,
+                next_event_to_scrape: 3,
+            },
+        );
+
+        futures::executor::block_on(scrape_source(&source));
+
+        assert_eq!(*source.fetched_pages.lock().unwrap(), vec![(3, 2)]);
+        assert_eq!(*source.applied_events.lock().unwrap(), vec![3, 4]);
+        assert_eq!(source.cursor().unwrap().next_event_to_scrape, 5);
+    }
+}
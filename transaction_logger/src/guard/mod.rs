@@ -2,6 +2,8 @@ use std::cell::RefCell;
 
 use serde::{Deserialize, Serialize};
 
+use crate::state::read_state;
+
 use std::collections::HashSet;
 
 #[derive(Clone, PartialEq, Hash, Debug, PartialOrd, Eq, Ord, Deserialize, Serialize, Copy)]
@@ -13,6 +15,10 @@ pub enum TaskType {
     RemoveInvalidTokens,
     UpdateUsdPrice,
     ScrapeDexEvents,
+    DetectStuckTransactions,
+    ReconcileLedgers,
+    RebuildIndexes,
+    ReloadCertifiedTree,
 }
 
 thread_local! {
@@ -40,10 +46,16 @@ pub struct TimerGuard {
 #[derive(Debug, PartialEq, Eq)]
 pub enum TimerGuardError {
     AlreadyProcessing,
+    MaintenanceMode,
 }
 
 impl TimerGuard {
     pub fn new(task: TaskType) -> Result<Self, TimerGuardError> {
+        // Timers are paused while the state is being imported
+        if read_state(|s| s.is_in_maintenance_mode()) {
+            return Err(TimerGuardError::MaintenanceMode);
+        }
+
         mutate_active_tasks(|active_tasks| {
             if !active_tasks.insert(task) {
                 return Err(TimerGuardError::AlreadyProcessing);
@@ -5,6 +5,7 @@ use std::collections::{HashMap, HashSet};
 
 use candid::{CandidType, Principal};
 use ethnum::U256;
+use futures::future::join_all;
 use ic_canister_log::log;
 use icp_swap_token_type::TokensListResult;
 use icp_swap_usd_node_types::PublicTokenOverview;
@@ -30,6 +31,13 @@ pub enum MetadataValue {
     Text(String),
 }
 
+// Record returned by icrc1_supported_standards
+#[derive(CandidType, Deserialize, Debug)]
+pub struct StandardRecord {
+    pub name: String,
+    pub url: String,
+}
+
 mod icp_swap_token_type;
 mod icp_swap_usd_node_types;
 
@@ -42,8 +50,11 @@ const APPIC_DEX_CANISTER_ID: &str = "nbepk-iyaaa-aaaad-qhlma-cai";
 
 const CKUSDC_LEDGER_ID: &str = "xevnm-gaaaa-aaaar-qafnq-cai";
 
-pub struct TokenService {
-    runtime: IcRunTime,
+// Maximum number of tokens validated at the same time
+pub const MAX_CONCURRENT_VALIDATIONS: usize = 20;
+
+pub struct TokenService<R: Runtime = IcRunTime> {
+    runtime: R,
 }
 
 impl Default for TokenService {
@@ -54,9 +65,19 @@ impl Default for TokenService {
 
 impl TokenService {
     pub fn new() -> Self {
-        Self {
-            runtime: IcRunTime {},
-        }
+        Self::with_runtime(IcRunTime {})
+    }
+}
+
+impl<R: Runtime> TokenService<R> {
+    // Ledgers are called through the given runtime, so that they can be mocked in tests
+    pub fn with_runtime(runtime: R) -> Self {
+        Self { runtime }
+    }
+
+    #[cfg(test)]
+    pub fn runtime(&self) -> &R {
+        &self.runtime
     }
 
     pub async fn get_appic_dex_tokens(&self) -> Vec<IcpToken> {
@@ -84,29 +105,20 @@ impl TokenService {
             }
         };
 
-        // Filter the tokens that already exist in the state
-        unique_tokens.retain(|token| read_state(|s| s.get_icp_token_by_principal(token).is_none()));
-
-        let mut validated_icp_tokens = Vec::new();
-
-        for ledger_id in unique_tokens.iter() {
-            match self
-                .validate_token(*ledger_id, Some(1_u32), Some(true))
-                .await
-            {
-                Ok(token) => validated_icp_tokens.push(token),
-                Err(e) => {
-                    log!(
-                        INFO,
-                        "Failed To validate token {:?} for {}",
-                        ledger_id.to_text(),
-                        e
-                    );
-                }
-            }
-        }
-
-        validated_icp_tokens
+        // Filter the tokens that already exist in the state or are blocked
+        unique_tokens.retain(|token| {
+            read_state(|s| {
+                s.get_icp_token_by_principal(token).is_none() && !s.is_ledger_blocked(token)
+            })
+        });
+
+        self.collect_valid_tokens(
+            unique_tokens
+                .into_iter()
+                .map(|ledger_id| (ledger_id, Some(1_u32), Some(true)))
+                .collect(),
+        )
+        .await
     }
 
     pub async fn get_icp_swap_tokens(&self) -> Vec<IcpToken> {
@@ -152,14 +164,32 @@ impl TokenService {
             }
         };
 
-        //Filter the tokens that already exist in the state
-        unique_tokens.retain(|token| read_state(|s| s.get_icp_token_by_principal(token).is_none()));
-
-        let mut validated_icp_tokens = Vec::new();
+        //Filter the tokens that already exist in the state or are blocked
+        unique_tokens.retain(|token| {
+            read_state(|s| {
+                s.get_icp_token_by_principal(token).is_none() && !s.is_ledger_blocked(token)
+            })
+        });
+
+        self.collect_valid_tokens(
+            unique_tokens
+                .into_iter()
+                .map(|ledger_id| (ledger_id, None, None))
+                .collect(),
+        )
+        .await
+    }
 
-        for ledger_id in unique_tokens.iter() {
-            match self.validate_token(*ledger_id, None, None).await {
-                Ok(token) => validated_icp_tokens.push(token),
+    // Validates the given tokens and only returns the valid ones, invalid tokens are logged
+    async fn collect_valid_tokens(
+        &self,
+        tokens: Vec<(Principal, Option<u32>, Option<bool>)>,
+    ) -> Vec<IcpToken> {
+        self.validate_tokens_batch(tokens)
+            .await
+            .into_iter()
+            .filter_map(|(ledger_id, result)| match result {
+                Ok(token) => Some(token),
                 Err(e) => {
                     log!(
                         INFO,
@@ -167,11 +197,37 @@ impl TokenService {
                         ledger_id.to_text(),
                         e
                     );
+                    None
                 }
-            }
+            })
+            .collect()
+    }
+
+    // Validates a batch of tokens (ledger_id, rank, listed_on_appic_dex)
+    // Calls are made in parallel, at most MAX_CONCURRENT_VALIDATIONS at the same time
+    // Results are returned in the same order as the input
+    pub async fn validate_tokens_batch(
+        &self,
+        tokens: Vec<(Principal, Option<u32>, Option<bool>)>,
+    ) -> Vec<(Principal, Result<IcpToken, CallError>)> {
+        let mut results = Vec::with_capacity(tokens.len());
+
+        for chunk in tokens.chunks(MAX_CONCURRENT_VALIDATIONS) {
+            let chunk_results =
+                join_all(chunk.iter().map(|(ledger_id, rank, listed_on_appic_dex)| {
+                    self.validate_token(*ledger_id, *rank, *listed_on_appic_dex)
+                }))
+                .await;
+
+            results.extend(
+                chunk
+                    .iter()
+                    .map(|(ledger_id, _, _)| *ledger_id)
+                    .zip(chunk_results),
+            );
         }
 
-        validated_icp_tokens
+        results
     }
 
     // Validate tokens on icp
@@ -198,7 +254,8 @@ impl TokenService {
                 if let Ok(icp_token) =
                     convert_to_icp_token(ledger_id, metadata, rank, listed_on_appic_dex)
                 {
-                    Ok(icp_token)
+                    let supported_standards = self.get_supported_standards(ledger_id).await;
+                    Ok(with_supported_standards(icp_token, supported_standards))
                 } else {
                     Err(CallError {
                         method: "icrc1_metadata".to_string(),
@@ -212,6 +269,27 @@ impl TokenService {
         }
     }
 
+    // Gets the standards supported by a ledger through icrc1_supported_standards
+    // Returns None if the ledger does not expose them
+    pub async fn get_supported_standards(&self, ledger_id: Principal) -> Option<Vec<String>> {
+        match self
+            .runtime
+            .call_canister::<(), Vec<StandardRecord>>(ledger_id, "icrc1_supported_standards", ())
+            .await
+        {
+            Ok(standards) => Some(standards.into_iter().map(|record| record.name).collect()),
+            Err(e) => {
+                log!(
+                    INFO,
+                    "Failed To get supported standards for {:?} for {}",
+                    ledger_id.to_text(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
     pub async fn get_appic_dex_tokens_usd_price(
         &self,
     ) -> Result<Vec<(Principal, f64, bool)>, String> {
@@ -372,9 +450,48 @@ pub fn convert_to_icp_token(
         token_type: IcpTokenType::ICRC2,
         rank,
         listed_on_appic_dex,
+        supported_standards: None,
+        validation_failures: None,
+        verified: None,
     })
 }
 
+// Records the supported standards of a token and derives its token type from them.
+// If standards are unknown, the token is left untouched.
+pub fn with_supported_standards(
+    icp_token: IcpToken,
+    supported_standards: Option<Vec<String>>,
+) -> IcpToken {
+    match supported_standards {
+        Some(standards) => IcpToken {
+            token_type: token_type_from_standards(&standards),
+            supported_standards: Some(standards),
+            ..icp_token
+        },
+        None => icp_token,
+    }
+}
+
+// ICRC2 and ICRC3 are only reported when approve/transfer_from is available,
+// since that is what the swap flows rely on. Like ICPSwap, ICRC3 implies ICRC2.
+pub fn token_type_from_standards(standards: &[String]) -> IcpTokenType {
+    let supports = |standard: &str| {
+        standards
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(standard))
+    };
+
+    if supports("ICRC-2") && supports("ICRC-3") {
+        IcpTokenType::ICRC3
+    } else if supports("ICRC-2") {
+        IcpTokenType::ICRC2
+    } else if supports("ICRC-1") {
+        IcpTokenType::ICRC1
+    } else {
+        IcpTokenType::Other(standards.join(","))
+    }
+}
+
 pub fn big_uint_to_u256(biguint: BigUint) -> Result<U256, String> {
     let value_bytes = biguint.to_bytes_be();
     let mut value_u256 = [0u8; 32];
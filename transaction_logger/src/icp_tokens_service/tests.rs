@@ -1,7 +1,89 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
 use candid::{Int, Nat};
+use serde::de::DeserializeOwned;
 
 use super::*;
 
+// Runtime answering every (canister, method) with a candid encoded response,
+// calls without a response fail with a canister error
+#[derive(Default)]
+pub struct MockRuntime {
+    responses: BTreeMap<(Principal, String), Vec<u8>>,
+    calls: Mutex<Vec<(Principal, String)>>,
+}
+
+impl MockRuntime {
+    pub fn respond<T: CandidType>(&mut self, canister_id: Principal, method: &str, response: T) {
+        self.responses.insert(
+            (canister_id, method.to_string()),
+            candid::encode_one(response).unwrap(),
+        );
+    }
+
+    pub fn calls(&self, method: &str) -> Vec<Principal> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_canister_id, called)| called == method)
+            .map(|(canister_id, _called)| *canister_id)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Runtime for MockRuntime {
+    async fn call_canister<I, O>(
+        &self,
+        canister_id: Principal,
+        method: &str,
+        _args: I,
+    ) -> Result<O, CallError>
+    where
+        I: CandidType + Debug + Send + 'static,
+        O: CandidType + DeserializeOwned + Debug + 'static,
+    {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((canister_id, method.to_string()));
+
+        match self.responses.get(&(canister_id, method.to_string())) {
+            Some(response) => Ok(candid::decode_one(response).unwrap()),
+            None => Err(CallError {
+                method: method.to_string(),
+                reason: Reason::CanisterError("canister not found".to_string()),
+            }),
+        }
+    }
+}
+
+// Icrc1 metadata of a valid token
+pub fn token_metadata(symbol: &str) -> Vec<(String, MetadataValue)> {
+    vec![
+        (
+            "icrc1:name".to_string(),
+            MetadataValue::Text(format!("{} token", symbol)),
+        ),
+        (
+            "icrc1:symbol".to_string(),
+            MetadataValue::Text(symbol.to_string()),
+        ),
+        (
+            "icrc1:decimals".to_string(),
+            MetadataValue::Nat(Nat::from(8_u8)),
+        ),
+        (
+            "icrc1:fee".to_string(),
+            MetadataValue::Nat(Nat::from(10_000_u64)),
+        ),
+    ]
+}
+
 #[test]
 fn test_claculate_usd_price_based_on_ck_usdc() {
     // Setup pool_id
@@ -63,3 +145,122 @@ fn test_claculate_usd_price_based_on_ck_usdc() {
         usd_price
     );
 }
+
+#[test]
+fn test_token_type_from_standards() {
+    let standards = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        token_type_from_standards(&standards(&["ICRC-1", "ICRC-2", "ICRC-3"])),
+        IcpTokenType::ICRC3
+    );
+    assert_eq!(
+        token_type_from_standards(&standards(&["ICRC-1", "ICRC-2"])),
+        IcpTokenType::ICRC2
+    );
+    // Without approve/transfer_from the token can not be used like an ICRC2 token
+    assert_eq!(
+        token_type_from_standards(&standards(&["ICRC-1", "ICRC-3"])),
+        IcpTokenType::ICRC1
+    );
+    assert_eq!(
+        token_type_from_standards(&standards(&["ICRC-1"])),
+        IcpTokenType::ICRC1
+    );
+    assert_eq!(
+        token_type_from_standards(&standards(&["ICRC-7", "ICRC-37"])),
+        IcpTokenType::Other("ICRC-7,ICRC-37".to_string())
+    );
+}
+
+#[test]
+fn test_with_supported_standards() {
+    let metadata = vec![
+        (
+            "icrc1:name".to_string(),
+            MetadataValue::Text("Token".to_string()),
+        ),
+        (
+            "icrc1:decimals".to_string(),
+            MetadataValue::Nat(Nat::from(8_u8)),
+        ),
+        (
+            "icrc1:symbol".to_string(),
+            MetadataValue::Text("TKN".to_string()),
+        ),
+        (
+            "icrc1:fee".to_string(),
+            MetadataValue::Nat(Nat::from(10_000_u64)),
+        ),
+    ];
+    let ledger_id = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+    let token = convert_to_icp_token(ledger_id, metadata, None, None).unwrap();
+
+    // Unknown standards leave the token untouched
+    let unchanged = with_supported_standards(token.clone(), None);
+    assert_eq!(unchanged.token_type, IcpTokenType::ICRC2);
+    assert_eq!(unchanged.supported_standards, None);
+
+    let icrc1_only = with_supported_standards(token, Some(vec!["ICRC-1".to_string()]));
+    assert_eq!(icrc1_only.token_type, IcpTokenType::ICRC1);
+    assert_eq!(
+        icrc1_only.supported_standards,
+        Some(vec!["ICRC-1".to_string()])
+    );
+}
+
+#[test]
+fn test_validate_tokens_batch_keeps_input_order() {
+    // More tokens than the concurrent validations, every third ledger is not reachable
+    let ledger_ids: Vec<Principal> = (0..MAX_CONCURRENT_VALIDATIONS as u8 + 5)
+        .map(|i| Principal::from_slice(&[i, 1]))
+        .collect();
+
+    let mut runtime = MockRuntime::default();
+    for (i, ledger_id) in ledger_ids.iter().enumerate() {
+        if i % 3 != 0 {
+            runtime.respond(
+                *ledger_id,
+                "icrc1_metadata",
+                token_metadata(&format!("T{}", i)),
+            );
+        }
+    }
+
+    let service = TokenService::with_runtime(runtime);
+    // Reversed input, results follow the input and not the ledger order
+    let input: Vec<(Principal, Option<u32>, Option<bool>)> = ledger_ids
+        .iter()
+        .rev()
+        .map(|ledger_id| (*ledger_id, Some(1), None))
+        .collect();
+    let results = futures::executor::block_on(service.validate_tokens_batch(input.clone()));
+
+    assert_eq!(
+        results
+            .iter()
+            .map(|(ledger_id, _result)| *ledger_id)
+            .collect::<Vec<_>>(),
+        input
+            .iter()
+            .map(|(ledger_id, _, _)| *ledger_id)
+            .collect::<Vec<_>>()
+    );
+
+    for (ledger_id, result) in results {
+        let i = ledger_ids.iter().position(|id| *id == ledger_id).unwrap();
+        match result {
+            Ok(token) => {
+                assert_ne!(i % 3, 0);
+                assert_eq!(token.ledger_id, ledger_id);
+                assert_eq!(token.symbol, format!("T{}", i));
+            }
+            Err(_) => assert_eq!(i % 3, 0),
+        }
+    }
+}
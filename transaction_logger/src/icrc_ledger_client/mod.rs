@@ -0,0 +1,62 @@
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc3::transactions::{
+    GetTransactionsRequest, GetTransactionsResponse, Transaction, TransactionRange,
+};
+
+use crate::minter_client::{CallError, IcRunTime, Runtime};
+
+pub struct IcrcLedgerClient {
+    pub runtime: IcRunTime,
+    pub id: Principal,
+}
+
+impl IcrcLedgerClient {
+    pub fn new(id: Principal) -> Self {
+        Self {
+            runtime: IcRunTime(),
+            id,
+        }
+    }
+
+    // Gets a single transaction through get_transactions, following the archive callback
+    // if the block was moved to an archive. None if the ledger does not have the block yet
+    pub async fn get_transaction(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<Transaction>, CallError> {
+        let request = GetTransactionsRequest {
+            start: Nat::from(block_index),
+            length: Nat::from(1_u8),
+        };
+
+        let response = self
+            .runtime
+            .call_canister::<GetTransactionsRequest, GetTransactionsResponse>(
+                self.id,
+                "get_transactions",
+                request.clone(),
+            )
+            .await?;
+
+        if response.first_index <= Nat::from(block_index) {
+            if let Some(transaction) = response.transactions.into_iter().next() {
+                return Ok(Some(transaction));
+            }
+        }
+
+        match response.archived_transactions.into_iter().next() {
+            Some(archived) => Ok(self
+                .runtime
+                .call_canister::<GetTransactionsRequest, TransactionRange>(
+                    archived.callback.canister_id,
+                    &archived.callback.method,
+                    request,
+                )
+                .await?
+                .transactions
+                .into_iter()
+                .next()),
+            None => Ok(None),
+        }
+    }
+}
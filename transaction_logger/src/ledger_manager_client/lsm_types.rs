@@ -3,7 +3,7 @@
 use std::str::FromStr;
 
 use crate::address::Address;
-use crate::state::types::{ChainId as StateChainId, Erc20Identifier};
+use crate::state::types::{ChainId as StateChainId, Erc20Identifier, Erc20TwinLedgerSuiteStatus};
 use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
 
@@ -143,6 +143,28 @@ pub struct ManagedCanisters {
     pub archives: Vec<Principal>,
 }
 
+impl ManagedCanisters {
+    pub fn erc20_identifier(&self) -> Option<Erc20Identifier> {
+        Some(Erc20Identifier(
+            Address::from_str(&self.erc20_contract.address).ok()?,
+            StateChainId::from(&self.erc20_contract.chain_id),
+        ))
+    }
+
+    // The ledger suite follows the status of its ledger canister
+    pub fn twin_ledger_suite_status(&self) -> Erc20TwinLedgerSuiteStatus {
+        match self.ledger {
+            None => Erc20TwinLedgerSuiteStatus::PendingApproval,
+            Some(ManagedCanisterStatus::Created { .. }) => Erc20TwinLedgerSuiteStatus::Created,
+            Some(ManagedCanisterStatus::Installed { .. }) => Erc20TwinLedgerSuiteStatus::Installed,
+        }
+    }
+
+    pub fn ledger_id(&self) -> Option<Principal> {
+        self.ledger.clone().map(Principal::from)
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct LedgerSuiteVersion {
     pub ledger_compressed_wasm_hash: String,
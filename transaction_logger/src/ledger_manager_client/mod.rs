@@ -49,13 +49,17 @@ impl LsClient {
                 Ok(EvmIcpBridgePairs::from(result))
             }
             Operator::AppicMinter => {
-                let result = self
-                    .runtime
-                    .call_canister::<_, LedgerManagerInfo>(self.id, "get_lsm_info", ())
-                    .await?;
+                let result = self.get_lsm_info().await?;
 
                 Ok(EvmIcpBridgePairs::from(result))
             }
         }
     }
+
+    // Only supported by the appic ledger suite manager
+    pub async fn get_lsm_info(&self) -> Result<LedgerManagerInfo, crate::minter_client::CallError> {
+        self.runtime
+            .call_canister::<_, LedgerManagerInfo>(self.id, "get_lsm_info", ())
+            .await
+    }
 }
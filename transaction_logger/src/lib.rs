@@ -5,19 +5,27 @@ pub mod address;
 pub mod appic_dex_client;
 pub mod appic_dex_types;
 pub mod cbor;
+pub mod certification;
 pub mod checked_amount;
+pub mod detect_stuck_tx;
 pub mod endpoints;
+pub mod event_source;
 pub mod guard;
 pub mod icp_tokens_service;
+pub mod icrc_ledger_client;
 pub mod ledger_manager_client;
 pub mod lifecycle;
 pub mod logs;
 pub mod minter_client;
+pub mod notifications;
 pub mod numeric;
+pub mod rebuild_indexes;
+pub mod reconcile_ledgers;
 pub mod remove_unverified_tx;
 pub mod scrape_dex_events;
 pub mod scrape_events;
 pub mod state;
+pub mod submission_guard;
 pub mod update_bridge_pairs;
 pub mod update_icp_tokens;
 
@@ -30,8 +38,8 @@ pub const SCRAPE_EVENTS: Duration = Duration::from_secs(1 * 60);
 // 5 Minutes
 pub const UPDATE_USD_PRICE: Duration = Duration::from_secs(5 * 60);
 
-// 1 Day
-pub const REMOVE_UNVERIFIED_TX: Duration = Duration::from_secs(24 * 60 * 60);
+// 1 Hour, so that the configurable expiry timeout is applied with at most one hour of delay
+pub const REMOVE_UNVERIFIED_TX: Duration = Duration::from_secs(60 * 60);
 
 // 1 Day
 pub const UPDATE_BRIDGE_PAIRS: Duration = Duration::from_secs(24 * 60 * 60);
@@ -39,5 +47,11 @@ pub const UPDATE_BRIDGE_PAIRS: Duration = Duration::from_secs(24 * 60 * 60);
 // 1 Day
 pub const UPDATE_ICP_TOKENS: Duration = Duration::from_secs(24 * 60 * 60);
 
+// 10 Minutes
+pub const DETECT_STUCK_TX: Duration = Duration::from_secs(10 * 60);
+
 // 1 Week
 pub const REMOVE_INVALID_ICP_TOKENS: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// 30 Minutes
+pub const RECONCILE_LEDGERS: Duration = Duration::from_secs(30 * 60);
@@ -1,8 +1,11 @@
 use crate::endpoints::InitArgs;
 use crate::endpoints::UpgradeArg;
 use crate::logs::INFO;
-use crate::state::nat_to_u64;
-use crate::state::types::{ChainId, Minter, MinterKey};
+use crate::rebuild_indexes::{resume_indexes_rebuild, start_certified_tree_reload};
+use crate::state::types::{ChainId, DexSource, Minter, MinterKey};
+use crate::state::{nat_to_u64, DEX_CANISTER_ID};
+
+use candid::Principal;
 
 use crate::state::mutate_state;
 use ic_canister_log::log;
@@ -13,9 +16,17 @@ pub fn init(init_args: InitArgs) {
     for minter in minters_iter {
         mutate_state(|s| s.record_minter(minter));
     }
+
+    mutate_state(|s| s.migrate_dex_info());
+
+    resume_indexes_rebuild();
+
+    start_certified_tree_reload();
 }
 
 pub fn post_upgrade(upgrade_arg: Option<UpgradeArg>) {
+    mutate_state(|s| s.migrate_dex_info());
+
     if let Some(args) = upgrade_arg {
         log!(INFO, "[upgrade]: upgrading logger with arg: {:?}", args);
 
@@ -46,19 +57,163 @@ pub fn post_upgrade(upgrade_arg: Option<UpgradeArg>) {
                         s.update_last_observed_event(&minter_key, nat_to_u64(last_observed))
                     })
                 }
-                if let Some(last_scraped) = &update_minter_args.last_observed_event {
+                if let Some(last_scraped) = &update_minter_args.last_scraped_event {
                     mutate_state(|s| {
-                        s.update_last_scraped_event(&minter_key, nat_to_u64(last_scraped))
+                        s.update_next_event_to_scrape(&minter_key, nat_to_u64(last_scraped))
                     })
                 }
             }
         }
+        // Legacy args, applied to the default AppicDEX source
+        let default_dex_id = Principal::from_text(DEX_CANISTER_ID).unwrap();
         if let Some(latest_scraped_event) = args.update_latest_scraped_dex_event {
-            mutate_state(|s| s.update_last_scraped_dex_event(nat_to_u64(&latest_scraped_event)))
+            mutate_state(|s| {
+                s.update_next_dex_event_to_scrape(
+                    &default_dex_id,
+                    nat_to_u64(&latest_scraped_event),
+                )
+            })
         }
 
         if let Some(latest_observed_event) = args.update_latest_observed_dex_event {
-            mutate_state(|s| s.update_last_observed_dex_event(nat_to_u64(&latest_observed_event)))
+            mutate_state(|s| {
+                s.update_last_observed_dex_event(
+                    &default_dex_id,
+                    nat_to_u64(&latest_observed_event),
+                )
+            })
+        }
+
+        if let Some(new_dex_sources) = args.add_dex_sources {
+            for source in new_dex_sources {
+                log!(INFO, "[upgrade]: adding new dex source: {:?}", source);
+
+                let next_event_to_scrape = source
+                    .next_event_to_scrape
+                    .as_ref()
+                    .map(nat_to_u64)
+                    .unwrap_or_default();
+
+                let added = mutate_state(|s| {
+                    s.add_dex_source(DexSource {
+                        id: source.id,
+                        adapter: source.adapter,
+                        enabled: true,
+                        last_observed_event: next_event_to_scrape.saturating_sub(1),
+                        next_event_to_scrape,
+                        label: source.label,
+                    })
+                });
+
+                // Re-adding a source would reset its cursors and scrape its actions twice
+                if !added {
+                    panic!(
+                        "Dex source {} already exists, use update_dex_sources instead",
+                        source.id
+                    );
+                }
+            }
+        }
+
+        if let Some(update_dex_sources) = args.update_dex_sources {
+            for update in update_dex_sources {
+                log!(INFO, "[upgrade]: updating dex source: {:?}", update);
+
+                mutate_state(|s| {
+                    if let Some(enabled) = update.enabled {
+                        s.set_dex_source_enabled(&update.id, enabled);
+                    }
+                    if let Some(last_observed) = &update.last_observed_event {
+                        s.update_last_observed_dex_event(&update.id, nat_to_u64(last_observed));
+                    }
+                    if let Some(next_event) = &update.next_event_to_scrape {
+                        s.update_next_dex_event_to_scrape(&update.id, nat_to_u64(next_event));
+                    }
+                })
+            }
+        }
+
+        if let Some(validation_config) = args.update_token_validation_config {
+            mutate_state(|s| {
+                s.update_token_validation_config(
+                    validation_config.max_strikes,
+                    validation_config
+                        .removal_grace_period_secs
+                        .map(|secs| secs.saturating_mul(1_000_000_000)),
+                )
+            })
+        }
+
+        if let Some(update_sla_thresholds) = args.update_sla_thresholds {
+            for update in update_sla_thresholds {
+                log!(INFO, "[upgrade]: updating sla thresholds: {:?}", update);
+
+                let to_nanos = |secs: u64| secs.saturating_mul(1_000_000_000);
+                mutate_state(|s| {
+                    s.update_sla_thresholds(
+                        ChainId::from(&update.chain_id),
+                        update.icp_to_evm_accepted_secs.map(to_nanos),
+                        update.icp_to_evm_created_secs.map(to_nanos),
+                        update.icp_to_evm_signed_secs.map(to_nanos),
+                        update.evm_to_icp_accepted_secs.map(to_nanos),
+                    )
+                })
+            }
+        }
+
+        if let Some(unverified_tx_config) = args.update_unverified_tx_config {
+            mutate_state(|s| {
+                s.update_unverified_tx_config(
+                    unverified_tx_config
+                        .expiry_timeout_secs
+                        .map(|secs| secs.saturating_mul(1_000_000_000)),
+                    unverified_tx_config
+                        .retention_period_secs
+                        .map(|secs| secs.saturating_mul(1_000_000_000)),
+                )
+            })
+        }
+
+        if let Some(relayers) = args.add_relayers {
+            log!(INFO, "[upgrade]: adding relayers: {:?}", relayers);
+            let now = ic_cdk::api::time();
+            mutate_state(|s| {
+                for relayer in relayers {
+                    s.add_allowed_relayer(relayer, now);
+                }
+            })
+        }
+
+        if let Some(relayers) = args.remove_relayers {
+            log!(INFO, "[upgrade]: removing relayers: {:?}", relayers);
+            mutate_state(|s| {
+                for relayer in relayers {
+                    s.remove_allowed_relayer(&relayer);
+                }
+            })
+        }
+
+        if let Some(subscribers) = args.add_subscribers {
+            log!(INFO, "[upgrade]: adding subscribers: {:?}", subscribers);
+            let now = ic_cdk::api::time();
+            mutate_state(|s| {
+                for subscriber in subscribers {
+                    s.add_allowed_subscriber(subscriber, now);
+                }
+            })
+        }
+
+        if let Some(subscribers) = args.remove_subscribers {
+            log!(INFO, "[upgrade]: removing subscribers: {:?}", subscribers);
+            mutate_state(|s| {
+                for subscriber in subscribers {
+                    s.remove_allowed_subscriber(&subscriber);
+                }
+            })
         }
     }
+
+    resume_indexes_rebuild();
+
+    start_certified_tree_reload();
 }
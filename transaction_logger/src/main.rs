@@ -1,37 +1,73 @@
 use base64::{engine::general_purpose, Engine as _};
-use candid::Principal;
+use candid::{Nat, Principal};
 use ic_canister_log::log;
 use ic_cdk::{init, post_upgrade, query, update};
 use ic_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, ICRC3ArchiveInfo};
+use icrc_ledger_types::icrc3::blocks::{
+    BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType,
+};
+use serde_bytes::ByteBuf;
 use std::borrow::Borrow;
 use std::collections::HashSet;
 use std::str::FromStr;
 use std::time::Duration;
 use transaction_logger::add_evm_tokens::add_evm_tokens_to_state;
 use transaction_logger::address::Address;
+use transaction_logger::certification::{
+    certified_bridge_pairs, certified_bridge_routes, certified_bridge_routes_for_icrc,
+    evm_token_key, icp_token_key, icp_tokens_witness, is_reloading_leaves, tip_witness,
+    token_witness, transactions_witness, tx_key, CertifiedTxKind,
+};
 use transaction_logger::endpoints::{
-    AddEvmToIcpTx, AddEvmToIcpTxError, AddIcpToEvmTx, AddIcpToEvmTxError, CandidDexAction,
-    CandidEvmToken, CandidIcpToken, EvmSearchQuery, GetEvmTokenArgs, GetIcpTokenArgs, GetTxParams,
-    Icrc28TrustedOriginsResponse, MinterArgs, TokenPair, TopVolumeTokens, Transaction,
+    AddErc20TwinLedgerSuiteRequest, AddEvmToIcpTx, AddEvmToIcpTxError, AddIcpToEvmTx,
+    AddIcpToEvmTxError, CandidBlocklistEntry, CandidBridgePairChange, CandidDexAction,
+    CandidDexSource, CandidErc20TwinLedgerSuiteRequest, CandidEvmToken, CandidIcpToken,
+    CandidIcpTokenAtRisk, CandidPendingBridgePair, CandidSearchedToken, CandidStuckTransaction,
+    CandidSubscription, CertifiedBridgePairs, CertifiedBridgeRoutes, CertifiedEvmToken,
+    CertifiedIcpToken, CertifiedIcpTokens, CertifiedTransaction, CertifiedTransactions,
+    EvmSearchQuery, ExportStateChunkError, GetEvmTokenArgs, GetIcpTokenArgs, GetTxParams,
+    Icrc28TrustedOriginsResponse, ImportStateChunkError, MinterArgs, ReconciliationReport,
+    SearchTokensArgs, StateChunk, SubscribeArgs, SubscribeError, TopVolumeTokens, Transaction,
+    UpdateEvmTokenMetadataArgs, WithdrawalCostEstimate,
 };
+use transaction_logger::event_source::{get_sources_health, SourceHealth};
 use transaction_logger::guard::{TaskType, TimerGuard};
 use transaction_logger::lifecycle::{self, init as initialize};
+use transaction_logger::notifications::{
+    is_canister, queued_notifications, remove_queue, MAX_METHOD_NAME_LENGTH, MAX_SUBSCRIPTIONS,
+};
+use transaction_logger::rebuild_indexes::{
+    schedule_indexes_rebuild, start_certified_tree_reload, INDEXES_VERSION,
+};
 use transaction_logger::scrape_dex_events::scrape_dex_events;
 use transaction_logger::state::{
+    block_log::{supported_block_types, MAX_BLOCKS_PER_RESPONSE},
+    blocklist::{BlockedToken, BlocklistEntry},
+    checked_nat_to_u64,
+    export::{decode_chunk, encode_chunk, StateSection},
     mutate_state, nat_to_erc20_amount, nat_to_ledger_burn_index, read_state,
+    search::{DEFAULT_SEARCH_RESULTS, MAX_SEARCH_RESULTS},
     types::{
         ChainId, Erc20Identifier, EvmToIcpStatus, EvmToIcpTx, EvmToIcpTxIdentifier, EvmToken,
         IcpToEvmIdentifier, IcpToEvmStatus, IcpToEvmTx, IcpToken,
     },
 };
+use transaction_logger::submission_guard::{
+    check_submission, get_submission_stats as submission_stats, record_submission, SubmissionStats,
+};
 use transaction_logger::update_bridge_pairs::APPIC_LEDGER_MANAGER_ID;
 use transaction_logger::update_icp_tokens::{update_icp_tokens, update_usd_price, validate_tokens};
+use transaction_logger::{
+    detect_stuck_tx::detect_stuck_transactions, reconcile_ledgers::reconcile_ledgers,
+    DETECT_STUCK_TX, RECONCILE_LEDGERS, REMOVE_INVALID_ICP_TOKENS, UPDATE_ICP_TOKENS,
+    UPDATE_USD_PRICE,
+};
 use transaction_logger::{
     endpoints::LoggerArgs, logs::INFO, remove_unverified_tx::remove_unverified_tx,
     scrape_events::scrape_events, update_bridge_pairs::update_bridge_pairs, REMOVE_UNVERIFIED_TX,
     SCRAPE_EVENTS, UPDATE_BRIDGE_PAIRS,
 };
-use transaction_logger::{REMOVE_INVALID_ICP_TOKENS, UPDATE_ICP_TOKENS, UPDATE_USD_PRICE};
 
 const ADMIN_ID: &str = "tb3vi-54bcb-4oudm-fmp2s-nntjp-rmhd3-ukvnq-lawfq-vk5vy-mnlc7-pae";
 const DATA_PROVIDER_ID: &str = "o74ab-rm2co-uhvn6-6ec2d-3kkvk-bwlcw-356yj-lbma2-m4qew-l4ett-wae";
@@ -49,6 +85,12 @@ fn setup_timers() {
     // Remove unverified transactions
     ic_cdk_timers::set_timer_interval(REMOVE_UNVERIFIED_TX, remove_unverified_tx);
 
+    // Flag transactions that exceed their sla
+    ic_cdk_timers::set_timer_interval(DETECT_STUCK_TX, detect_stuck_transactions);
+
+    // Check minted deposits and withdrawal burns against the ledger blocks
+    ic_cdk_timers::set_timer_interval(RECONCILE_LEDGERS, || ic_cdk::spawn(reconcile_ledgers()));
+
     // Check new supported twin tokens
     ic_cdk_timers::set_timer_interval(UPDATE_BRIDGE_PAIRS, || ic_cdk::spawn(update_bridge_pairs()));
 
@@ -72,6 +114,19 @@ fn is_authorized_caller(caller: Principal) -> bool {
     caller == appic_ledger_manager_id || caller == admin_id || caller == appic_data_provider_id
 }
 
+// Relayers can submit transactions on behalf of users without rate limits
+fn is_relayer(caller: Principal) -> bool {
+    is_authorized_caller(caller) || read_state(|s| s.is_allowed_relayer(&caller))
+}
+
+// Guard of the update endpoints, the state only changes through imports in maintenance mode
+fn reject_in_maintenance_mode() -> Result<(), String> {
+    if read_state(|s| s.is_in_maintenance_mode()) {
+        return Err("The logger is in maintenance mode".to_string());
+    }
+    Ok(())
+}
+
 #[init]
 pub fn init(init_args: LoggerArgs) {
     match init_args {
@@ -123,15 +178,20 @@ fn post_upgrade(upgrade_args: Option<LoggerArgs>) {
         None => lifecycle::post_upgrade(None),
     }
 
-    //add_evm_tokens_to_state();
+    // Only applied if the bundled token list changed
+    add_evm_tokens_to_state();
 
     // Set up timers
     setup_timers();
 }
 
 // Add new icp to evm transaction
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 fn new_icp_to_evm_tx(tx: AddIcpToEvmTx) -> Result<(), AddIcpToEvmTxError> {
+    let caller = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    check_submission(caller, tx.from, is_relayer(caller), now)?;
+
     let tx_identifier = IcpToEvmIdentifier::from(&tx);
     let chain_id = ChainId::from(&tx.chain_id);
 
@@ -150,7 +210,7 @@ fn new_icp_to_evm_tx(tx: AddIcpToEvmTx) -> Result<(), AddIcpToEvmTxError> {
         .map_err(|_e| AddIcpToEvmTxError::InvalidTokenContract)?;
 
     let icrc_pair = read_state(|s| {
-        match s.get_icrc_twin_for_erc20(
+        match s.get_listed_icrc_twin_for_erc20(
             &Erc20Identifier::new(&erc20_contract_address, chain_id),
             &tx.operator,
         ) {
@@ -171,7 +231,7 @@ fn new_icp_to_evm_tx(tx: AddIcpToEvmTx) -> Result<(), AddIcpToEvmTxError> {
                 destination,
                 from: tx.from,
                 from_subaccount: tx.from_subaccount,
-                time: ic_cdk::api::time(),
+                time: now,
                 max_transaction_fee: Some(nat_to_erc20_amount(tx.max_transaction_fee)),
                 effective_gas_price: None,
                 gas_used: None,
@@ -183,16 +243,25 @@ fn new_icp_to_evm_tx(tx: AddIcpToEvmTx) -> Result<(), AddIcpToEvmTxError> {
                 status: IcpToEvmStatus::PendingVerification,
                 operator: tx.operator,
                 chain_id,
+                reimbursement: None,
+                status_updated_at: None,
             },
+            now,
         )
     });
 
+    record_submission(caller, now);
+
     Ok(())
 }
 
 // Add new evm to icp transaction
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 fn new_evm_to_icp_tx(tx: AddEvmToIcpTx) -> Result<(), AddEvmToIcpTxError> {
+    let caller = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    check_submission(caller, tx.principal, is_relayer(caller), now)?;
+
     let tx_identifier = EvmToIcpTxIdentifier::from(&tx);
     let chain_id = ChainId::from(&tx.chain_id);
 
@@ -211,7 +280,7 @@ fn new_evm_to_icp_tx(tx: AddEvmToIcpTx) -> Result<(), AddEvmToIcpTxError> {
         .map_err(|_e| AddEvmToIcpTxError::InvalidTokenContract)?;
 
     let icrc_pair = read_state(|s| {
-        match s.get_icrc_twin_for_erc20(
+        match s.get_listed_icrc_twin_for_erc20(
             &Erc20Identifier::new(&erc20_contract_address, chain_id),
             &tx.operator,
         ) {
@@ -228,7 +297,7 @@ fn new_evm_to_icp_tx(tx: AddEvmToIcpTx) -> Result<(), AddEvmToIcpTxError> {
             EvmToIcpTx {
                 transaction_hash: tx.transaction_hash,
                 actual_received: None,
-                time: ic_cdk::api::time(),
+                time: now,
                 erc20_contract_address,
                 icrc_ledger_id: Some(icrc_pair),
                 ledger_mint_index: None,
@@ -242,29 +311,80 @@ fn new_evm_to_icp_tx(tx: AddEvmToIcpTx) -> Result<(), AddEvmToIcpTxError> {
                 subaccount: tx.subaccount,
                 chain_id,
                 total_gas_spent: Some(nat_to_erc20_amount(tx.total_gas_spent)),
+                status_updated_at: None,
             },
+            now,
         )
     });
 
+    record_submission(caller, now);
+
     Ok(())
 }
 
+// Key of a bridge transaction in the certified tree
+fn certified_tx_key(transaction: &Transaction) -> Option<Vec<u8>> {
+    match transaction {
+        Transaction::EvmToIcp(tx) => Some(tx_key(
+            &tx.principal,
+            CertifiedTxKind::EvmToIcp,
+            &EvmToIcpTxIdentifier::new(&tx.transaction_hash, ChainId::from(&tx.chain_id)),
+        )),
+        Transaction::IcpToEvm(tx) => Some(tx_key(
+            &tx.from,
+            CertifiedTxKind::IcpToEvm,
+            &IcpToEvmIdentifier::new(
+                nat_to_ledger_burn_index(&tx.native_ledger_burn_index),
+                ChainId::from(&tx.chain_id),
+            ),
+        )),
+        Transaction::DexAction(_) => None,
+    }
+}
+
+// Certificate of the transactions, None while the certified transactions are rebuilt or reloaded
+// since their witnesses would be incomplete
+fn transactions_certificate() -> Option<ByteBuf> {
+    if is_reloading_leaves() || read_state(|s| s.is_rebuilding_indexes()) {
+        return None;
+    }
+    ic_cdk::api::data_certificate().map(ByteBuf::from)
+}
+
+// Transactions with a witness of each of them and, if a principal is given,
+// of all the transactions of the principal
+fn certified_transactions(
+    transactions: Vec<Transaction>,
+    principal: Option<&Principal>,
+) -> CertifiedTransactions {
+    let keys: Vec<Vec<u8>> = transactions.iter().filter_map(certified_tx_key).collect();
+
+    CertifiedTransactions {
+        transactions,
+        certificate: transactions_certificate(),
+        witness: ByteBuf::from(transactions_witness(principal, &keys)),
+    }
+}
+
 #[query]
-pub fn get_txs_by_address(address: String) -> Vec<Transaction> {
+pub fn get_txs_by_address(address: String) -> CertifiedTransactions {
     let address = Address::from_str(&address).expect("Address should be valid");
-    read_state(|s| s.get_transaction_for_address(address))
+    certified_transactions(read_state(|s| s.get_transaction_for_address(address)), None)
 }
 
 #[query]
-pub fn get_txs_by_principal(principal_id: Principal) -> Vec<Transaction> {
-    read_state(|s| s.get_transaction_for_principal(principal_id))
+pub fn get_txs_by_principal(principal_id: Principal) -> CertifiedTransactions {
+    certified_transactions(
+        read_state(|s| s.get_transaction_for_principal(principal_id)),
+        Some(&principal_id),
+    )
 }
 
 #[query]
 pub fn get_txs_by_address_principal_combination(
     address: String,
     principal_id: Principal,
-) -> Vec<Transaction> {
+) -> CertifiedTransactions {
     let address = Address::from_str(&address).expect("Address should be valid");
 
     // get transactions by address and principal
@@ -281,28 +401,91 @@ pub fn get_txs_by_address_principal_combination(
     });
 
     // Convert the HashSet back to a Vec and return
-    unique_txs_set.into_iter().collect()
+    certified_transactions(unique_txs_set.into_iter().collect(), Some(&principal_id))
+}
+
+#[query]
+// Bridge pairs as of their last certification, usd prices are certified every 5 minutes
+pub fn get_bridge_pairs() -> CertifiedBridgePairs {
+    let (bridge_pairs, witness) = certified_bridge_pairs();
+
+    CertifiedBridgePairs {
+        bridge_pairs,
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(witness),
+    }
+}
+
+#[query]
+// Routes as of their last certification, same as the bridge pairs
+pub fn get_bridge_routes(chain_id: Nat, erc20_address: String) -> CertifiedBridgeRoutes {
+    let identifier = Erc20Identifier::new(
+        &Address::from_str(&erc20_address).expect("Wrong Address Provided"),
+        ChainId::from(&chain_id),
+    );
+    let (routes, witness) = certified_bridge_routes(&identifier);
+
+    CertifiedBridgeRoutes {
+        routes,
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(witness),
+    }
+}
+
+#[query]
+// Added and removed bridge pairs recorded since the given timestamp (in nanoseconds), oldest first
+pub fn get_bridge_pair_changes(since: u64) -> Vec<CandidBridgePairChange> {
+    read_state(|s| s.get_bridge_pair_changes(since))
+        .into_iter()
+        .map(|(id, change)| CandidBridgePairChange::new(id, change))
+        .collect()
+}
+
+#[query]
+pub fn get_bridge_routes_for_icrc(ledger_id: Principal) -> CertifiedBridgeRoutes {
+    let (routes, witness) = certified_bridge_routes_for_icrc(&ledger_id);
+
+    CertifiedBridgeRoutes {
+        routes,
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(witness),
+    }
 }
+
 #[query]
-pub fn get_bridge_pairs() -> Vec<TokenPair> {
-    read_state(|s| s.get_supported_bridge_pairs())
+pub fn estimate_withdrawal_cost(chain_id: Nat, erc20_address: String) -> WithdrawalCostEstimate {
+    let identifier = Erc20Identifier::new(
+        &Address::from_str(&erc20_address).expect("Wrong Address Provided"),
+        ChainId::from(&chain_id),
+    );
+
+    read_state(|s| s.estimate_withdrawal_cost(&identifier))
 }
 
 #[query]
-pub fn get_transaction(params: GetTxParams) -> Option<Transaction> {
+// Missing transactions and dex actions are not certified
+pub fn get_transaction(params: GetTxParams) -> CertifiedTransaction {
     // Check if chain id is supported
     let chain_id = ChainId::from(&params.chain_id);
     let chain_check_result = read_state(|s| s.if_chain_id_exists(chain_id));
 
-    if !chain_check_result {
-        return None;
-    }
+    let transaction = if chain_check_result {
+        read_state(|s| s.get_transaction_by_search_params(params.search_param, chain_id))
+    } else {
+        None
+    };
+    let keys: Vec<Vec<u8>> = transaction.iter().filter_map(certified_tx_key).collect();
 
-    read_state(|s| s.get_transaction_by_search_params(params.search_param, chain_id))
+    CertifiedTransaction {
+        transaction,
+        certificate: transactions_certificate(),
+        witness: ByteBuf::from(transactions_witness(None, &keys)),
+    }
 }
 
 #[query]
-pub fn get_evm_token(args: GetEvmTokenArgs) -> Option<CandidEvmToken> {
+// Missing tokens are certified by an absence proof
+pub fn get_evm_token(args: GetEvmTokenArgs) -> CertifiedEvmToken {
     // Validate address and create identifier
     let identifier = Erc20Identifier::new(
         &Address::from_str(&args.address).expect("Wrong Address Provided"),
@@ -310,22 +493,29 @@ pub fn get_evm_token(args: GetEvmTokenArgs) -> Option<CandidEvmToken> {
     );
 
     // Get token from state
-    let token = read_state(|s| s.get_evm_token_by_identifier(&identifier))?;
+    let token = read_state(|s| s.get_evm_token_by_identifier(&identifier));
 
-    // Return Token
-    Some(CandidEvmToken::from(token))
+    CertifiedEvmToken {
+        token: token.map(CandidEvmToken::from),
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(token_witness(&evm_token_key(&identifier))),
+    }
 }
 
 #[query]
-pub fn get_icp_token(args: GetIcpTokenArgs) -> Option<CandidIcpToken> {
+// Missing tokens are certified by an absence proof
+pub fn get_icp_token(args: GetIcpTokenArgs) -> CertifiedIcpToken {
     // Get token from state
-    let token = read_state(|s| s.get_icp_token_by_principal(&args.ledger_id))?;
+    let token = read_state(|s| s.get_icp_token_by_principal(&args.ledger_id));
 
-    // Return Token
-    Some(CandidIcpToken::from(token))
+    CertifiedIcpToken {
+        token: token.map(CandidIcpToken::from),
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(token_witness(&icp_token_key(&args.ledger_id))),
+    }
 }
 
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 // Can only be called by lsm
 pub fn add_icp_token(token: CandidIcpToken) {
     if !is_authorized_caller(ic_cdk::caller()) {
@@ -333,10 +523,111 @@ pub fn add_icp_token(token: CandidIcpToken) {
     }
 
     let token: IcpToken = token.into();
-    mutate_state(|s| s.record_icp_token(token.ledger_id, token))
+
+    // Keep the curated verified flag if it is not provided
+    let verified = token.verified.or_else(|| {
+        read_state(|s| s.get_icp_token_by_principal(&token.ledger_id)).and_then(|t| t.verified)
+    });
+
+    mutate_state(|s| {
+        s.record_icp_token(
+            token.ledger_id,
+            IcpToken { verified, ..token },
+            ic_cdk::api::time(),
+        )
+    })
 }
 
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by lsm
+pub fn new_twin_ls_request(request: AddErc20TwinLedgerSuiteRequest) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can record twin ledger suite requests")
+    }
+
+    let identifier = Erc20Identifier::new(
+        &Address::from_str(&request.erc20_contract_address).expect("Wrong Address Provided"),
+        ChainId::from(&request.chain_id),
+    );
+
+    mutate_state(|s| {
+        s.record_twin_ledger_request(
+            identifier,
+            request.creator,
+            request.icp_token_symbol,
+            request.fee_charged.into(),
+            ic_cdk::api::time(),
+        )
+    })
+}
+
+#[query]
+// Returns all the twin ledger suite requests, or only the ones submitted by the creator
+pub fn get_twin_ledger_requests(
+    creator: Option<Principal>,
+) -> Vec<CandidErc20TwinLedgerSuiteRequest> {
+    read_state(|s| s.get_twin_ledger_requests(creator))
+        .into_iter()
+        .map(CandidErc20TwinLedgerSuiteRequest::from)
+        .collect()
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admins, marks a token as verified by curators
+pub fn set_icp_token_verified(ledger_id: Principal, verified: bool) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change icp tokens details")
+    }
+
+    if !mutate_state(|s| s.set_icp_token_verified(&ledger_id, verified, ic_cdk::api::time())) {
+        panic!("Token not found")
+    }
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admins
+pub fn add_to_icp_token_blocklist(token: BlockedToken, reason: Option<String>) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change the icp token blocklist")
+    }
+
+    let now = ic_cdk::api::time();
+    mutate_state(|s| {
+        s.add_to_icp_token_blocklist(
+            token,
+            BlocklistEntry {
+                reason,
+                added_at: now,
+            },
+            now,
+        )
+    })
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admins
+pub fn remove_from_icp_token_blocklist(token: BlockedToken) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change the icp token blocklist")
+    }
+
+    mutate_state(|s| s.remove_from_icp_token_blocklist(&token))
+}
+
+#[query]
+// Can only be called by admins
+pub fn get_icp_token_blocklist() -> Vec<CandidBlocklistEntry> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can query the icp token blocklist")
+    }
+
+    read_state(|s| s.get_icp_token_blocklist())
+        .into_iter()
+        .map(CandidBlocklistEntry::from)
+        .collect()
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
 async fn validate_all_icp_token() {
     if !is_authorized_caller(ic_cdk::caller()) {
         panic!("Only admins can change icp tokens details")
@@ -345,7 +636,7 @@ async fn validate_all_icp_token() {
     validate_tokens().await;
 }
 
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 // Can only be called by admin
 pub fn add_evm_token(token: CandidEvmToken) {
     if !is_authorized_caller(ic_cdk::caller()) {
@@ -357,11 +648,60 @@ pub fn add_evm_token(token: CandidEvmToken) {
         s.record_evm_token(
             Erc20Identifier::new(&token.erc20_contract_address, token.chain_id),
             token,
+            ic_cdk::api::time(),
         )
     })
 }
 
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admin
+pub fn remove_evm_token(args: GetEvmTokenArgs) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change evm tokens details")
+    }
+
+    let identifier = Erc20Identifier::new(
+        &Address::from_str(&args.address).expect("Wrong Address Provided"),
+        ChainId::from(&args.chain_id),
+    );
+
+    if !mutate_state(|s| s.remove_evm_token(&identifier, ic_cdk::api::time())) {
+        panic!("Token not found")
+    }
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admin
+// Updated fields are marked as runtime managed and will not be overwritten by the bundled token list
+pub fn update_evm_token_metadata(args: UpdateEvmTokenMetadataArgs) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change evm tokens details")
+    }
+
+    let identifier = Erc20Identifier::new(
+        &Address::from_str(&args.address).expect("Wrong Address Provided"),
+        ChainId::from(&args.chain_id),
+    );
+
+    if !mutate_state(|s| s.update_evm_token_metadata(&identifier, args.into(), ic_cdk::api::time()))
+    {
+        panic!("Token not found")
+    }
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
+// Can only be called by admin
+// Adds or updates tokens without an upgrade, existing prices are kept if not provided
+pub fn bulk_import_evm_tokens(tokens: Vec<CandidEvmToken>) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can change evm tokens details")
+    }
+
+    let tokens: Vec<EvmToken> = tokens.into_iter().map(EvmToken::from).collect();
+    mutate_state(|s| s.import_runtime_evm_tokens(tokens, ic_cdk::api::time()))
+}
+
+#[update(guard = "reject_in_maintenance_mode")]
 // can only be called by
 // arguments: (Vec<(cmc_id,volume,price)>)
 // updates based on cmc_id
@@ -373,7 +713,7 @@ pub fn update_evm_token_price_volume(data: Vec<(u64, String, String)>) {
     mutate_state(|s| s.update_evm_price_volume_by_cmc_id(data))
 }
 
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 // updates based on token address and chain id in a batch
 pub fn batch_update_evm_token_price_volume(data: Vec<(GetEvmTokenArgs, String, String)>) {
     if !is_authorized_caller(ic_cdk::caller()) {
@@ -403,16 +743,64 @@ pub fn search_evm_token(query: EvmSearchQuery) -> Vec<CandidEvmToken> {
 }
 
 #[query]
-pub fn get_icp_tokens() -> Vec<CandidIcpToken> {
-    // Get tokens from state
-    let tokens = read_state(|s| s.get_icp_tokens());
+// Ranked search over both evm and icp tokens
+pub fn search_tokens(args: SearchTokensArgs) -> Vec<CandidSearchedToken> {
+    let limit = args
+        .limit
+        .map(|limit| limit as usize)
+        .unwrap_or(DEFAULT_SEARCH_RESULTS)
+        .min(MAX_SEARCH_RESULTS);
+
+    read_state(|s| s.search_tokens(&args.query, args.filter, limit))
+        .into_iter()
+        .map(CandidSearchedToken::from)
+        .collect()
+}
+
+#[query]
+// Optionally filtered by verified status, the witness reveals every icp token
+pub fn get_icp_tokens(verified: Option<bool>) -> CertifiedIcpTokens {
+    // Get tokens from state, soft disabled tokens are not listed
+    let tokens = read_state(|s| s.get_enabled_icp_tokens(verified));
+
+    CertifiedIcpTokens {
+        tokens: tokens.into_iter().map(CandidIcpToken::from).collect(),
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        witness: ByteBuf::from(icp_tokens_witness()),
+    }
+}
+
+// Can only be called by admins
+// Missing evm tokens can be fixed with add_evm_token, the pair is recorded on the next bridge pairs update
+#[query]
+pub fn get_pending_bridge_pairs() -> Vec<CandidPendingBridgePair> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can query pending bridge pairs")
+    }
+
+    read_state(|s| s.get_pending_bridge_pairs())
+        .into_iter()
+        .map(CandidPendingBridgePair::from)
+        .collect()
+}
+
+// Can only be called by admins
+#[query]
+pub fn get_icp_tokens_at_risk() -> Vec<CandidIcpTokenAtRisk> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can query tokens at risk")
+    }
+
+    let config = read_state(|s| s.get_token_validation_config());
 
-    // Return Tokens
-    tokens.into_iter().map(CandidIcpToken::from).collect()
+    read_state(|s| s.get_icp_tokens_at_risk())
+        .into_iter()
+        .filter_map(|token| CandidIcpTokenAtRisk::from_icp_token(token, &config))
+        .collect()
 }
 
 // Can only be called by controller
-#[update]
+#[update(guard = "reject_in_maintenance_mode")]
 pub async fn request_update_bridge_pairs() {
     if !is_authorized_caller(ic_cdk::caller()) {
         panic!("Only admins request update bride pairs")
@@ -429,6 +817,246 @@ pub fn get_minters() -> Vec<MinterArgs> {
         .collect()
 }
 
+#[query]
+// Scraping health of the minters and dex sources since the last upgrade
+pub fn get_scraper_health() -> Vec<SourceHealth> {
+    get_sources_health()
+}
+
+#[query]
+// Transactions that exceeded the sla of their current status in the last check
+pub fn get_stuck_transactions() -> Vec<CandidStuckTransaction> {
+    read_state(|s| s.get_stuck_transactions(ic_cdk::api::time()))
+}
+
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    read_state(|s| {
+        let mut blocks = vec![];
+        let mut remaining = MAX_BLOCKS_PER_RESPONSE;
+
+        for request in args {
+            let start = checked_nat_to_u64(&request.start).unwrap_or(u64::MAX);
+            let length = checked_nat_to_u64(&request.length)
+                .unwrap_or(u64::MAX)
+                .min(remaining);
+
+            for (id, block) in s.get_blocks(start, length) {
+                blocks.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: block.to_icrc3_value(),
+                });
+                remaining -= 1;
+            }
+
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        GetBlocksResult {
+            log_length: Nat::from(s.block_log_length()),
+            blocks,
+            archived_blocks: vec![],
+        }
+    })
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ic_cdk::api::data_certificate()?;
+
+    Some(ICRC3DataCertificate {
+        certificate: ByteBuf::from(certificate),
+        hash_tree: ByteBuf::from(tip_witness()),
+    })
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    supported_block_types()
+}
+
+#[query]
+// The block log is never archived
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ICRC3ArchiveInfo> {
+    vec![]
+}
+
+#[query]
+// Minted deposits and withdrawal burns checked against their ledger blocks
+pub fn get_reconciliation_report() -> ReconciliationReport {
+    read_state(|s| s.get_reconciliation_report())
+}
+
+// Can only be called by admins
+// Submission counters since the last upgrade
+#[query]
+pub fn get_submission_stats() -> SubmissionStats {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can query submission stats")
+    }
+
+    submission_stats()
+}
+
+// Can only be called by admins
+// Pauses the timers, rejects the other update endpoints and allows state imports,
+// the indexes and the certified tree are rebuilt in batches when disabled
+#[update]
+pub fn set_maintenance_mode(enabled: bool) {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can set the maintenance mode")
+    }
+
+    let was_enabled = read_state(|s| s.is_in_maintenance_mode());
+    mutate_state(|s| s.set_maintenance_mode(enabled, ic_cdk::api::time()));
+    log!(INFO, "[Maintenance] Maintenance mode enabled: {}", enabled);
+
+    // Imported transactions are neither indexed nor certified, imported tokens and bridge pairs
+    // are certified again with the tree once the maintenance is over
+    if was_enabled && !enabled {
+        mutate_state(|s| s.start_indexes_rebuild(INDEXES_VERSION));
+        schedule_indexes_rebuild();
+        start_certified_tree_reload();
+    }
+}
+
+#[query]
+pub fn is_in_maintenance_mode() -> bool {
+    read_state(|s| s.is_in_maintenance_mode())
+}
+
+// Can only be called by admins
+// Exports the entries of a section after the cursor, starting from the first entry without a cursor
+#[query]
+pub fn export_state_chunk(
+    section: StateSection,
+    cursor: Option<ByteBuf>,
+) -> Result<StateChunk, ExportStateChunkError> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can export the state")
+    }
+
+    let (entries, next_cursor) =
+        read_state(|s| s.export_state_chunk(section, cursor.as_deref().map(|c| c.as_slice())))
+            .map_err(|err| ExportStateChunkError::EntryTooLarge {
+                key: ByteBuf::from(err.key),
+                size: err.size as u64,
+            })?;
+
+    Ok(StateChunk {
+        entries: ByteBuf::from(encode_chunk(&entries)),
+        count: entries.len() as u64,
+        next_cursor: next_cursor.map(ByteBuf::from),
+    })
+}
+
+// Can only be called by admins
+// Imports a chunk returned by export_state_chunk, existing entries are overwritten
+#[update]
+pub fn import_state_chunk(
+    section: StateSection,
+    entries: ByteBuf,
+) -> Result<u64, ImportStateChunkError> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can import the state")
+    }
+
+    if !read_state(|s| s.is_in_maintenance_mode()) {
+        return Err(ImportStateChunkError::NotInMaintenanceMode);
+    }
+
+    let entries = decode_chunk(&entries).map_err(ImportStateChunkError::InvalidChunk)?;
+    let count = entries.len() as u64;
+
+    mutate_state(|s| s.import_state_chunk(section, entries));
+    log!(
+        INFO,
+        "[Maintenance] Imported {} entries of {:?}",
+        count,
+        section
+    );
+
+    Ok(count)
+}
+
+// Subscribes the calling canister to the transaction changes matching the filter,
+// subscribing again replaces the previous subscription.
+// Only the canisters allowed by the admins through the upgrade args can subscribe
+#[update(guard = "reject_in_maintenance_mode")]
+pub fn subscribe(args: SubscribeArgs) -> Result<(), SubscribeError> {
+    let caller = ic_cdk::caller();
+
+    if !is_canister(&caller) {
+        return Err(SubscribeError::CallerNotACanister);
+    }
+
+    if !read_state(|s| s.is_allowed_subscriber(&caller)) {
+        return Err(SubscribeError::CallerNotAllowed);
+    }
+
+    if args.method.is_empty() || args.method.len() > MAX_METHOD_NAME_LENGTH {
+        return Err(SubscribeError::InvalidMethod);
+    }
+
+    if args.evm_to_icp_statuses.is_empty()
+        && args.icp_to_evm_statuses.is_empty()
+        && !args.dex_actions
+    {
+        return Err(SubscribeError::EmptyFilter);
+    }
+
+    mutate_state(|s| {
+        if !s.subscriptions.contains_key(&caller) && s.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(SubscribeError::TooManySubscriptions);
+        }
+
+        log!(
+            INFO,
+            "[Notifications] {} subscribed with {:?}",
+            caller,
+            args
+        );
+        s.record_subscription(caller, args.into_subscription(ic_cdk::api::time()));
+        Ok(())
+    })
+}
+
+// Removes the subscription of the calling canister and its queued notifications
+#[update(guard = "reject_in_maintenance_mode")]
+pub fn unsubscribe() {
+    let caller = ic_cdk::caller();
+
+    if mutate_state(|s| s.remove_subscription(&caller)).is_some() {
+        log!(INFO, "[Notifications] {} unsubscribed", caller);
+    }
+    remove_queue(&caller);
+}
+
+// Can only be called by admins
+#[query]
+pub fn get_subscriptions() -> Vec<CandidSubscription> {
+    if !is_authorized_caller(ic_cdk::caller()) {
+        panic!("Only admins can query subscriptions")
+    }
+
+    read_state(|s| s.get_subscriptions())
+        .into_iter()
+        .map(|(subscriber, subscription)| {
+            CandidSubscription::new(subscriber, subscription, queued_notifications(&subscriber))
+        })
+        .collect()
+}
+
+#[query]
+pub fn get_dex_sources() -> Vec<CandidDexSource> {
+    read_state(|s| s.get_dex_sources())
+        .into_iter()
+        .map(CandidDexSource::from)
+        .collect()
+}
+
 #[query]
 pub fn get_dex_actions_for_principal(principal_id: Principal) -> Vec<CandidDexAction> {
     read_state(|s| s.get_dex_actions_for_principal(principal_id))
@@ -2,6 +2,10 @@ use crate::minter_client::appic_minter_types::events::{
     ReimbursementIndex as AppicReimbursementIndex, TransactionReceipt as AppicTransactionReceipt,
     TransactionStatus as AppicTransactionStatus, UnsignedTransaction as AppicUnsignedTransaction,
 };
+use crate::minter_client::appic_minter_types::{
+    CandidBlockTag as AppicCandidBlockTag, EvmNetwork as AppicEvmNetwork, InitArg as AppicInitArg,
+    UpgradeArg as AppicUpgradeArg,
+};
 
 use candid::{CandidType, Deserialize, Nat, Principal};
 use serde::Serialize;
@@ -12,12 +16,40 @@ pub enum CandidBlockTag {
     Finalized,
 }
 
+impl From<CandidBlockTag> for AppicCandidBlockTag {
+    fn from(value: CandidBlockTag) -> Self {
+        match value {
+            CandidBlockTag::Latest => Self::Latest,
+            CandidBlockTag::Safe => Self::Safe,
+            CandidBlockTag::Finalized => Self::Finalized,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize)]
 pub enum EthereumNetwork {
     Mainnet,
     Sepolia,
 }
 
+impl EthereumNetwork {
+    pub fn native_symbol(&self) -> &'static str {
+        match self {
+            EthereumNetwork::Mainnet => "ckETH",
+            EthereumNetwork::Sepolia => "ckSepoliaETH",
+        }
+    }
+}
+
+impl From<EthereumNetwork> for AppicEvmNetwork {
+    fn from(value: EthereumNetwork) -> Self {
+        match value {
+            EthereumNetwork::Mainnet => Self::Ethereum,
+            EthereumNetwork::Sepolia => Self::Sepolia,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct InitArg {
     pub ethereum_network: EthereumNetwork,
@@ -30,6 +62,32 @@ pub struct InitArg {
     pub last_scraped_block_number: Nat,
 }
 
+// The ckETH minter does not charge deposit or withdrawal fees on top of the gas fee,
+// the index and ledger suite orchestrator ids are not part of its init args and are not used by the logger.
+// The ledger transfer fee is not part of the init args either, it is read from the ledger when
+// the event is scraped, see MinterClient::scrape_events
+impl From<InitArg> for AppicInitArg {
+    fn from(value: InitArg) -> Self {
+        Self {
+            native_symbol: value.ethereum_network.native_symbol().to_string(),
+            evm_network: value.ethereum_network.into(),
+            ecdsa_key_name: value.ecdsa_key_name,
+            helper_contract_address: value.ethereum_contract_address,
+            native_ledger_id: value.ledger_id,
+            native_index_id: Principal::anonymous(),
+            block_height: value.ethereum_block_height.into(),
+            native_minimum_withdrawal_amount: value.minimum_withdrawal_amount,
+            native_ledger_transfer_fee: Nat::from(0_u8),
+            next_transaction_nonce: value.next_transaction_nonce,
+            last_scraped_block_number: value.last_scraped_block_number,
+            min_max_priority_fee_per_gas: Nat::from(0_u8),
+            ledger_suite_manager_id: Principal::anonymous(),
+            deposit_native_fee: Nat::from(0_u8),
+            withdrawal_native_fee: Nat::from(0_u8),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct UpgradeArg {
     pub next_transaction_nonce: Option<Nat>,
@@ -44,6 +102,24 @@ pub struct UpgradeArg {
     pub last_deposit_with_subaccount_scraped_block_number: Option<Nat>,
 }
 
+// No fee is changed since the ckETH minter does not charge a withdrawal fee
+impl From<UpgradeArg> for AppicUpgradeArg {
+    fn from(value: UpgradeArg) -> Self {
+        Self {
+            next_transaction_nonce: value.next_transaction_nonce,
+            native_minimum_withdrawal_amount: value.minimum_withdrawal_amount,
+            helper_contract_address: value.ethereum_contract_address,
+            block_height: value.ethereum_block_height.map(Into::into),
+            last_scraped_block_number: None,
+            evm_rpc_id: value.evm_rpc_id,
+            native_ledger_transfer_fee: None,
+            min_max_priority_fee_per_gas: None,
+            deposit_native_fee: None,
+            withdrawal_native_fee: None,
+        }
+    }
+}
+
 pub mod events {
 
     use super::*;
@@ -75,13 +75,30 @@ impl From<DfinityCkGetEventsResult> for AppicGetEventsResult {
                 let timestamp = event.timestamp;
 
                 let event_payload = match event.payload {
-                    DfinityEventPayload::Init(..)
-                    | DfinityEventPayload::Upgrade(..)
-                    | DfinityEventPayload::SyncedToBlock { .. }
+                    DfinityEventPayload::SyncedToBlock { .. }
                     | DfinityEventPayload::SyncedErc20ToBlock { .. }
                     | DfinityEventPayload::SyncedDepositWithSubaccountToBlock { .. }
-                    | DfinityEventPayload::SkippedBlock { .. }
-                    | DfinityEventPayload::AddedCkErc20Token { .. } => None,
+                    | DfinityEventPayload::SkippedBlock { .. } => None,
+
+                    DfinityEventPayload::Init(init_arg) => {
+                        Some(AppicEventPayload::Init(init_arg.into()))
+                    }
+
+                    DfinityEventPayload::Upgrade(upgrade_arg) => {
+                        Some(AppicEventPayload::Upgrade(upgrade_arg.into()))
+                    }
+
+                    DfinityEventPayload::AddedCkErc20Token {
+                        chain_id,
+                        address,
+                        ckerc20_token_symbol,
+                        ckerc20_ledger_id,
+                    } => Some(AppicEventPayload::AddedErc20Token {
+                        chain_id,
+                        address,
+                        erc20_token_symbol: ckerc20_token_symbol,
+                        erc20_ledger_id: ckerc20_ledger_id,
+                    }),
 
                     DfinityEventPayload::AcceptedDeposit {
                         transaction_hash,
@@ -300,3 +317,121 @@ impl From<DfinityCkGetEventsResult> for AppicGetEventsResult {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use candid::{Nat, Principal};
+
+    use super::*;
+    use crate::minter_client::appic_minter_types::{
+        CandidBlockTag as AppicCandidBlockTag, EvmNetwork, InitArg as AppicInitArg,
+        UpgradeArg as AppicUpgradeArg,
+    };
+    use crate::minter_client::dfinity_ck_minter_types::events::Event as DfinityEvent;
+    use crate::minter_client::dfinity_ck_minter_types::{
+        CandidBlockTag, EthereumNetwork, InitArg, UpgradeArg,
+    };
+
+    pub fn dfinity_events(payloads: Vec<DfinityEventPayload>) -> DfinityCkGetEventsResult {
+        DfinityCkGetEventsResult {
+            total_event_count: payloads.len() as u64,
+            events: payloads
+                .into_iter()
+                .map(|payload| DfinityEvent {
+                    timestamp: 1,
+                    payload,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn sepolia_init_arg(ledger_id: Principal) -> InitArg {
+        InitArg {
+            ethereum_network: EthereumNetwork::Sepolia,
+            ecdsa_key_name: "key_1".to_string(),
+            ethereum_contract_address: Some(
+                "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".to_string(),
+            ),
+            ledger_id,
+            ethereum_block_height: CandidBlockTag::Finalized,
+            minimum_withdrawal_amount: Nat::from(10_u8),
+            next_transaction_nonce: Nat::from(0_u8),
+            last_scraped_block_number: Nat::from(100_u8),
+        }
+    }
+
+    fn reduced_payloads(payloads: Vec<DfinityEventPayload>) -> Vec<AppicEventPayload> {
+        dfinity_events(payloads)
+            .reduce()
+            .events
+            .into_iter()
+            .map(|event| event.payload)
+            .collect()
+    }
+
+    #[test]
+    fn test_init_conversion() {
+        let ledger_id = Principal::from_slice(&[1, 1]);
+
+        assert_eq!(
+            reduced_payloads(vec![DfinityEventPayload::Init(sepolia_init_arg(ledger_id))]),
+            vec![AppicEventPayload::Init(AppicInitArg {
+                evm_network: EvmNetwork::Sepolia,
+                ecdsa_key_name: "key_1".to_string(),
+                helper_contract_address: Some(
+                    "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".to_string()
+                ),
+                native_ledger_id: ledger_id,
+                native_index_id: Principal::anonymous(),
+                native_symbol: "ckSepoliaETH".to_string(),
+                block_height: AppicCandidBlockTag::Finalized,
+                native_minimum_withdrawal_amount: Nat::from(10_u8),
+                // Read from the ledger when the event is scraped
+                native_ledger_transfer_fee: Nat::from(0_u8),
+                next_transaction_nonce: Nat::from(0_u8),
+                last_scraped_block_number: Nat::from(100_u8),
+                min_max_priority_fee_per_gas: Nat::from(0_u8),
+                ledger_suite_manager_id: Principal::anonymous(),
+                deposit_native_fee: Nat::from(0_u8),
+                withdrawal_native_fee: Nat::from(0_u8),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_conversion() {
+        assert_eq!(
+            reduced_payloads(vec![DfinityEventPayload::Upgrade(UpgradeArg {
+                minimum_withdrawal_amount: Some(Nat::from(20_u8)),
+                ethereum_block_height: Some(CandidBlockTag::Latest),
+                erc20_helper_contract_address: Some("0x1".to_string()),
+                ..Default::default()
+            })]),
+            vec![AppicEventPayload::Upgrade(AppicUpgradeArg {
+                native_minimum_withdrawal_amount: Some(Nat::from(20_u8)),
+                block_height: Some(AppicCandidBlockTag::Latest),
+                ..Default::default()
+            })]
+        );
+    }
+
+    #[test]
+    fn test_added_ckerc20_token_conversion() {
+        let ledger_id = Principal::from_slice(&[2, 1]);
+
+        assert_eq!(
+            reduced_payloads(vec![DfinityEventPayload::AddedCkErc20Token {
+                chain_id: Nat::from(1_u8),
+                address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+                ckerc20_token_symbol: "ckUSDC".to_string(),
+                ckerc20_ledger_id: ledger_id,
+            }]),
+            vec![AppicEventPayload::AddedErc20Token {
+                chain_id: Nat::from(1_u8),
+                address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+                erc20_token_symbol: "ckUSDC".to_string(),
+                erc20_ledger_id: ledger_id,
+            }]
+        );
+    }
+}
@@ -2,7 +2,7 @@ pub mod appic_minter_types;
 pub mod dfinity_ck_minter_types;
 pub mod event_conversion;
 use async_trait::async_trait;
-use candid::Principal;
+use candid::{Nat, Principal};
 
 use std::fmt;
 
@@ -14,7 +14,8 @@ use std::fmt::Debug;
 use crate::state::types::{Minter, Operator};
 
 use appic_minter_types::{
-    events::GetEventsArg as AppicGetEventsArg, events::GetEventsResult as AppicGetEventsResult,
+    events::EventPayload as AppicEventPayload, events::GetEventsArg as AppicGetEventsArg,
+    events::GetEventsResult as AppicGetEventsResult,
 };
 use dfinity_ck_minter_types::{
     events::GetEventsArg as DfinityCkGetEventsArg,
@@ -63,33 +64,36 @@ impl Runtime for IcRunTime {
     }
 }
 
-pub struct MinterClient {
-    runtime: IcRunTime,
+pub struct MinterClient<R: Runtime = IcRunTime> {
+    runtime: R,
     minter_id: Principal,
     operator: Operator,
 }
 
 impl From<&Minter> for MinterClient {
     fn from(value: &Minter) -> Self {
-        Self {
-            runtime: IcRunTime(),
-            minter_id: value.id,
-            operator: value.operator,
-        }
+        Self::new(value.id, value.operator)
     }
 }
 
 impl MinterClient {
     pub fn new(minter_id: Principal, operator: Operator) -> Self {
+        Self::with_runtime(IcRunTime(), minter_id, operator)
+    }
+}
+
+impl<R: Runtime> MinterClient<R> {
+    // Canisters are called through the given runtime, so that they can be mocked in tests
+    pub fn with_runtime(runtime: R, minter_id: Principal, operator: Operator) -> Self {
         Self {
-            runtime: IcRunTime(),
+            runtime,
             minter_id,
             operator,
         }
     }
 
     // Get total events count
-    pub async fn get_total_events_count(&self) -> u64 {
+    pub async fn get_total_events_count(&self) -> Result<u64, CallError> {
         // Get total events count
         let total_events_count = match self.operator {
             Operator::DfinityCkEthMinter => {
@@ -102,8 +106,7 @@ impl MinterClient {
                             length: 0,
                         },
                     )
-                    .await
-                    .expect("Call should not fail. will retry in next interval")
+                    .await?
                     .total_event_count
             }
             Operator::AppicMinter => {
@@ -116,30 +119,43 @@ impl MinterClient {
                             length: 0,
                         },
                     )
-                    .await
-                    .expect("Call should not fail. will retry in next interval")
+                    .await?
                     .total_event_count
             }
         };
 
-        total_events_count
+        Ok(total_events_count)
     }
 
     // scrape events
     pub async fn scrape_events(&self, from_event: u64, length: u64) -> Result<Events, CallError> {
         match self.operator {
-            Operator::DfinityCkEthMinter => self
-                .runtime
-                .call_canister::<DfinityCkGetEventsArg, DfinityCkGetEventsResult>(
-                    self.minter_id,
-                    "get_events",
-                    DfinityCkGetEventsArg {
-                        start: from_event,
-                        length,
-                    },
-                )
-                .await
-                .map(|response| response.reduce()),
+            Operator::DfinityCkEthMinter => {
+                let mut events = self
+                    .runtime
+                    .call_canister::<DfinityCkGetEventsArg, DfinityCkGetEventsResult>(
+                        self.minter_id,
+                        "get_events",
+                        DfinityCkGetEventsArg {
+                            start: from_event,
+                            length,
+                        },
+                    )
+                    .await?
+                    .reduce();
+
+                // The ckETH minter init args do not include the ledger transfer fee
+                for event in events.events.iter_mut() {
+                    if let AppicEventPayload::Init(init_arg) = &mut event.payload {
+                        init_arg.native_ledger_transfer_fee = self
+                            .runtime
+                            .call_canister::<(), Nat>(init_arg.native_ledger_id, "icrc1_fee", ())
+                            .await?;
+                    }
+                }
+
+                Ok(events)
+            }
             Operator::AppicMinter => self
                 .runtime
                 .call_canister::<AppicGetEventsArg, AppicGetEventsResult>(
@@ -232,3 +248,46 @@ impl Reason {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icp_tokens_service::tests::MockRuntime;
+    use crate::minter_client::dfinity_ck_minter_types::events::EventPayload as DfinityEventPayload;
+    use crate::minter_client::event_conversion::tests::{dfinity_events, sepolia_init_arg};
+
+    #[test]
+    fn test_cketh_init_reads_ledger_fee() {
+        let minter_id = Principal::from_slice(&[1, 1]);
+        let ledger_id = Principal::from_slice(&[2, 1]);
+
+        let mut runtime = MockRuntime::default();
+        runtime.respond(
+            minter_id,
+            "get_events",
+            dfinity_events(vec![DfinityEventPayload::Init(sepolia_init_arg(ledger_id))]),
+        );
+        let client = MinterClient::with_runtime(runtime, minter_id, Operator::DfinityCkEthMinter);
+
+        // The page is not applied until the fee is known
+        assert!(futures::executor::block_on(client.scrape_events(0, 1)).is_err());
+
+        let mut runtime = MockRuntime::default();
+        runtime.respond(
+            minter_id,
+            "get_events",
+            dfinity_events(vec![DfinityEventPayload::Init(sepolia_init_arg(ledger_id))]),
+        );
+        runtime.respond(ledger_id, "icrc1_fee", Nat::from(2_000_000_000_000_u64));
+        let client = MinterClient::with_runtime(runtime, minter_id, Operator::DfinityCkEthMinter);
+
+        let events = futures::executor::block_on(client.scrape_events(0, 1)).unwrap();
+        match &events.events[0].payload {
+            AppicEventPayload::Init(init_arg) => assert_eq!(
+                init_arg.native_ledger_transfer_fee,
+                Nat::from(2_000_000_000_000_u64)
+            ),
+            payload => panic!("Unexpected payload {:?}", payload),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+// Notifications of transaction changes for subscribed canisters.
+// Changes matching the filter of a subscription are queued and delivered in batches to the
+// callback method of the subscriber after new minter or dex events are applied.
+// Batches are sent as one way calls, so a slow or stopped subscriber never keeps a call context
+// of the logger open and can not block its upgrades. Subscribers get no delivery guarantee:
+// a batch that could not be sent is retried with a delay and dropped after MAX_DELIVERY_ATTEMPTS.
+// Queues are kept in heap memory and reset on upgrade, subscriptions are kept in stable memory.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use candid::Principal;
+use ic_canister_log::log;
+
+use crate::{
+    endpoints::{Transaction, TxNotification},
+    logs::INFO,
+    minter_client::Reason,
+    state::read_state,
+};
+
+// Maximum number of subscribed canisters
+pub const MAX_SUBSCRIPTIONS: u64 = 100;
+
+// Maximum length of the callback method name
+pub const MAX_METHOD_NAME_LENGTH: usize = 64;
+
+pub const MAX_NOTIFICATIONS_PER_BATCH: usize = 50;
+
+// Oldest notifications are dropped once a subscriber has this many queued notifications
+pub const MAX_QUEUED_NOTIFICATIONS: usize = 1_000;
+
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+// 30 Seconds
+pub const RETRY_DELIVERY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct SubscriberQueue {
+    notifications: VecDeque<TxNotification>,
+    // Failed deliveries of the first batch
+    attempts: u32,
+    in_flight: bool,
+}
+
+thread_local! {
+    static QUEUES: RefCell<BTreeMap<Principal, SubscriberQueue>> = RefCell::default();
+
+    static NEXT_NOTIFICATION_ID: RefCell<u64> = RefCell::default();
+}
+
+fn mutate_queue<F, R>(subscriber: Principal, f: F) -> R
+where
+    F: FnOnce(&mut SubscriberQueue) -> R,
+{
+    QUEUES.with(|queues| f(queues.borrow_mut().entry(subscriber).or_default()))
+}
+
+// Only canisters can subscribe, canister ids are opaque ids ending with 0x01
+pub fn is_canister(principal: &Principal) -> bool {
+    principal.as_slice().last() == Some(&0x01)
+}
+
+pub fn enqueue_notification(subscriber: Principal, transaction: Transaction, now: u64) {
+    let id = NEXT_NOTIFICATION_ID.with(|next_id| {
+        let mut next_id = next_id.borrow_mut();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    });
+
+    let notification = TxNotification {
+        id,
+        transaction,
+        timestamp: now,
+    };
+
+    mutate_queue(subscriber, |queue| {
+        if queue.notifications.len() >= MAX_QUEUED_NOTIFICATIONS {
+            queue.notifications.pop_front();
+        }
+        queue.notifications.push_back(notification);
+    });
+}
+
+pub fn remove_queue(subscriber: &Principal) {
+    QUEUES.with(|queues| queues.borrow_mut().remove(subscriber));
+}
+
+pub fn queued_notifications(subscriber: &Principal) -> u64 {
+    QUEUES.with(|queues| {
+        queues
+            .borrow()
+            .get(subscriber)
+            .map_or(0, |queue| queue.notifications.len() as u64)
+    })
+}
+
+// Marks the queue of the subscriber in flight and returns its first batch,
+// None if the queue is empty or a delivery is already in flight
+fn take_batch(subscriber: Principal) -> Option<Vec<TxNotification>> {
+    mutate_queue(subscriber, |queue| {
+        if queue.in_flight || queue.notifications.is_empty() {
+            return None;
+        }
+        queue.in_flight = true;
+        Some(
+            queue
+                .notifications
+                .iter()
+                .take(MAX_NOTIFICATIONS_PER_BATCH)
+                .cloned()
+                .collect(),
+        )
+    })
+}
+
+// Removes the delivered batch, or counts the failed attempt and drops the batch once
+// it reached the maximum attempts. Returns true if the batch should be retried.
+fn complete_batch(subscriber: Principal, batch_len: usize, delivered: bool) -> bool {
+    QUEUES.with(|queues| {
+        // The queue is removed if the subscriber unsubscribed during the delivery
+        let mut queues = queues.borrow_mut();
+        let Some(queue) = queues.get_mut(&subscriber) else {
+            return false;
+        };
+
+        queue.in_flight = false;
+
+        if !delivered {
+            queue.attempts += 1;
+            if queue.attempts < MAX_DELIVERY_ATTEMPTS {
+                return true;
+            }
+            log!(
+                INFO,
+                "[Notifications] Dropped {} notifications of {} after {} attempts",
+                batch_len,
+                subscriber,
+                queue.attempts
+            );
+        }
+
+        queue.attempts = 0;
+        let batch_len = batch_len.min(queue.notifications.len());
+        queue.notifications.drain(..batch_len);
+        false
+    })
+}
+
+// Sends the queued batches of the subscriber, returns true if a batch should be retried
+fn deliver_to(subscriber: Principal, method: String) -> bool {
+    let mut retry = false;
+
+    while let Some(batch) = take_batch(subscriber) {
+        let batch_len = batch.len();
+        let result = ic_cdk::api::call::notify(subscriber, &method, (batch,));
+
+        if let Err(code) = &result {
+            log!(
+                INFO,
+                "[Notifications] Failed to notify {}: {:?}",
+                subscriber,
+                Reason::from_reject(*code, "failed to enqueue the notification".to_string())
+            );
+        }
+
+        if complete_batch(subscriber, batch_len, result.is_ok()) {
+            retry = true;
+            break;
+        }
+    }
+
+    retry
+}
+
+// Delivers the queued notifications of every subscriber
+pub async fn deliver_notifications() {
+    let subscriptions: Vec<(Principal, String)> = read_state(|s| {
+        s.subscriptions
+            .iter()
+            .map(|(subscriber, subscription)| (subscriber, subscription.method))
+            .collect()
+    });
+
+    // Every subscriber is delivered even if a previous one has to be retried
+    let retry = subscriptions
+        .into_iter()
+        .map(|(subscriber, method)| deliver_to(subscriber, method))
+        .fold(false, |retry, subscriber_retry| retry || subscriber_retry);
+
+    if retry {
+        ic_cdk_timers::set_timer(RETRY_DELIVERY_DELAY, || {
+            ic_cdk::spawn(deliver_notifications())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use crate::endpoints::CandidEvmToIcp;
+    use crate::numeric::Erc20TokenAmount;
+    use crate::state::types::{ChainId, EvmToIcpStatus, EvmToIcpTx, Operator, SubscriptionFilter};
+
+    fn deposit(principal: Principal, chain_id: ChainId, status: EvmToIcpStatus) -> EvmToIcpTx {
+        EvmToIcpTx {
+            from_address: Address::ZERO,
+            transaction_hash: "0x1".to_string(),
+            value: Erc20TokenAmount::from(1_000_u64),
+            ledger_mint_index: None,
+            block_number: None,
+            actual_received: None,
+            principal,
+            subaccount: None,
+            chain_id,
+            total_gas_spent: None,
+            erc20_contract_address: Address::ZERO,
+            icrc_ledger_id: None,
+            status,
+            verified: true,
+            time: 0,
+            operator: Operator::AppicMinter,
+            status_updated_at: None,
+        }
+    }
+
+    fn queue_notifications(subscriber: Principal, count: u64) {
+        let transaction = Transaction::EvmToIcp(CandidEvmToIcp::from(deposit(
+            Principal::anonymous(),
+            ChainId(1),
+            EvmToIcpStatus::Minted,
+        )));
+        mutate_queue(subscriber, |queue| {
+            for id in 0..count {
+                queue.notifications.push_back(TxNotification {
+                    id,
+                    transaction: transaction.clone(),
+                    timestamp: 0,
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_filter_matches_evm_to_icp() {
+        let principal = Principal::from_slice(&[1]);
+        let filter = SubscriptionFilter {
+            principals: vec![principal],
+            chain_ids: vec![ChainId(1)],
+            evm_to_icp_statuses: vec![EvmToIcpStatus::Invalid(String::new())],
+            icp_to_evm_statuses: vec![],
+            dex_actions: false,
+        };
+
+        // Statuses with a payload match regardless of the payload
+        let invalid = EvmToIcpStatus::Invalid("reason".to_string());
+        assert!(filter.matches_evm_to_icp(&deposit(principal, ChainId(1), invalid.clone())));
+
+        assert!(!filter.matches_evm_to_icp(&deposit(
+            principal,
+            ChainId(1),
+            EvmToIcpStatus::Minted
+        )));
+        assert!(!filter.matches_evm_to_icp(&deposit(principal, ChainId(56), invalid.clone())));
+        assert!(!filter.matches_evm_to_icp(&deposit(Principal::anonymous(), ChainId(1), invalid)));
+    }
+
+    #[test]
+    fn test_empty_filter_lists_match_everything() {
+        let filter = SubscriptionFilter {
+            principals: vec![],
+            chain_ids: vec![],
+            evm_to_icp_statuses: vec![EvmToIcpStatus::Minted],
+            icp_to_evm_statuses: vec![],
+            dex_actions: true,
+        };
+
+        assert!(filter.matches_evm_to_icp(&deposit(
+            Principal::anonymous(),
+            ChainId(8453),
+            EvmToIcpStatus::Minted
+        )));
+        assert!(filter.matches_dex_action(&Principal::anonymous()));
+
+        let filter = SubscriptionFilter {
+            dex_actions: false,
+            ..filter
+        };
+        assert!(!filter.matches_dex_action(&Principal::anonymous()));
+    }
+
+    #[test]
+    fn test_complete_batch_retries_then_drops() {
+        let subscriber = Principal::from_slice(&[2, 1]);
+        queue_notifications(subscriber, 60);
+
+        let batch = take_batch(subscriber).unwrap();
+        assert_eq!(batch.len(), MAX_NOTIFICATIONS_PER_BATCH);
+
+        // No other batch is taken while one is in flight
+        assert_eq!(take_batch(subscriber), None);
+
+        // The same batch is retried until the maximum attempts
+        assert!(complete_batch(subscriber, batch.len(), false));
+        for _ in 1..MAX_DELIVERY_ATTEMPTS - 1 {
+            assert_eq!(take_batch(subscriber), Some(batch.clone()));
+            assert!(complete_batch(subscriber, batch.len(), false));
+        }
+        assert_eq!(queued_notifications(&subscriber), 60);
+
+        // and dropped after the last attempt
+        assert_eq!(take_batch(subscriber), Some(batch.clone()));
+        assert!(!complete_batch(subscriber, batch.len(), false));
+        assert_eq!(queued_notifications(&subscriber), 10);
+
+        // Attempts are reset for the next batch
+        let batch = take_batch(subscriber).unwrap();
+        assert_eq!(batch.len(), 10);
+        assert!(complete_batch(subscriber, batch.len(), false));
+        assert_eq!(take_batch(subscriber), Some(batch.clone()));
+        assert!(!complete_batch(subscriber, batch.len(), true));
+        assert_eq!(queued_notifications(&subscriber), 0);
+        assert_eq!(take_batch(subscriber), None);
+    }
+
+    #[test]
+    fn test_complete_batch_after_unsubscribe() {
+        let subscriber = Principal::from_slice(&[3, 1]);
+        queue_notifications(subscriber, 1);
+
+        let batch = take_batch(subscriber).unwrap();
+        remove_queue(&subscriber);
+
+        assert!(!complete_batch(subscriber, batch.len(), false));
+        assert_eq!(queued_notifications(&subscriber), 0);
+    }
+
+    #[test]
+    fn test_is_canister() {
+        assert!(is_canister(
+            &Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap()
+        ));
+        assert!(!is_canister(&Principal::anonymous()));
+        assert!(!is_canister(&Principal::management_canister()));
+    }
+}
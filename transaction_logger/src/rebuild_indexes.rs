@@ -0,0 +1,81 @@
+// Rebuild of the indexes derived from the transactions, like the minted deposits of each principal.
+// Indexes are updated on every write and only rebuilt after upgrades that change them.
+// The rebuild runs in batches, each in its own message to stay below the instruction limit,
+// and its progress is kept in stable memory so that it resumes after an upgrade.
+// The certified tree is reloaded from its leaves in stable memory the same way after every upgrade,
+// its progress is kept in heap memory since the tree itself is lost on upgrades.
+
+use std::time::Duration;
+
+use ic_canister_log::log;
+
+use crate::{
+    guard::{TaskType, TimerGuard},
+    logs::INFO,
+    state::mutate_state,
+};
+
+// Bumped whenever an index is added or changed, so that the indexes are rebuilt on upgrade
+pub const INDEXES_VERSION: u32 = 7;
+
+pub const INDEXES_REBUILD_BATCH_SIZE: usize = 1_000;
+
+pub const CERTIFIED_TREE_RELOAD_BATCH_SIZE: usize = 10_000;
+
+// Starts a rebuild if the indexes were built by another version, and resumes an interrupted rebuild
+pub fn resume_indexes_rebuild() {
+    mutate_state(|s| {
+        if s.get_indexes_checkpoint().version != INDEXES_VERSION {
+            log!(
+                INFO,
+                "[Rebuild Indexes] Rebuilding indexes for version {}",
+                INDEXES_VERSION
+            );
+            s.start_indexes_rebuild(INDEXES_VERSION);
+        }
+    });
+
+    schedule_indexes_rebuild();
+}
+
+pub fn schedule_indexes_rebuild() {
+    ic_cdk_timers::set_timer(Duration::from_secs(0), rebuild_indexes);
+}
+
+pub fn rebuild_indexes() {
+    // Paused in maintenance mode, resumed once the maintenance mode is disabled
+    let _guard = match TimerGuard::new(TaskType::RebuildIndexes) {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    if mutate_state(|s| s.rebuild_indexes_batch(INDEXES_REBUILD_BATCH_SIZE)) {
+        schedule_indexes_rebuild();
+    } else {
+        log!(INFO, "[Rebuild Indexes] Indexes are complete");
+    }
+}
+
+// Reloads the certified tree lost on upgrade, certified responses carry no certificate until it completes
+pub fn start_certified_tree_reload() {
+    mutate_state(|s| s.start_certified_tree_reload());
+
+    schedule_certified_tree_reload();
+}
+
+pub fn schedule_certified_tree_reload() {
+    ic_cdk_timers::set_timer(Duration::from_secs(0), reload_certified_tree);
+}
+
+pub fn reload_certified_tree() {
+    let _guard = match TimerGuard::new(TaskType::ReloadCertifiedTree) {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    if mutate_state(|s| s.reload_certified_tree_batch(CERTIFIED_TREE_RELOAD_BATCH_SIZE)) {
+        schedule_certified_tree_reload();
+    } else {
+        log!(INFO, "[Rebuild Indexes] Certified tree is reloaded");
+    }
+}
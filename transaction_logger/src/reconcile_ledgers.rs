@@ -0,0 +1,239 @@
+use candid::Nat;
+use ic_canister_log::log;
+use icrc_ledger_types::icrc3::transactions::Transaction;
+
+use crate::{
+    guard::TimerGuard,
+    icrc_ledger_client::IcrcLedgerClient,
+    logs::{DEBUG, INFO},
+    numeric::Erc20TokenAmount,
+    state::{
+        checked_nat_to_erc20_amount, mutate_state, read_state,
+        types::{
+            ExpectedLedgerOperation, LedgerAccount, LedgerOperation, ReconciliationMismatch,
+            ReconciliationStatus,
+        },
+    },
+};
+
+// Maximum number of blocks fetched in one run, the remaining transactions are checked in the next runs
+pub const MAX_RECONCILIATIONS_PER_RUN: usize = 50;
+
+// Checks minted deposits and withdrawal burns against the blocks of their icrc ledgers,
+// amount and account mismatches are kept for the reconciliation report
+pub async fn reconcile_ledgers() {
+    // Issue a timer guard
+    let _guard = match TimerGuard::new(crate::guard::TaskType::ReconcileLedgers) {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    let pending = read_state(|s| s.pending_reconciliations(MAX_RECONCILIATIONS_PER_RUN));
+
+    for (key, expected) in pending {
+        let client = IcrcLedgerClient::new(expected.ledger_id);
+        let transaction = match client.get_transaction(expected.block_index).await {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                log!(
+                    DEBUG,
+                    "[Reconcile Ledgers] Failed to get block {} of {}: {:?}",
+                    expected.block_index,
+                    expected.ledger_id,
+                    err
+                );
+                continue;
+            }
+        };
+
+        let status = reconcile(&expected, transaction.as_ref());
+        if status != ReconciliationStatus::Matched {
+            log!(
+                INFO,
+                "[Reconcile Ledgers] Transaction {:?} does not match block {} of {}: {:?}",
+                key,
+                expected.block_index,
+                expected.ledger_id,
+                status
+            );
+        }
+
+        mutate_state(|s| s.record_reconciliation(key, expected, status, ic_cdk::api::time()));
+    }
+}
+
+// Compares the expected operation with the ledger transaction
+pub fn reconcile(
+    expected: &ExpectedLedgerOperation,
+    transaction: Option<&Transaction>,
+) -> ReconciliationStatus {
+    let Some(transaction) = transaction else {
+        return ReconciliationStatus::BlockNotFound;
+    };
+
+    let found = match expected.operation {
+        LedgerOperation::Mint => transaction
+            .mint
+            .as_ref()
+            .map(|mint| (mint.amount.clone(), mint.to)),
+        LedgerOperation::Burn => transaction
+            .burn
+            .as_ref()
+            .map(|burn| (burn.amount.clone(), burn.from)),
+    };
+
+    reconcile_operation(
+        expected,
+        &transaction.kind,
+        found.map(|(amount, account)| {
+            (
+                amount,
+                LedgerAccount {
+                    owner: account.owner,
+                    subaccount: account.subaccount,
+                },
+            )
+        }),
+    )
+}
+
+// Compares the expected operation with the amount and account of the block,
+// found is None if the block is not of the expected operation
+fn reconcile_operation(
+    expected: &ExpectedLedgerOperation,
+    kind: &str,
+    found: Option<(Nat, LedgerAccount)>,
+) -> ReconciliationStatus {
+    let Some((amount, found_account)) = found else {
+        return ReconciliationStatus::Mismatch(vec![ReconciliationMismatch::Operation {
+            expected: expected.operation,
+            found: kind.to_string(),
+        }]);
+    };
+
+    let mut mismatches = vec![];
+
+    // Amounts that do not fit in a U256 can not match
+    let found_amount = checked_nat_to_erc20_amount(amount).unwrap_or(Erc20TokenAmount::MAX);
+    if found_amount != expected.amount {
+        mismatches.push(ReconciliationMismatch::Amount {
+            expected: expected.amount,
+            found: found_amount,
+        });
+    }
+
+    if !found_account.is_same_account(&expected.account) {
+        mismatches.push(ReconciliationMismatch::Account {
+            expected: expected.account.clone(),
+            found: found_account,
+        });
+    }
+
+    if mismatches.is_empty() {
+        ReconciliationStatus::Matched
+    } else {
+        ReconciliationStatus::Mismatch(mismatches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::{Nat, Principal};
+
+    use crate::{
+        numeric::Erc20TokenAmount,
+        reconcile_ledgers::{reconcile, reconcile_operation},
+        state::types::{
+            ExpectedLedgerOperation, LedgerAccount, LedgerOperation, ReconciliationMismatch,
+            ReconciliationStatus,
+        },
+    };
+
+    fn account(subaccount: Option<[u8; 32]>) -> LedgerAccount {
+        LedgerAccount {
+            owner: Principal::from_slice(&[1]),
+            subaccount,
+        }
+    }
+
+    fn expected_mint() -> ExpectedLedgerOperation {
+        ExpectedLedgerOperation {
+            ledger_id: Principal::from_slice(&[2]),
+            block_index: 10,
+            operation: LedgerOperation::Mint,
+            amount: Erc20TokenAmount::from(1_000_u64),
+            account: account(None),
+        }
+    }
+
+    #[test]
+    fn test_matched_block() {
+        assert_eq!(
+            reconcile_operation(
+                &expected_mint(),
+                "mint",
+                Some((Nat::from(1_000_u64), account(None)))
+            ),
+            ReconciliationStatus::Matched
+        );
+    }
+
+    #[test]
+    fn test_amount_mismatch() {
+        assert_eq!(
+            reconcile_operation(
+                &expected_mint(),
+                "mint",
+                Some((Nat::from(999_u64), account(None)))
+            ),
+            ReconciliationStatus::Mismatch(vec![ReconciliationMismatch::Amount {
+                expected: Erc20TokenAmount::from(1_000_u64),
+                found: Erc20TokenAmount::from(999_u64),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_account_mismatch() {
+        // None and the zero subaccount are the same account
+        assert_eq!(
+            reconcile_operation(
+                &expected_mint(),
+                "mint",
+                Some((Nat::from(1_000_u64), account(Some([0; 32]))))
+            ),
+            ReconciliationStatus::Matched
+        );
+
+        assert_eq!(
+            reconcile_operation(
+                &expected_mint(),
+                "mint",
+                Some((Nat::from(1_000_u64), account(Some([1; 32]))))
+            ),
+            ReconciliationStatus::Mismatch(vec![ReconciliationMismatch::Account {
+                expected: account(None),
+                found: account(Some([1; 32])),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_wrong_operation() {
+        assert_eq!(
+            reconcile_operation(&expected_mint(), "transfer", None),
+            ReconciliationStatus::Mismatch(vec![ReconciliationMismatch::Operation {
+                expected: LedgerOperation::Mint,
+                found: "transfer".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_block_not_found() {
+        assert_eq!(
+            reconcile(&expected_mint(), None),
+            ReconciliationStatus::BlockNotFound
+        );
+    }
+}
@@ -3,13 +3,36 @@ use ic_canister_log::log;
 use crate::{
     guard::TimerGuard,
     logs::INFO,
-    state::{mutate_state, read_state},
+    state::{mutate_state, read_state, types::UnverifiedTxConfig},
 };
 
-// If the transaction time is older than one hour and it is still unverified,
-// Tx should be removed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnverifiedTxAction {
+    Expire,
+    Remove,
+}
+
+// Action for an unverified transaction submitted at tx_time, expired_at is None if it did not expire yet
+pub fn unverified_tx_action(
+    tx_time: u64,
+    expired_at: Option<u64>,
+    config: &UnverifiedTxConfig,
+    now: u64,
+) -> Option<UnverifiedTxAction> {
+    match expired_at {
+        None if tx_time.saturating_add(config.expiry_timeout) < now => {
+            Some(UnverifiedTxAction::Expire)
+        }
+        Some(expired_at) if expired_at.saturating_add(config.retention_period) < now => {
+            Some(UnverifiedTxAction::Remove)
+        }
+        _ => None,
+    }
+}
 
-const ONE_HOUR_IN_NS: u64 = 3_600_000_000_000;
+// If the transaction is older than the expiry timeout and it is still unverified,
+// Tx is marked as expired and kept in case the minter event arrives later.
+// Expired transactions are removed after the retention period
 
 pub fn remove_unverified_tx() {
     // Issue a timer guard
@@ -18,32 +41,151 @@ pub fn remove_unverified_tx() {
         Err(_) => return,
     };
 
+    let config = read_state(|s| s.get_unverified_tx_config());
+
     let all_unverified_evm_to_icp_tx = read_state(|s| s.all_unverified_evm_to_icp());
 
-    log!(INFO, "[Remove Unverified Tx] Removing unverified tx");
+    log!(INFO, "[Remove Unverified Tx] Expiring unverified tx");
 
     let current_time = ic_cdk::api::time();
-    for (identifier, tx_time) in all_unverified_evm_to_icp_tx {
-        if tx_time + ONE_HOUR_IN_NS < current_time {
-            log!(
-                INFO,
-                "[Remove Unverified Tx] Removing unverified tx with identifier {:?}",
-                identifier,
-            );
-            mutate_state(|s| s.remove_unverified_evm_to_icp(&identifier))
+    for (identifier, tx_time, expired_at) in all_unverified_evm_to_icp_tx {
+        match unverified_tx_action(tx_time, expired_at, &config, current_time) {
+            Some(UnverifiedTxAction::Expire) => {
+                log!(
+                    INFO,
+                    "[Remove Unverified Tx] Expiring unverified tx with identifier {:?}",
+                    identifier,
+                );
+                mutate_state(|s| s.expire_unverified_evm_to_icp(&identifier, current_time))
+            }
+            Some(UnverifiedTxAction::Remove) => {
+                log!(
+                    INFO,
+                    "[Remove Unverified Tx] Removing expired tx with identifier {:?}",
+                    identifier,
+                );
+                mutate_state(|s| s.remove_unverified_evm_to_icp(&identifier))
+            }
+            None => {}
         }
     }
 
     let all_unverified_icp_to_evm_tx = read_state(|s| s.all_unverified_icp_to_evm());
 
-    for (identifier, tx_time) in all_unverified_icp_to_evm_tx {
-        if tx_time + ONE_HOUR_IN_NS < current_time {
-            log!(
-                INFO,
-                "[Remove Unverified Tx] Removing unverified tx with identifier {:?} ",
-                identifier,
-            );
-            mutate_state(|s| s.remove_unverified_icp_to_evm(&identifier))
+    for (identifier, tx_time, expired_at) in all_unverified_icp_to_evm_tx {
+        match unverified_tx_action(tx_time, expired_at, &config, current_time) {
+            Some(UnverifiedTxAction::Expire) => {
+                log!(
+                    INFO,
+                    "[Remove Unverified Tx] Expiring unverified tx with identifier {:?}",
+                    identifier,
+                );
+                mutate_state(|s| s.expire_unverified_icp_to_evm(&identifier, current_time))
+            }
+            Some(UnverifiedTxAction::Remove) => {
+                log!(
+                    INFO,
+                    "[Remove Unverified Tx] Removing expired tx with identifier {:?}",
+                    identifier,
+                );
+                mutate_state(|s| s.remove_unverified_icp_to_evm(&identifier))
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use crate::numeric::Erc20TokenAmount;
+    use crate::state::types::{ChainId, EvmToIcpStatus, EvmToIcpTx, Operator};
+    use candid::Principal;
+
+    const CONFIG: UnverifiedTxConfig = UnverifiedTxConfig {
+        expiry_timeout: 100,
+        retention_period: 1_000,
+    };
+
+    fn submitted_deposit(time: u64) -> EvmToIcpTx {
+        EvmToIcpTx {
+            from_address: Address::ZERO,
+            transaction_hash: "0x1".to_string(),
+            value: Erc20TokenAmount::from(1_000_u64),
+            ledger_mint_index: None,
+            block_number: None,
+            actual_received: None,
+            principal: Principal::anonymous(),
+            subaccount: None,
+            chain_id: ChainId(1),
+            total_gas_spent: None,
+            erc20_contract_address: Address::ZERO,
+            icrc_ledger_id: None,
+            status: EvmToIcpStatus::PendingVerification,
+            verified: false,
+            time,
+            operator: Operator::AppicMinter,
+            status_updated_at: None,
         }
     }
+
+    #[test]
+    fn test_expire_revive_and_purge() {
+        let tx = submitted_deposit(10);
+
+        // Kept until the expiry timeout passed
+        assert_eq!(tx.expired_at(), None);
+        assert_eq!(
+            unverified_tx_action(tx.time, tx.expired_at(), &CONFIG, 110),
+            None
+        );
+        assert_eq!(
+            unverified_tx_action(tx.time, tx.expired_at(), &CONFIG, 111),
+            Some(UnverifiedTxAction::Expire)
+        );
+
+        let expired = EvmToIcpTx {
+            status: EvmToIcpStatus::Expired,
+            status_updated_at: Some(111),
+            ..tx.clone()
+        };
+        assert_eq!(expired.expired_at(), Some(111));
+
+        // Kept during the retention period, in case the minter event arrives late
+        assert_eq!(
+            unverified_tx_action(expired.time, expired.expired_at(), &CONFIG, 1_111),
+            None
+        );
+
+        // The minter event revives the transaction, verified transactions are never removed
+        let revived = EvmToIcpTx {
+            status: EvmToIcpStatus::Accepted,
+            verified: true,
+            status_updated_at: Some(500),
+            ..expired.clone()
+        };
+        assert_eq!(revived.expired_at(), None);
+
+        // Without a minter event the transaction is removed after the retention period
+        assert_eq!(
+            unverified_tx_action(expired.time, expired.expired_at(), &CONFIG, 1_112),
+            Some(UnverifiedTxAction::Remove)
+        );
+    }
+
+    #[test]
+    fn test_expired_at_falls_back_to_submission_time() {
+        let expired = EvmToIcpTx {
+            status: EvmToIcpStatus::Expired,
+            status_updated_at: None,
+            ..submitted_deposit(10)
+        };
+
+        assert_eq!(expired.expired_at(), Some(10));
+        assert_eq!(
+            unverified_tx_action(expired.time, expired.expired_at(), &CONFIG, 1_011),
+            Some(UnverifiedTxAction::Remove)
+        );
+    }
 }
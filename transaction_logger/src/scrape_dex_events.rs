@@ -1,15 +1,20 @@
+use async_trait::async_trait;
+use candid::Principal;
+
 use crate::{
     appic_dex_client::DexClient,
-    appic_dex_types::GetEventsResult,
+    appic_dex_types::{CandidEventType, GetEventsResult},
+    event_source::{scrape_source, EventCursor, EventSource},
     guard::TimerGuard,
-    logs::{DEBUG, INFO},
-    state::{mutate_state, read_state},
+    minter_client::CallError,
+    notifications::deliver_notifications,
+    state::{
+        dex::types::DexAction,
+        mutate_state, read_state,
+        types::{DexAdapterKind, DexSource},
+    },
 };
 
-use ic_canister_log::log;
-
-const MAX_EVENTS_PER_RESPONSE: u64 = 100;
-
 pub const NATIVE_ERC20_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
 pub async fn scrape_dex_events() {
@@ -19,122 +24,91 @@ pub async fn scrape_dex_events() {
         Err(_) => return,
     };
 
-    let dex_info = read_state(|s| s.dex_info.get().clone());
+    // Scrape only enabled dex sources
+    let dex_sources = read_state(|s| s.get_enabled_dex_sources());
 
-    let dex_clinet = DexClient::new(dex_info.id);
+    for dex_source in dex_sources {
+        match dex_source.adapter {
+            DexAdapterKind::AppicDexV1 => {
+                scrape_source(&AppicDexEventSource::from(&dex_source)).await
+            }
+        }
+    }
+}
 
-    // Get the latest event count to update last_observed_event;
-    // -1 since the starting index in 0 not 1
-    let latest_event_count = dex_clinet.get_total_events_count().await - 1;
+pub struct AppicDexEventSource {
+    dex_id: Principal,
+    label: Option<String>,
+    client: DexClient,
+}
 
-    // Check if the previous last_observed_event is greater or equal to latest one;
-    // If yes there should be no scraping for events and last_observed_event should not be updated
-    if dex_info.last_observed_event >= latest_event_count {
-        return;
-    };
+impl From<&DexSource> for AppicDexEventSource {
+    fn from(value: &DexSource) -> Self {
+        Self {
+            dex_id: value.id,
+            label: value.label.clone(),
+            client: DexClient::new(value.id),
+        }
+    }
+}
 
-    // Updating last observed event count
-    mutate_state(|s| s.update_last_observed_dex_event(latest_event_count));
+#[async_trait]
+impl EventSource for AppicDexEventSource {
+    type Page = GetEventsResult;
 
-    let last_scraped_event = dex_info.last_scraped_event;
+    fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{} dex ({})", label, self.dex_id),
+            None => format!("dex {}", self.dex_id),
+        }
+    }
 
-    // Scraping logs between specified ranges
-    // MAX_EVENT_RESPONSE= 100 so the log range should not be more than 100
-    // min((last_observed_event - last_scraped_event),100) will be the specified range
-    // If last_observed_event - last_scraped_event contains more than 100, the event scaping will be divided into multiple calls
+    fn cursor(&self) -> Option<EventCursor> {
+        read_state(|s| s.dex_sources.get(&self.dex_id)).map(|source| EventCursor {
+            last_observed_event: source.last_observed_event,
+            next_event_to_scrape: source.next_event_to_scrape,
+        })
+    }
 
-    scrape_events_range(
-        latest_event_count,
-        last_scraped_event,
-        MAX_EVENTS_PER_RESPONSE,
-        &dex_clinet,
-    )
-    .await
-}
+    fn set_last_observed_event(&self, event: u64) {
+        mutate_state(|s| s.update_last_observed_dex_event(&self.dex_id, event));
+    }
 
-pub async fn scrape_events_range(
-    last_observed_event: u64,
-    last_scraped_event: u64,
-    max_event_scrap: u64,
-    dex_client: &DexClient,
-) {
-    if last_scraped_event >= last_observed_event {
-        log!(
-            INFO,
-            "[Scraping Events DEX] No events to scrape. All events are already processed."
-        );
-        return;
+    fn set_next_event_to_scrape(&self, event: u64) {
+        mutate_state(|s| s.update_next_dex_event_to_scrape(&self.dex_id, event));
     }
 
-    let mut start = last_scraped_event; // Start from the next event after the last scraped
-    let end = last_observed_event; // Scrape up to the last observed event
-    const MAX_RETRIES: u32 = 5; // Maximum retry attempts
-
-    while start <= end {
-        let chunk_end = std::cmp::min(start + max_event_scrap - 1, end); // Define the range limit
-        log!(
-            INFO,
-            "[Scraping Events DEX] Scraping events from {} to {}",
-            start,
-            chunk_end,
-        );
-
-        let mut attempts = 0; // Initialize retry counter
-        let mut success = false; // Track success status
-
-        while attempts < MAX_RETRIES {
-            let events_result = dex_client.scrape_events(start, 100).await;
-            match events_result {
-                Ok(events) => {
-                    log!(INFO, "[Scraping Events] Received Event {:?}", events);
-
-                    apply_dex_state_transition(events);
-                    mutate_state(|s| s.update_last_scraped_dex_event(chunk_end));
-                    success = true; // Mark as successful
-                    break; // Exit retry loop
-                }
-                Err(err) => {
-                    attempts += 1;
-                    log!(
-                        DEBUG,
-                        "[Scraping Events DEX] Error scraping events from {} to {}: {:?}. Retrying... ({}/{})",
-                        start,
-                        chunk_end,
-                        err,
-                        attempts,
-                        MAX_RETRIES
-                    );
-
-                    if attempts >= MAX_RETRIES {
-                        log!(
-                            DEBUG,
-                            "[Scraping Events DEX] Failed to scrape events from {} to {} after {} retries. Skipping...",
-                            start,
-                            chunk_end,
-                            MAX_RETRIES
-                        );
-                    }
-                }
-            }
-        }
+    async fn total_events_count(&self) -> Result<u64, CallError> {
+        self.client.get_total_events_count().await
+    }
 
-        if success {
-            // Move to the next range only if scraping was successful
-            start = chunk_end + 1;
-        } else {
-            // If scraping ultimately fails, break to prevent an infinite loop
-            log!(
-                DEBUG,
-                "[Scraping Events DEX] Aborting further scraping due to repeated failures."
-            );
-            break;
-        }
+    async fn fetch_page(&self, start: u64, length: u64) -> Result<GetEventsResult, CallError> {
+        self.client.scrape_events(start, length).await
+    }
+
+    fn apply_page(&self, page: GetEventsResult) {
+        apply_dex_state_transition(page, self.dex_id);
     }
 }
 
-pub fn apply_dex_state_transition(events: GetEventsResult) {
+// Actions are tagged with the dex that emitted them
+pub fn apply_dex_state_transition(events: GetEventsResult, dex_id: Principal) {
+    let now = ic_cdk::api::time();
     for event in events.events.into_iter() {
         let principal = event.payload.get_principal();
-        mutate_state(|s| s.record_dex_action_for_principal(principal, event.into()))
+        let is_swap = matches!(event.payload, CandidEventType::Swap { .. });
+        mutate_state(|s| {
+            s.record_dex_action_for_principal(
+                principal,
+                DexAction::from(event).with_dex_id(dex_id),
+                now,
+            );
+            if is_swap {
+                s.link_swaps_to_bridge_txs(principal);
+            }
+        })
     }
+
+    // Deliver the new dex actions to the subscribed canisters
+    ic_cdk::spawn(deliver_notifications());
 }
@@ -1,17 +1,23 @@
 use std::str::FromStr;
 
+use async_trait::async_trait;
+use candid::{Nat, Principal};
+
 use crate::{
+    event_source::{scrape_source, EventCursor, EventSource},
     guard::TimerGuard,
-    logs::{DEBUG, INFO},
     minter_client::{
         appic_minter_types::{InitArg, UpgradeArg},
-        MinterClient,
+        CallError, MinterClient,
     },
+    notifications::deliver_notifications,
     state::{
         mutate_state, nat_to_erc20_amount, nat_to_ledger_burn_index, nat_to_ledger_mint_index,
         read_state,
         types::{
-            ChainId, Erc20Identifier, EvmToIcpTxIdentifier, IcpToEvmIdentifier, MinterKey, Operator,
+            BridgePair, ChainId, Erc20Identifier, Erc20TwinLedgerSuiteStatus, EvmToIcpTxIdentifier,
+            IcpToEvmIdentifier, IcpToEvmReimbursementReason, MinterKey, Operator,
+            PendingBridgePairKey,
         },
     },
     update_bridge_pairs::update_bridge_pairs,
@@ -20,10 +26,8 @@ use crate::{
 
 use crate::address::Address;
 use crate::minter_client::appic_minter_types::events::EventPayload as AppicEventPayload;
-use ic_canister_log::log;
 
 use crate::minter_client::event_conversion::Events;
-const MAX_EVENTS_PER_RESPONSE: u64 = 100;
 
 pub const NATIVE_ERC20_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
@@ -38,122 +42,152 @@ pub async fn scrape_events() {
 
     // Scrape only active minters
     for (minter_key, minter) in minters.iter() {
-        let minter_client = MinterClient::from(minter);
-
-        // Get the latest event count to update last_observed_event;
-        // -1 since the starting index in 0 not 1
-        let latest_event_count = minter_client.get_total_events_count().await - 1;
-
-        // Check if the previous last_observed_event is greater or equal to latest one;
-        // If yes there should be no scraping for events and last_observed_event should not be updated
-        if minter.last_observed_event >= latest_event_count {
-            continue;
+        let source = MinterEventSource {
+            minter_key: minter_key.clone(),
+            client: MinterClient::from(minter),
         };
 
-        // Updating last observed event count
-        mutate_state(|s| s.update_last_observed_event(minter_key, latest_event_count));
+        scrape_source(&source).await
+    }
+}
 
-        let last_scraped_event = minter.last_scraped_event;
+pub struct MinterEventSource {
+    minter_key: MinterKey,
+    client: MinterClient,
+}
 
-        // Scraping logs between specified ranges
-        // MAX_EVENT_RESPONSE= 100 so the log range should not be more than 100
-        // min((last_observed_event - last_scraped_event),100) will be the specified range
-        // If last_observed_event - last_scraped_event contains more than 100, the event scaping will be divided into multiple calls
+#[async_trait]
+impl EventSource for MinterEventSource {
+    type Page = Events;
 
-        scrape_events_range(
-            latest_event_count,
-            last_scraped_event,
-            MAX_EVENTS_PER_RESPONSE,
-            &minter_client,
-            minter_key,
+    fn name(&self) -> String {
+        format!(
+            "{:?} minter on chain {}",
+            self.minter_key.operator(),
+            self.minter_key.chain_id().0
         )
-        .await
     }
-}
 
-pub async fn scrape_events_range(
-    last_observed_event: u64,
-    last_scraped_event: u64,
-    max_event_scrap: u64,
-    minter_client: &MinterClient,
-    minter_key: &MinterKey,
-) {
-    if last_scraped_event >= last_observed_event {
-        log!(
-            INFO,
-            "[Scraping Events] No events to scrape. All events are already processed."
-        );
-        return;
+    fn cursor(&self) -> Option<EventCursor> {
+        read_state(|s| s.minters.get(&self.minter_key)).map(|minter| EventCursor {
+            last_observed_event: minter.last_observed_event,
+            next_event_to_scrape: minter.next_event_to_scrape,
+        })
     }
 
-    let mut start = last_scraped_event; // Start from the next event after the last scraped
-    let end = last_observed_event; // Scrape up to the last observed event
-    const MAX_RETRIES: u32 = 5; // Maximum retry attempts
-
-    while start <= end {
-        let chunk_end = std::cmp::min(start + max_event_scrap - 1, end); // Define the range limit
-        log!(
-            INFO,
-            "[Scraping Events] Scraping events from {} to {} minter {:?}",
-            start,
-            chunk_end,
-            minter_key
-        );
+    fn set_last_observed_event(&self, event: u64) {
+        mutate_state(|s| s.update_last_observed_event(&self.minter_key, event));
+    }
 
-        let mut attempts = 0; // Initialize retry counter
-        let mut success = false; // Track success status
+    fn set_next_event_to_scrape(&self, event: u64) {
+        mutate_state(|s| s.update_next_event_to_scrape(&self.minter_key, event));
+    }
 
-        while attempts < MAX_RETRIES {
-            let events_result = minter_client.scrape_events(start, 100).await;
-            match events_result {
-                Ok(events) => {
-                    log!(INFO, "[Scraping Events] Received Event {:?}", events);
+    async fn total_events_count(&self) -> Result<u64, CallError> {
+        self.client.get_total_events_count().await
+    }
 
-                    apply_state_transition(events, minter_key.operator(), minter_key.chain_id());
-                    mutate_state(|s| s.update_last_scraped_event(minter_key, chunk_end));
-                    success = true; // Mark as successful
-                    break; // Exit retry loop
-                }
-                Err(err) => {
-                    attempts += 1;
-                    log!(
-                        DEBUG,
-                        "[Scraping Events] Error scraping events from {} to {}: {:?}. Retrying... ({}/{})",
-                        start,
-                        chunk_end,
-                        err,
-                        attempts,
-                        MAX_RETRIES
-                    );
+    async fn fetch_page(&self, start: u64, length: u64) -> Result<Events, CallError> {
+        self.client.scrape_events(start, length).await
+    }
 
-                    if attempts >= MAX_RETRIES {
-                        log!(
-                            DEBUG,
-                            "[Scraping Events] Failed to scrape events from {} to {} after {} retries. Skipping...",
-                            start,
-                            chunk_end,
-                            MAX_RETRIES
-                        );
-                    }
-                }
-            }
-        }
+    fn apply_page(&self, page: Events) {
+        apply_state_transition(page, self.minter_key.operator(), self.minter_key.chain_id());
+    }
+}
 
-        if success {
-            // Move to the next range only if scraping was successful
-            start = chunk_end + 1;
-        } else {
-            // If scraping ultimately fails, break to prevent an infinite loop
-            log!(
-                DEBUG,
-                "[Scraping Events] Aborting further scraping due to repeated failures."
-            );
-            break;
-        }
+// Reimbursement of a withdrawal reported by a minter event
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReimbursementEvent {
+    pub identifier: IcpToEvmIdentifier,
+    pub reason: IcpToEvmReimbursementReason,
+    pub reimbursed_amount: Nat,
+    // None if the native ledger is reimbursed
+    pub ledger_id: Option<Principal>,
+    // None for failed requests, the reimbursement is minted in a later event
+    pub reimbursed_in_block: Option<Nat>,
+}
+
+pub fn reimbursement_event(
+    payload: &AppicEventPayload,
+    chain_id: ChainId,
+) -> Option<ReimbursementEvent> {
+    let identifier = |withdrawal_id: &Nat| {
+        IcpToEvmIdentifier::new(nat_to_ledger_burn_index(withdrawal_id), chain_id)
+    };
+
+    match payload {
+        AppicEventPayload::ReimbursedNativeWithdrawal {
+            reimbursed_in_block,
+            withdrawal_id,
+            reimbursed_amount,
+            transaction_hash: _,
+        } => Some(ReimbursementEvent {
+            identifier: identifier(withdrawal_id),
+            reason: IcpToEvmReimbursementReason::FailedTransaction,
+            reimbursed_amount: reimbursed_amount.clone(),
+            ledger_id: None,
+            reimbursed_in_block: Some(reimbursed_in_block.clone()),
+        }),
+        AppicEventPayload::ReimbursedErc20Withdrawal {
+            withdrawal_id,
+            burn_in_block: _,
+            reimbursed_in_block,
+            ledger_id,
+            reimbursed_amount,
+            transaction_hash: _,
+        } => Some(ReimbursementEvent {
+            identifier: identifier(withdrawal_id),
+            reason: IcpToEvmReimbursementReason::FailedTransaction,
+            reimbursed_amount: reimbursed_amount.clone(),
+            ledger_id: Some(*ledger_id),
+            reimbursed_in_block: Some(reimbursed_in_block.clone()),
+        }),
+        AppicEventPayload::FailedErc20WithdrawalRequest {
+            withdrawal_id,
+            reimbursed_amount,
+            to: _,
+            to_subaccount: _,
+        } => Some(ReimbursementEvent {
+            identifier: identifier(withdrawal_id),
+            reason: IcpToEvmReimbursementReason::FailedErc20Burn,
+            reimbursed_amount: reimbursed_amount.clone(),
+            ledger_id: None,
+            reimbursed_in_block: None,
+        }),
+        AppicEventPayload::FailedIcrcLockRequest {
+            withdrawal_id,
+            reimbursed_amount,
+            to: _,
+            to_subaccount: _,
+        } => Some(ReimbursementEvent {
+            identifier: identifier(withdrawal_id),
+            reason: IcpToEvmReimbursementReason::FailedIcrcLock,
+            reimbursed_amount: reimbursed_amount.clone(),
+            ledger_id: None,
+            reimbursed_in_block: None,
+        }),
+        AppicEventPayload::ReimbursedIcrcWrap {
+            native_ledger_burn_index,
+            lock_in_block: _,
+            reimbursed_in_block,
+            reimbursed_icrc_token,
+            reimbursed_amount,
+            transaction_hash: _,
+            transfer_fee: _,
+        } => Some(ReimbursementEvent {
+            identifier: identifier(native_ledger_burn_index),
+            reason: IcpToEvmReimbursementReason::FailedTransaction,
+            reimbursed_amount: reimbursed_amount.clone(),
+            ledger_id: Some(*reimbursed_icrc_token),
+            reimbursed_in_block: Some(reimbursed_in_block.clone()),
+        }),
+        _ => None,
     }
 }
 
 fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId) {
+    let now = ic_cdk::api::time();
     for event in events.events.into_iter() {
         // Applying the state transition
         let is_new_twin_added = match event.payload {
@@ -161,6 +195,26 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
             _ => false,
         };
 
+        // Reimbursements are reported by several events, the native ledger is reimbursed
+        // if the event does not specify the ledger
+        if let Some(reimbursement) = reimbursement_event(&event.payload, chain_id) {
+            mutate_state(|s| {
+                let ledger_id = reimbursement.ledger_id.or_else(|| {
+                    s.get_icrc_twin_for_erc20(&Erc20Identifier(Address::ZERO, chain_id), &operator)
+                });
+                s.record_reimbursed_icp_to_evm(
+                    reimbursement.identifier,
+                    reimbursement.reason,
+                    reimbursement.reimbursed_amount,
+                    ledger_id,
+                    reimbursement.reimbursed_in_block,
+                    event.timestamp,
+                    now,
+                )
+            });
+            continue;
+        }
+
         mutate_state(|s| match event.payload {
             AppicEventPayload::Init(InitArg {
                 evm_network: _,
@@ -189,6 +243,7 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                     native_symbol,
                     nat_to_erc20_amount(native_ledger_transfer_fee),
                     chain_id,
+                    now,
                 );
             }
             AppicEventPayload::Upgrade(UpgradeArg {
@@ -230,6 +285,7 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 chain_id,
                 operator,
                 event.timestamp,
+                now,
             ),
             AppicEventPayload::AcceptedErc20Deposit {
                 transaction_hash,
@@ -252,6 +308,7 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 chain_id,
                 operator,
                 event.timestamp,
+                now,
             ),
             AppicEventPayload::InvalidDeposit {
                 event_source,
@@ -259,6 +316,8 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
             } => s.record_invalid_evm_to_icp(
                 EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
                 reason,
+                event.timestamp,
+                now,
             ),
             AppicEventPayload::MintedNative {
                 event_source,
@@ -267,6 +326,8 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
                 nat_to_ledger_mint_index(&mint_block_index),
                 None,
+                event.timestamp,
+                now,
             ),
             AppicEventPayload::SyncedToBlock { .. } => {}
             AppicEventPayload::AcceptedNativeWithdrawalRequest {
@@ -294,39 +355,35 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 event.timestamp,
                 l1_fee,
                 withdrawal_fee,
+                now,
             ),
             AppicEventPayload::CreatedTransaction { withdrawal_id, .. } => s
-                .record_created_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
+                .record_created_icp_to_evm(
+                    IcpToEvmIdentifier::new(nat_to_ledger_burn_index(&withdrawal_id), chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::SignedTransaction { withdrawal_id, .. } => s
-                .record_signed_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
+                .record_signed_icp_to_evm(
+                    IcpToEvmIdentifier::new(nat_to_ledger_burn_index(&withdrawal_id), chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::ReplacedTransaction { withdrawal_id, .. } => s
-                .record_replaced_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
+                .record_replaced_icp_to_evm(
+                    IcpToEvmIdentifier::new(nat_to_ledger_burn_index(&withdrawal_id), chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::FinalizedTransaction {
                 withdrawal_id,
                 transaction_receipt,
             } => s.record_finalized_icp_to_evm(
                 IcpToEvmIdentifier::new(nat_to_ledger_burn_index(&withdrawal_id), chain_id),
                 transaction_receipt,
+                event.timestamp,
+                now,
             ),
-            AppicEventPayload::ReimbursedNativeWithdrawal { withdrawal_id, .. } => s
-                .record_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
-            AppicEventPayload::ReimbursedErc20Withdrawal { withdrawal_id, .. } => s
-                .record_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
             AppicEventPayload::SkippedBlock { .. } => {}
             AppicEventPayload::AddedErc20Token {
                 chain_id: _,
@@ -334,12 +391,49 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 erc20_token_symbol,
                 erc20_ledger_id,
             } => {
+                let address = Address::from_str(&address).unwrap();
+                let erc20_identifier = Erc20Identifier::new(&address, chain_id);
+
+                // The minter only adds tokens whose ledger suite is installed,
+                // twin ledger suite requests are only submitted to the appic lsm
+                if operator == Operator::AppicMinter {
+                    s.update_twin_ledger_request_status(
+                        erc20_identifier.clone(),
+                        Erc20TwinLedgerSuiteStatus::Installed,
+                        Some(erc20_ledger_id),
+                        Some(erc20_token_symbol.clone()),
+                        event.timestamp,
+                    );
+                }
+
                 s.record_icp_token_added_to_minter_by_lsm(
-                    Address::from_str(&address).unwrap(),
+                    address,
                     erc20_ledger_id,
                     erc20_token_symbol,
                     chain_id,
+                    now,
                 );
+
+                // Record the bridge pair right away instead of waiting for the next ledger manager poll
+                if let (Some(evm_token), Some(icp_token)) = (
+                    s.get_evm_token_by_identifier(&erc20_identifier),
+                    s.get_icp_token_by_principal(&erc20_ledger_id),
+                ) {
+                    s.remove_pending_bridge_pair(&PendingBridgePairKey(
+                        operator,
+                        erc20_identifier.clone(),
+                    ));
+                    s.record_bridge_pair(
+                        operator,
+                        erc20_identifier,
+                        BridgePair {
+                            icp_token,
+                            evm_token,
+                            removed_at: None,
+                        },
+                        event.timestamp,
+                    );
+                }
             }
             AppicEventPayload::AcceptedErc20WithdrawalRequest {
                 max_transaction_fee,
@@ -374,12 +468,8 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 event.timestamp,
                 l1_fee,
                 withdrawal_fee,
+                now,
             ),
-            AppicEventPayload::FailedErc20WithdrawalRequest { withdrawal_id, .. } => s
-                .record_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                    nat_to_ledger_burn_index(&withdrawal_id),
-                    chain_id,
-                )),
             AppicEventPayload::MintedErc20 {
                 event_source,
                 mint_block_index,
@@ -388,17 +478,21 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
                 nat_to_ledger_mint_index(&mint_block_index),
                 None,
+                event.timestamp,
+                now,
             ),
             AppicEventPayload::QuarantinedDeposit { event_source } => s
-                .record_quarantined_evm_to_icp(EvmToIcpTxIdentifier::new(
-                    &event_source.transaction_hash,
-                    chain_id,
-                )),
+                .record_quarantined_evm_to_icp(
+                    EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::QuarantinedReimbursement { index } => s
-                .record_quarantined_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                    index.into(),
-                    chain_id,
-                )),
+                .record_quarantined_reimbursed_icp_to_evm(
+                    IcpToEvmIdentifier::new(index.into(), chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::AcceptedWrappedIcrcBurn {
                 transaction_hash,
                 block_number,
@@ -421,6 +515,7 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 chain_id,
                 operator,
                 event.timestamp,
+                now,
             ),
             AppicEventPayload::InvalidEvent {
                 event_source,
@@ -428,6 +523,8 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
             } => s.record_invalid_evm_to_icp(
                 EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
                 reason,
+                event.timestamp,
+                now,
             ),
             AppicEventPayload::DeployedWrappedIcrcToken {
                 transaction_hash: _,
@@ -441,12 +538,14 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                     &Address::from_str(&deployed_wrapped_erc20).unwrap(),
                     chain_id,
                 ),
+                event.timestamp,
             ),
             AppicEventPayload::QuarantinedRelease { event_source } => s
-                .record_quarantined_evm_to_icp(EvmToIcpTxIdentifier::new(
-                    &event_source.transaction_hash,
-                    chain_id,
-                )),
+                .record_quarantined_evm_to_icp(
+                    EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
+                    event.timestamp,
+                    now,
+                ),
             AppicEventPayload::ReleasedIcrcToken {
                 event_source,
                 release_block_index,
@@ -455,23 +554,9 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
                 EvmToIcpTxIdentifier::new(&event_source.transaction_hash, chain_id),
                 nat_to_ledger_burn_index(&release_block_index),
                 Some(transfer_fee),
+                event.timestamp,
+                now,
             ),
-            AppicEventPayload::FailedIcrcLockRequest {
-                withdrawal_id,
-                reimbursed_amount: _,
-                to: _,
-                to_subaccount: _,
-            } => s.record_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                nat_to_ledger_burn_index(&withdrawal_id),
-                chain_id,
-            )),
-            AppicEventPayload::ReimbursedIcrcWrap {
-                native_ledger_burn_index,
-                ..
-            } => s.record_reimbursed_icp_to_evm(IcpToEvmIdentifier::new(
-                nat_to_ledger_burn_index(&native_ledger_burn_index),
-                chain_id,
-            )),
             _ => {}
         });
         if is_new_twin_added {
@@ -479,4 +564,104 @@ fn apply_state_transition(events: Events, operator: Operator, chain_id: ChainId)
             ic_cdk::spawn(update_bridge_pairs());
         }
     }
+
+    // Deliver the changes of the applied events to the subscribed canisters
+    ic_cdk::spawn(deliver_notifications());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::Erc20TokenAmount;
+    use crate::state::types::IcpToEvmReimbursement;
+
+    fn to_reimbursement(event: ReimbursementEvent) -> IcpToEvmReimbursement {
+        IcpToEvmReimbursement {
+            reason: event.reason,
+            reimbursed_amount: nat_to_erc20_amount(event.reimbursed_amount),
+            ledger_id: event.ledger_id,
+            reimbursed_in_block: event
+                .reimbursed_in_block
+                .map(|block_index| nat_to_ledger_mint_index(&block_index)),
+        }
+    }
+
+    #[test]
+    fn test_failed_request_followed_by_reimbursement() {
+        let chain_id = ChainId(56);
+        let to = Principal::anonymous();
+
+        let failed = reimbursement_event(
+            &AppicEventPayload::FailedErc20WithdrawalRequest {
+                withdrawal_id: Nat::from(7_u64),
+                reimbursed_amount: Nat::from(1_000_u64),
+                to,
+                to_subaccount: None,
+            },
+            chain_id,
+        )
+        .unwrap();
+        assert_eq!(
+            failed,
+            ReimbursementEvent {
+                identifier: IcpToEvmIdentifier::new(7, chain_id),
+                reason: IcpToEvmReimbursementReason::FailedErc20Burn,
+                reimbursed_amount: Nat::from(1_000_u64),
+                ledger_id: None,
+                reimbursed_in_block: None,
+            }
+        );
+
+        let reimbursed = reimbursement_event(
+            &AppicEventPayload::ReimbursedNativeWithdrawal {
+                reimbursed_in_block: Nat::from(42_u64),
+                withdrawal_id: Nat::from(7_u64),
+                reimbursed_amount: Nat::from(990_u64),
+                transaction_hash: None,
+            },
+            chain_id,
+        )
+        .unwrap();
+        assert_eq!(reimbursed.identifier, failed.identifier);
+        assert_eq!(
+            reimbursed.reason,
+            IcpToEvmReimbursementReason::FailedTransaction
+        );
+
+        // The reason of the failed request is kept, the amount and block are taken
+        // from the reimbursement
+        let merged = to_reimbursement(failed).merge(to_reimbursement(reimbursed));
+        assert_eq!(
+            merged,
+            IcpToEvmReimbursement {
+                reason: IcpToEvmReimbursementReason::FailedErc20Burn,
+                reimbursed_amount: Erc20TokenAmount::from(990_u64),
+                ledger_id: None,
+                reimbursed_in_block: Some(42),
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_known_ledger_and_block() {
+        let ledger_id = Principal::from_text("ss2fx-dyaaa-aaaar-qacoq-cai").unwrap();
+        let minted = IcpToEvmReimbursement {
+            reason: IcpToEvmReimbursementReason::FailedIcrcLock,
+            reimbursed_amount: Erc20TokenAmount::from(10_u64),
+            ledger_id: Some(ledger_id),
+            reimbursed_in_block: Some(5),
+        };
+        let newer = IcpToEvmReimbursement {
+            reason: IcpToEvmReimbursementReason::FailedTransaction,
+            reimbursed_amount: Erc20TokenAmount::from(8_u64),
+            ledger_id: None,
+            reimbursed_in_block: None,
+        };
+
+        let merged = minted.merge(newer);
+        assert_eq!(merged.reason, IcpToEvmReimbursementReason::FailedIcrcLock);
+        assert_eq!(merged.reimbursed_amount, Erc20TokenAmount::from(8_u64));
+        assert_eq!(merged.ledger_id, Some(ledger_id));
+        assert_eq!(merged.reimbursed_in_block, Some(5));
+    }
 }
@@ -1,19 +1,50 @@
+use crate::add_evm_tokens::merge_bundled_evm_token;
 use crate::address::Address;
+use crate::certification::{
+    certified_hash, certify_bridge_pairs, certify_if_changed, certify_leaf, certify_tip,
+    certify_token, certify_tokens, dex_action_key, evm_token_key, icp_token_key,
+    leaves_reload_cursor, reload_leaves, start_leaves_reload, tx_key, uncertify_leaf,
+    CertifiedTxKind,
+};
+use crate::detect_stuck_tx::{exceeds_threshold, stuck_tx};
 use crate::logs::INFO;
+use crate::notifications::enqueue_notification;
 use crate::numeric::LedgerMintIndex;
-use crate::state::config::dex_info_id;
+use crate::state::block_log::{ChangedToken, LoggedBlock, StateTransition, TokenChangeKind};
+use crate::state::blocklist::{BlockedToken, BlocklistEntry};
+use crate::state::config::{
+    allowed_relayers_id, allowed_subscribers_id, block_log_id, bridge_pair_changes_id,
+    certified_leaves_id, dex_info_id, dex_sources_id, evm_token_list_info_id,
+    icp_token_blocklist_id, indexes_checkpoint_id, ledger_reconciliations_id, maintenance_mode_id,
+    minted_deposits_id, pending_bridge_pairs_id, pending_unverified_txs_id,
+    recent_withdrawal_fees_id, reconciliation_queue_id, sla_thresholds_id, sla_tracked_txs_id,
+    stuck_txs_id, subscriptions_id, token_validation_checkpoint_id, token_validation_config_id,
+    unverified_tx_config_id,
+};
+use crate::state::dex::correlation::{is_swap_funded_by, SWAP_FUNDING_WINDOW_NS};
 use crate::state::dex::types::{DexAction, UserDexActions};
+use crate::state::export::{
+    export_entries, import_entries, EntryTooLarge, StateEntry, StateSection,
+};
+use crate::state::search::{
+    match_tier, normalize_query, sort_search_results, MatchTier, SearchedToken, TokenSearchFilter,
+};
 use crate::state::types::*;
+use crate::state::withdrawal_cost::{
+    gas_fee_stats, is_finalized_withdrawal, to_usd, WITHDRAWAL_COST_SAMPLE_SIZE,
+};
+use crate::update_icp_tokens::with_previous_standards;
 
 use std::cmp::Ordering;
 use std::collections::BTreeMap as STDBTreeMap;
-use std::ops::Add;
+use std::collections::BTreeSet;
+use std::ops::{Add, Bound};
 
 use candid::{CandidType, Nat, Principal};
 use ic_canister_log::log;
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::DefaultMemoryImpl;
-use ic_stable_structures::{BTreeMap, Cell};
+use ic_stable_structures::{BTreeMap, Cell, Storable};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
@@ -22,9 +53,11 @@ use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
 use crate::endpoints::{
-    AddEvmToIcpTx, AddIcpToEvmTx, CandidErc20TwinLedgerSuiteFee, CandidErc20TwinLedgerSuiteStatus,
-    CandidEvmToIcp, CandidEvmToken, CandidIcpToEvm, CandidIcpToken, GetEvmTokenArgs, MinterArgs,
-    TokenPair, Transaction, TransactionSearchParam,
+    AddEvmToIcpTx, AddIcpToEvmTx, BridgeRoute, CandidErc20TwinLedgerSuiteFee,
+    CandidErc20TwinLedgerSuiteStatus, CandidEvmToIcp, CandidEvmToken, CandidFeeAmount,
+    CandidIcpToEvm, CandidIcpToken, CandidLedgerReconciliation, CandidMinterFee,
+    CandidStuckTransaction, GetEvmTokenArgs, MinterArgs, ReconciliationReport, TokenPair,
+    Transaction, TransactionSearchParam, WithdrawalCostEstimate,
 };
 use crate::numeric::{BlockNumber, Erc20TokenAmount, LedgerBurnIndex};
 use crate::scrape_events::NATIVE_ERC20_ADDRESS;
@@ -33,10 +66,18 @@ use std::fmt::Debug;
 
 use crate::minter_client::appic_minter_types::events::{TransactionReceipt, TransactionStatus};
 
+pub mod block_log;
+pub mod blocklist;
 mod config;
 pub mod dex;
+pub mod export;
+pub mod search;
 mod storable_impl;
 pub mod types;
+pub mod withdrawal_cost;
+
+#[cfg(test)]
+pub mod tests;
 
 use config::{
     dex_actions_list, erc20_twin_ledger_requests_id, evm_to_icp_memory, evm_token_list_id,
@@ -66,6 +107,72 @@ pub struct State {
     pub dex_actions_list: BTreeMap<Principal, UserDexActions, StableMemory>,
 
     pub dex_info: Cell<DexInfo, StableMemory>,
+
+    pub token_validation_checkpoint: Cell<TokenValidationCheckpoint, StableMemory>,
+
+    pub token_validation_config: Cell<TokenValidationConfig, StableMemory>,
+
+    // Admin managed blocklist checked while ingesting new icp tokens
+    pub icp_token_blocklist: BTreeMap<BlockedToken, BlocklistEntry, StableMemory>,
+
+    pub evm_token_list_info: Cell<EvmTokenListInfo, StableMemory>,
+
+    // Twin ledger suite creation requests submitted to the appic ledger suite manager
+    pub twin_ledger_requests: BTreeMap<Erc20Identifier, Erc20TwinLedgerSuiteRequest, StableMemory>,
+
+    // Log of added and removed bridge pairs
+    pub bridge_pair_changes: BTreeMap<BridgePairChangeId, BridgePairChange, StableMemory>,
+
+    // Listed bridge pairs waiting for their tokens metadata
+    pub pending_bridge_pairs: BTreeMap<PendingBridgePairKey, PendingBridgePair, StableMemory>,
+
+    // Dex canisters scraped for dex actions, each with its own cursors
+    pub dex_sources: BTreeMap<Principal, DexSource, StableMemory>,
+
+    // Custom sla thresholds per chain, used to detect stuck transactions
+    pub sla_thresholds: BTreeMap<ChainId, SlaThresholds, StableMemory>,
+
+    // Transactions that exceeded the sla of their current status
+    pub stuck_txs: BTreeMap<StuckTxKey, StuckTx, StableMemory>,
+
+    pub unverified_tx_config: Cell<UnverifiedTxConfig, StableMemory>,
+
+    // Principals allowed to submit transactions on behalf of users, with the time they were added
+    pub allowed_relayers: BTreeMap<Principal, u64, StableMemory>,
+
+    // Last check of minted deposits and withdrawal burns against their ledger blocks
+    pub ledger_reconciliations: BTreeMap<ReconciliationKey, LedgerReconciliation, StableMemory>,
+
+    // Hash chained log of the recorded state transitions, exposed as icrc3 blocks
+    pub blocks: BTreeMap<u64, LoggedBlock, StableMemory>,
+
+    // Canisters notified about transaction changes matching their filter
+    pub subscriptions: BTreeMap<Principal, Subscription, StableMemory>,
+
+    pub maintenance_mode: Cell<MaintenanceMode, StableMemory>,
+
+    // Index of the minted deposits per principal, used to link swaps to the deposits that funded them
+    pub minted_deposits: BTreeMap<MintedDepositKey, (), StableMemory>,
+
+    pub indexes_checkpoint: Cell<IndexesCheckpoint, StableMemory>,
+
+    // Gas fees of the last WITHDRAWAL_COST_SAMPLE_SIZE finalized withdrawals of every token
+    pub recent_withdrawal_fees: BTreeMap<WithdrawalFeeKey, Erc20TokenAmount, StableMemory>,
+
+    // Transactions in a status with an sla, the only ones checked by the stuck transactions detection
+    pub sla_tracked_txs: BTreeMap<StuckTxKey, (), StableMemory>,
+
+    // Transactions pending verification per owner, used to cap the pending submissions of an owner
+    pub pending_unverified_txs: BTreeMap<PendingUnverifiedKey, (), StableMemory>,
+
+    // Ledger blocks that were never checked, changed since their last check or were not found yet
+    pub reconciliation_queue: BTreeMap<ReconciliationKey, ExpectedLedgerOperation, StableMemory>,
+
+    // Hashes of the leaves of the certified tree, reloaded into the heap tree after upgrades
+    pub certified_leaves: BTreeMap<Vec<u8>, [u8; 32], StableMemory>,
+
+    // Canisters allowed to subscribe to transaction changes, with the time they were added
+    pub allowed_subscribers: BTreeMap<Principal, u64, StableMemory>,
 }
 
 impl State {
@@ -99,32 +206,92 @@ impl State {
         }
     }
 
-    pub fn update_last_scraped_event(&mut self, minter_key: &MinterKey, last_scraped_event: u64) {
+    pub fn update_next_event_to_scrape(
+        &mut self,
+        minter_key: &MinterKey,
+        next_event_to_scrape: u64,
+    ) {
         if let Some(minter) = self.minters.get(minter_key) {
             let new_minter = Minter {
-                last_scraped_event,
+                next_event_to_scrape,
                 ..minter
             };
             self.record_minter(new_minter);
         }
     }
 
-    pub fn update_last_observed_dex_event(&mut self, last_observed_event: u64) {
-        let info = self.dex_info.get();
-        let new_info = DexInfo {
-            last_observed_event,
-            ..info.clone()
-        };
-        let _ = self.dex_info.set(new_info);
+    pub fn update_last_observed_dex_event(&mut self, dex_id: &Principal, last_observed_event: u64) {
+        if let Some(source) = self.dex_sources.get(dex_id) {
+            self.dex_sources.insert(
+                *dex_id,
+                DexSource {
+                    last_observed_event,
+                    ..source
+                },
+            );
+        }
     }
 
-    pub fn update_last_scraped_dex_event(&mut self, last_scraped_event: u64) {
-        let info = self.dex_info.get();
-        let new_info = DexInfo {
-            last_scraped_event,
-            ..info.clone()
-        };
-        let _ = self.dex_info.set(new_info);
+    pub fn update_next_dex_event_to_scrape(
+        &mut self,
+        dex_id: &Principal,
+        next_event_to_scrape: u64,
+    ) {
+        if let Some(source) = self.dex_sources.get(dex_id) {
+            self.dex_sources.insert(
+                *dex_id,
+                DexSource {
+                    next_event_to_scrape,
+                    ..source
+                },
+            );
+        }
+    }
+
+    // The single dex scraped before multiple dex sources were supported becomes the first source
+    pub fn migrate_dex_info(&mut self) {
+        if !self.dex_sources.is_empty() {
+            return;
+        }
+
+        let info = self.dex_info.get().clone();
+        self.add_dex_source(DexSource {
+            id: info.id,
+            adapter: DexAdapterKind::AppicDexV1,
+            enabled: true,
+            last_observed_event: info.last_observed_event,
+            // The single dex was scraped from its last scraped event
+            next_event_to_scrape: info.last_scraped_event,
+            label: Some("AppicDEX".to_string()),
+        });
+    }
+
+    // Returns false if the source already exists, its cursors are changed through
+    // update_last_observed_dex_event and update_next_dex_event_to_scrape instead
+    pub fn add_dex_source(&mut self, source: DexSource) -> bool {
+        if self.dex_sources.contains_key(&source.id) {
+            return false;
+        }
+        self.dex_sources.insert(source.id, source);
+        true
+    }
+
+    pub fn set_dex_source_enabled(&mut self, dex_id: &Principal, enabled: bool) {
+        if let Some(source) = self.dex_sources.get(dex_id) {
+            self.dex_sources
+                .insert(*dex_id, DexSource { enabled, ..source });
+        }
+    }
+
+    pub fn get_dex_sources(&self) -> Vec<DexSource> {
+        self.dex_sources.values().collect()
+    }
+
+    pub fn get_enabled_dex_sources(&self) -> Vec<DexSource> {
+        self.dex_sources
+            .values()
+            .filter(|source| source.enabled)
+            .collect()
     }
 
     pub fn get_minters(&self) -> Vec<(MinterKey, Minter)> {
@@ -145,7 +312,19 @@ impl State {
     }
 
     pub fn record_minter(&mut self, minter: Minter) {
-        self.minters.insert(MinterKey::from(&minter), minter);
+        let previous = self
+            .minters
+            .insert(MinterKey::from(&minter), minter.clone());
+
+        // Routes only certify the id, status and fee of the minter
+        let is_route_changed = previous.is_none_or(|previous| {
+            previous.id != minter.id
+                || previous.enabled != minter.enabled
+                || previous.icp_to_evm_fee != minter.icp_to_evm_fee
+        });
+        if is_route_changed {
+            self.certify_bridge_pairs();
+        }
     }
 
     pub fn get_icrc_twin_for_erc20(
@@ -165,6 +344,25 @@ impl State {
         }
     }
 
+    // Same as get_icrc_twin_for_erc20 but skips pairs that were removed from the minter,
+    // used to validate new transactions submitted by users
+    pub fn get_listed_icrc_twin_for_erc20(
+        &self,
+        erc20_identifier: &Erc20Identifier,
+        operator: &Operator,
+    ) -> Option<Principal> {
+        let bridge_pair = match operator {
+            Operator::AppicMinter => self.supported_twin_appic_tokens.get(erc20_identifier),
+            Operator::DfinityCkEthMinter => self.supported_ckerc20_tokens.get(erc20_identifier),
+        }?;
+
+        if bridge_pair.is_removed() {
+            return None;
+        }
+
+        Some(bridge_pair.icp_token.ledger_id)
+    }
+
     pub fn if_evm_to_icp_tx_exists(&self, identifier: &EvmToIcpTxIdentifier) -> bool {
         self.evm_to_icp_txs.get(identifier).is_some()
     }
@@ -173,7 +371,25 @@ impl State {
         self.icp_to_evm_txs.get(identifier).is_some()
     }
 
-    pub fn record_new_evm_to_icp(&mut self, identifier: EvmToIcpTxIdentifier, tx: EvmToIcpTx) {
+    pub fn record_new_evm_to_icp(
+        &mut self,
+        identifier: EvmToIcpTxIdentifier,
+        tx: EvmToIcpTx,
+        now: u64,
+    ) {
+        let previous = self.evm_to_icp_txs.get(&identifier);
+        let transaction = Transaction::from(CandidEvmToIcp::from(tx.clone()));
+
+        if previous
+            .as_ref()
+            .map_or(true, |previous| previous.status != tx.status)
+        {
+            self.append_block(StateTransition::evm_to_icp(identifier.clone(), &tx), now);
+            self.notify_subscribers(|filter| filter.matches_evm_to_icp(&tx), &transaction, now);
+        }
+
+        self.index_evm_to_icp(&identifier, previous.as_ref(), Some(&tx));
+
         self.evm_to_icp_txs.insert(identifier, tx);
     }
 
@@ -190,6 +406,7 @@ impl State {
         chain_id: ChainId,
         operator: Operator,
         timestamp: u64,
+        now: u64,
     ) {
         // Parse addresses once
         let parsed_from_address = Address::from_str(&from_address)
@@ -198,6 +415,10 @@ impl State {
             .expect("Should not fail converting erc20_contract_address to Address");
 
         if let Some(tx) = self.evm_to_icp_txs.get(&identifier) {
+            if tx.status == EvmToIcpStatus::Expired {
+                log!(INFO, "Reviving expired transaction {:?}", identifier);
+            }
+
             // Update only the necessary fields in the existing transaction
             let new_tx = EvmToIcpTx {
                 verified: true,
@@ -212,9 +433,10 @@ impl State {
                     &Erc20Identifier(parsed_erc20_address, chain_id),
                     &operator,
                 ),
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_evm_to_icp(identifier, new_tx);
+            self.record_new_evm_to_icp(identifier, new_tx, now);
         } else {
             // Create a new transaction only if one doses not already exist
             let new_tx = EvmToIcpTx {
@@ -237,9 +459,10 @@ impl State {
                 time: timestamp,
                 operator,
                 ledger_mint_index: None,
+                status_updated_at: Some(timestamp),
             };
 
-            self.record_new_evm_to_icp(identifier, new_tx);
+            self.record_new_evm_to_icp(identifier, new_tx, now);
         }
     }
 
@@ -248,6 +471,8 @@ impl State {
         identifier: EvmToIcpTxIdentifier,
         ledger_mint_index: LedgerMintIndex,
         transfer_fee: Option<Nat>,
+        timestamp: u64,
+        now: u64,
     ) {
         if let Some(tx) = self.evm_to_icp_txs.get(&identifier) {
             // Fee calculation
@@ -263,33 +488,69 @@ impl State {
                 actual_received,
                 ledger_mint_index: Some(ledger_mint_index),
                 status: EvmToIcpStatus::Minted,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_evm_to_icp(identifier, new_tx);
+            let principal = new_tx.principal;
+            self.record_new_evm_to_icp(identifier, new_tx, now);
+
+            // Dex events might have been scraped before the minter events
+            self.link_swaps_to_bridge_txs(principal);
         }
     }
 
-    pub fn record_invalid_evm_to_icp(&mut self, identifier: EvmToIcpTxIdentifier, reason: String) {
+    pub fn record_invalid_evm_to_icp(
+        &mut self,
+        identifier: EvmToIcpTxIdentifier,
+        reason: String,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.evm_to_icp_txs.get(&identifier) {
             let new_tx = EvmToIcpTx {
                 status: EvmToIcpStatus::Invalid(reason),
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_evm_to_icp(identifier, new_tx);
+            self.record_new_evm_to_icp(identifier, new_tx, now);
         }
     }
 
-    pub fn record_quarantined_evm_to_icp(&mut self, identifier: EvmToIcpTxIdentifier) {
+    pub fn record_quarantined_evm_to_icp(
+        &mut self,
+        identifier: EvmToIcpTxIdentifier,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.evm_to_icp_txs.get(&identifier) {
             let new_tx = EvmToIcpTx {
                 status: EvmToIcpStatus::Quarantined,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_evm_to_icp(identifier, new_tx);
+            self.record_new_evm_to_icp(identifier, new_tx, now);
         }
     }
 
-    pub fn record_new_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier, tx: IcpToEvmTx) {
+    pub fn record_new_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        tx: IcpToEvmTx,
+        now: u64,
+    ) {
+        let previous = self.icp_to_evm_txs.get(&identifier);
+        let transaction = Transaction::from(CandidIcpToEvm::from(tx.clone()));
+
+        if previous
+            .as_ref()
+            .map_or(true, |previous| previous.status != tx.status)
+        {
+            self.append_block(StateTransition::icp_to_evm(identifier.clone(), &tx), now);
+            self.notify_subscribers(|filter| filter.matches_icp_to_evm(&tx), &transaction, now);
+        }
+
+        self.index_icp_to_evm(&identifier, previous.as_ref(), Some(&tx));
+
         self.icp_to_evm_txs.insert(identifier, tx);
     }
 
@@ -310,6 +571,7 @@ impl State {
         timestamp: u64,
         l1_fee: Option<Nat>,
         withdrawal_fee: Option<Nat>,
+        now: u64,
     ) {
         let l1_fee = nat_to_erc20_amount(l1_fee.unwrap_or(Nat::from(0_u8)));
         let withdrawal_fee = nat_to_erc20_amount(withdrawal_fee.unwrap_or(Nat::from(0_u8)));
@@ -334,6 +596,10 @@ impl State {
             .map(|burn_index| LedgerBurnIndex::from(nat_to_u64(&burn_index)));
 
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
+            if tx.status == IcpToEvmStatus::Expired {
+                log!(INFO, "Reviving expired transaction {:?}", identifier);
+            }
+
             let icrc_ledger_id =
                 self.get_icrc_twin_for_erc20(&Erc20Identifier(erc20_address, chain_id), &operator);
 
@@ -349,10 +615,11 @@ impl State {
                 from_subaccount,
                 status: IcpToEvmStatus::Accepted,
                 icrc_ledger_id,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
 
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         } else {
             let icrc_ledger_id =
                 self.get_icrc_twin_for_erc20(&Erc20Identifier(erc20_address, chain_id), &operator);
@@ -377,39 +644,59 @@ impl State {
                 gas_used: None,
                 transaction_hash: None,
                 total_gas_spent: None,
+                reimbursement: None,
+                status_updated_at: Some(timestamp),
             };
 
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
-    pub fn record_created_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier) {
+    pub fn record_created_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
             let new_tx = IcpToEvmTx {
                 status: IcpToEvmStatus::Created,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
-    pub fn record_signed_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier) {
+    pub fn record_signed_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
             let new_tx = IcpToEvmTx {
                 status: IcpToEvmStatus::SignedTransaction,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
-    pub fn record_replaced_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier) {
+    pub fn record_replaced_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
             let new_tx = IcpToEvmTx {
                 status: IcpToEvmStatus::ReplacedTransaction,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
@@ -417,6 +704,8 @@ impl State {
         &mut self,
         identifier: IcpToEvmIdentifier,
         receipt: TransactionReceipt,
+        timestamp: u64,
+        now: u64,
     ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
             let gas_used = nat_to_erc20_amount(receipt.gas_used);
@@ -441,29 +730,61 @@ impl State {
                 effective_gas_price: Some(effective_gas_price),
                 total_gas_spent: Some(total_gas_spent),
                 status,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
-    pub fn record_reimbursed_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier) {
+    // ledger_id is None when the reimbursement is made on the ledger the tokens were burned from
+    pub fn record_reimbursed_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        reason: IcpToEvmReimbursementReason,
+        reimbursed_amount: Nat,
+        ledger_id: Option<Principal>,
+        reimbursed_in_block: Option<Nat>,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
+            let reimbursement = IcpToEvmReimbursement {
+                reason,
+                reimbursed_amount: nat_to_erc20_amount(reimbursed_amount),
+                ledger_id: ledger_id.or(tx.icrc_ledger_id),
+                reimbursed_in_block: reimbursed_in_block
+                    .map(|block_index| nat_to_ledger_mint_index(&block_index)),
+            };
+
+            let reimbursement = match tx.reimbursement.clone() {
+                Some(previous) => previous.merge(reimbursement),
+                None => reimbursement,
+            };
+
             let new_tx = IcpToEvmTx {
                 status: IcpToEvmStatus::Reimbursed,
+                reimbursement: Some(reimbursement),
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
-    pub fn record_quarantined_reimbursed_icp_to_evm(&mut self, identifier: IcpToEvmIdentifier) {
+    pub fn record_quarantined_reimbursed_icp_to_evm(
+        &mut self,
+        identifier: IcpToEvmIdentifier,
+        timestamp: u64,
+        now: u64,
+    ) {
         if let Some(tx) = self.icp_to_evm_txs.get(&identifier) {
             let new_tx = IcpToEvmTx {
                 status: IcpToEvmStatus::QuarantinedReimbursement,
+                status_updated_at: Some(timestamp),
                 ..tx
             };
-            self.record_new_icp_to_evm(identifier, new_tx);
+            self.record_new_icp_to_evm(identifier, new_tx, now);
         }
     }
 
@@ -473,6 +794,7 @@ impl State {
         symbol: String,
         transfer_fee: Erc20TokenAmount,
         chain_id: ChainId,
+        now: u64,
     ) {
         log!(
             INFO,
@@ -481,28 +803,42 @@ impl State {
             ledger.to_text()
         );
 
-        let evm_token = self
-            .get_evm_token_by_identifier(&Erc20Identifier(Address::ZERO, chain_id))
-            .expect("Native token should already be available");
+        // The logo is taken from the native evm token, which is not bundled for every chain
+        let logo = match self.get_evm_token_by_identifier(&Erc20Identifier(Address::ZERO, chain_id))
+        {
+            Some(evm_token) => evm_token.logo,
+            None => {
+                log!(
+                    INFO,
+                    "Native token for chain_id:{:?} is not available, recording the ledger without a logo",
+                    chain_id
+                );
+                String::new()
+            }
+        };
         let icp_token = IcpToken {
             ledger_id: ledger,
             name: symbol.clone(),
             decimals: 18,
             symbol,
             usd_price: "0.01".to_string(),
-            logo: evm_token.logo,
+            logo,
             fee: transfer_fee,
             token_type: IcpTokenType::ICRC2,
             rank: Some(1),
             listed_on_appic_dex: Some(false),
+            supported_standards: None,
+            validation_failures: None,
+            verified: None,
         };
-        self.record_icp_token(ledger, icp_token);
+        self.record_icp_token(ledger, icp_token, now);
     }
 
     pub fn record_deployed_wrapped_icrc_token(
         &mut self,
         icrc_token: Principal,
         wrapped_token: Erc20Identifier,
+        timestamp: u64,
     ) {
         log!(
             INFO,
@@ -521,164 +857,1273 @@ impl State {
                 cmc_id: None,
                 usd_price: None,
                 volume_usd_24h: None,
+                runtime_managed_fields: None,
+                source: Some(EvmTokenSource::Runtime),
             };
             self.evm_token_list
                 .insert(wrapped_token.clone(), evm_token.clone());
 
-            self.supported_twin_appic_tokens.insert(
+            self.record_bridge_pair(
+                Operator::AppicMinter,
                 wrapped_token,
                 BridgePair {
                     icp_token: token,
                     evm_token,
+                    removed_at: None,
                 },
+                timestamp,
             );
         };
     }
 
-    pub fn all_unverified_icp_to_evm(&self) -> Vec<(IcpToEvmIdentifier, u64)> {
+    // Returns the submission time and the expiry time (None if not expired yet) of unverified txs
+    pub fn all_unverified_icp_to_evm(&self) -> Vec<(IcpToEvmIdentifier, u64, Option<u64>)> {
         self.icp_to_evm_txs
             .iter()
             .filter(|(_, tx)| !tx.verified) // Filter out verified transactions
-            .map(|(identifier, tx)| (identifier, tx.time)) // Map to the desired tuple
+            .map(|(identifier, tx)| (identifier, tx.time, tx.expired_at()))
             .collect()
     }
 
+    pub fn expire_unverified_icp_to_evm(&mut self, identifier: &IcpToEvmIdentifier, now: u64) {
+        if let Some(tx) = self.icp_to_evm_txs.get(identifier) {
+            let new_tx = IcpToEvmTx {
+                status: IcpToEvmStatus::Expired,
+                status_updated_at: Some(now),
+                ..tx
+            };
+            self.record_new_icp_to_evm(identifier.clone(), new_tx, now);
+        }
+    }
+
     pub fn remove_unverified_icp_to_evm(&mut self, identifier: &IcpToEvmIdentifier) {
-        self.icp_to_evm_txs.remove(identifier);
+        if let Some(tx) = self.icp_to_evm_txs.remove(identifier) {
+            self.index_icp_to_evm(identifier, Some(&tx), None);
+        }
     }
 
-    pub fn all_unverified_evm_to_icp(&self) -> Vec<(EvmToIcpTxIdentifier, u64)> {
+    // Returns the submission time and the expiry time (None if not expired yet) of unverified txs
+    pub fn all_unverified_evm_to_icp(&self) -> Vec<(EvmToIcpTxIdentifier, u64, Option<u64>)> {
         self.evm_to_icp_txs
             .iter()
             .filter(|(_, tx)| !tx.verified) // Filter out verified transactions
-            .map(|(identifier, tx)| (identifier, tx.time)) // Map to the desired tuple
+            .map(|(identifier, tx)| (identifier, tx.time, tx.expired_at()))
             .collect()
     }
 
-    pub fn remove_unverified_evm_to_icp(&mut self, identifier: &EvmToIcpTxIdentifier) {
-        self.evm_to_icp_txs.remove(identifier);
+    pub fn expire_unverified_evm_to_icp(&mut self, identifier: &EvmToIcpTxIdentifier, now: u64) {
+        if let Some(tx) = self.evm_to_icp_txs.get(identifier) {
+            let new_tx = EvmToIcpTx {
+                status: EvmToIcpStatus::Expired,
+                status_updated_at: Some(now),
+                ..tx
+            };
+            self.record_new_evm_to_icp(identifier.clone(), new_tx, now);
+        }
     }
 
-    // Gets all the transaction history for an evm address
-    pub fn get_transaction_for_address(&self, address: Address) -> Vec<Transaction> {
-        let result: Vec<Transaction> = self
-            .evm_to_icp_txs
-            .iter()
-            .filter(|(_id, tx)| tx.from_address == address)
-            .map(|(_id, tx)| Transaction::from(CandidEvmToIcp::from(tx)))
-            .chain(
-                self.icp_to_evm_txs
-                    .iter()
-                    .filter(|(_id, tx)| tx.destination == address)
-                    .map(|(_id, tx)| Transaction::from(CandidIcpToEvm::from(tx))),
-            )
-            .collect();
+    pub fn add_allowed_relayer(&mut self, relayer: Principal, now: u64) {
+        if !self.allowed_relayers.contains_key(&relayer) {
+            self.allowed_relayers.insert(relayer, now);
+        }
+    }
 
-        result
+    pub fn remove_allowed_relayer(&mut self, relayer: &Principal) {
+        self.allowed_relayers.remove(relayer);
     }
 
-    // Gets all the transaction history for a principal
-    pub fn get_transaction_for_principal(&self, principal_id: Principal) -> Vec<Transaction> {
-        let result: Vec<Transaction> = self
-            .evm_to_icp_txs
-            .iter()
-            .filter(|(_id, tx)| tx.principal == principal_id)
-            .map(|(_id, tx)| Transaction::from(CandidEvmToIcp::from(tx)))
-            .chain(
-                self.icp_to_evm_txs
-                    .iter()
-                    .filter(|(_id, tx)| tx.from == principal_id)
-                    .map(|(_id, tx)| Transaction::from(CandidIcpToEvm::from(tx))),
-            )
-            .chain(
-                self.get_dex_actions_for_principal(principal_id)
-                    .into_iter()
-                    .map(|action| Transaction::DexAction(action.into())),
-            )
-            .collect();
+    pub fn is_allowed_relayer(&self, caller: &Principal) -> bool {
+        self.allowed_relayers.contains_key(caller)
+    }
 
-        result
+    pub fn get_allowed_relayers(&self) -> Vec<Principal> {
+        self.allowed_relayers.keys().collect()
     }
 
-    // Gets supported twin token pairs for both Appic and Dfinity NNS Twin tokens
-    pub fn get_supported_bridge_pairs(&self) -> Vec<TokenPair> {
-        self.supported_ckerc20_tokens
-            .values()
-            .map(|bridge_pair| {
-                // Update usd price
-                let icp_token_with_new_usd_price: IcpToken = IcpToken {
-                    usd_price: self
-                        .get_icp_token_price(&bridge_pair.icp_token.ledger_id)
-                        .unwrap_or("0".to_string()),
-                    ..bridge_pair.icp_token
-                };
-                TokenPair {
-                    evm_token: CandidEvmToken::from(bridge_pair.evm_token),
-                    icp_token: CandidIcpToken::from(icp_token_with_new_usd_price),
-                    operator: Operator::DfinityCkEthMinter,
-                }
-            })
-            .chain(
-                self.supported_twin_appic_tokens
-                    .values()
-                    .map(|bridge_pair| {
-                        // Update usd price
-                        let icp_token_with_new_usd_price = IcpToken {
-                            usd_price: self
-                                .get_icp_token_price(&bridge_pair.icp_token.ledger_id)
-                                .unwrap_or("0".to_string()),
-                            ..bridge_pair.icp_token
-                        };
-                        TokenPair {
-                            evm_token: CandidEvmToken::from(bridge_pair.evm_token),
-                            icp_token: CandidIcpToken::from(icp_token_with_new_usd_price),
-                            operator: Operator::AppicMinter,
-                        }
-                    }),
-            )
-            .collect()
+    pub fn add_allowed_subscriber(&mut self, subscriber: Principal, now: u64) {
+        if !self.allowed_subscribers.contains_key(&subscriber) {
+            self.allowed_subscribers.insert(subscriber, now);
+        }
     }
 
-    // Searches for a transaction by hash in both evm_to_icp and icp_to_evm
-    fn get_transaction_by_hash(&self, tx_hash: &String, chain_id: ChainId) -> Option<Transaction> {
-        let evm_to_icp_id = EvmToIcpTxIdentifier::new(tx_hash, chain_id);
+    // The subscription of a removed subscriber is removed as well
+    pub fn remove_allowed_subscriber(&mut self, subscriber: &Principal) {
+        self.allowed_subscribers.remove(subscriber);
+        self.subscriptions.remove(subscriber);
+    }
 
-        self.evm_to_icp_txs
-            .get(&evm_to_icp_id)
-            .map(|tx| Transaction::from(CandidEvmToIcp::from(tx)))
-            .or_else(|| {
-                self.icp_to_evm_txs
-                    .values()
-                    .find(|tx| {
-                        tx.chain_id == chain_id && tx.transaction_hash.as_ref() == Some(tx_hash)
-                    })
-                    .map(|tx| Transaction::from(CandidIcpToEvm::from(tx)))
-            })
+    pub fn is_allowed_subscriber(&self, caller: &Principal) -> bool {
+        self.allowed_subscribers.contains_key(caller)
     }
 
-    // Searches for a transaction by burn index id in icp_to_evm_tx
-    fn get_transaction_by_burn_index(
-        &self,
-        ledger_burn_index: LedgerBurnIndex,
-        chain_id: ChainId,
-    ) -> Option<Transaction> {
-        let icp_to_evm_id = IcpToEvmIdentifier(ledger_burn_index, chain_id);
+    pub fn record_subscription(&mut self, subscriber: Principal, subscription: Subscription) {
+        self.subscriptions.insert(subscriber, subscription);
+    }
 
-        self.icp_to_evm_txs
-            .get(&icp_to_evm_id)
-            .map(|tx| Transaction::from(CandidIcpToEvm::from(tx)))
+    pub fn remove_subscription(&mut self, subscriber: &Principal) -> Option<Subscription> {
+        self.subscriptions.remove(subscriber)
     }
 
-    // Searches for a transaction by mint id in evm_to_icp_txs
-    fn get_transaction_by_mint_id(
-        &self,
-        ledger_mint_index: LedgerMintIndex,
-        chain_id: ChainId,
-    ) -> Option<Transaction> {
-        self.evm_to_icp_txs
-            .values()
-            .find(|tx| tx.chain_id == chain_id && tx.ledger_mint_index == Some(ledger_mint_index))
-            .map(|tx| Transaction::EvmToIcp(CandidEvmToIcp::from(tx)))
+    pub fn get_subscriptions(&self) -> Vec<(Principal, Subscription)> {
+        self.subscriptions.iter().collect()
+    }
+
+    // Queues the changed transaction for every subscriber whose filter matches it,
+    // the queued notifications are delivered after the events are applied
+    fn notify_subscribers<F>(&self, matches: F, transaction: &Transaction, now: u64)
+    where
+        F: Fn(&SubscriptionFilter) -> bool,
+    {
+        for (subscriber, subscription) in self.subscriptions.iter() {
+            if matches(&subscription.filter) {
+                enqueue_notification(subscriber, transaction.clone(), now);
+            }
+        }
+    }
+
+    pub fn is_in_maintenance_mode(&self) -> bool {
+        self.maintenance_mode.get().enabled
+    }
+
+    pub fn set_maintenance_mode(&mut self, enabled: bool, now: u64) {
+        let _ = self.maintenance_mode.set(MaintenanceMode {
+            enabled,
+            enabled_at: enabled.then_some(now),
+        });
+    }
+
+    pub fn export_state_chunk(
+        &self,
+        section: StateSection,
+        cursor: Option<&[u8]>,
+    ) -> Result<(Vec<StateEntry>, Option<Vec<u8>>), EntryTooLarge> {
+        match section {
+            StateSection::Minters => export_entries(&self.minters, cursor),
+            StateSection::EvmToIcpTxs => export_entries(&self.evm_to_icp_txs, cursor),
+            StateSection::IcpToEvmTxs => export_entries(&self.icp_to_evm_txs, cursor),
+            StateSection::EvmTokens => export_entries(&self.evm_token_list, cursor),
+            StateSection::IcpTokens => export_entries(&self.icp_token_list, cursor),
+            StateSection::Ckerc20BridgePairs => {
+                export_entries(&self.supported_ckerc20_tokens, cursor)
+            }
+            StateSection::TwinAppicBridgePairs => {
+                export_entries(&self.supported_twin_appic_tokens, cursor)
+            }
+            StateSection::DexActions => export_entries(&self.dex_actions_list, cursor),
+            StateSection::DexSources => export_entries(&self.dex_sources, cursor),
+        }
+    }
+
+    // Imported entries are not certified nor logged as blocks,
+    // the certified tree is rebuilt once the maintenance mode is disabled
+    pub fn import_state_chunk(&mut self, section: StateSection, entries: Vec<StateEntry>) {
+        match section {
+            StateSection::Minters => import_entries(&mut self.minters, entries),
+            StateSection::EvmToIcpTxs => import_entries(&mut self.evm_to_icp_txs, entries),
+            StateSection::IcpToEvmTxs => import_entries(&mut self.icp_to_evm_txs, entries),
+            StateSection::EvmTokens => import_entries(&mut self.evm_token_list, entries),
+            StateSection::IcpTokens => import_entries(&mut self.icp_token_list, entries),
+            StateSection::Ckerc20BridgePairs => {
+                import_entries(&mut self.supported_ckerc20_tokens, entries)
+            }
+            StateSection::TwinAppicBridgePairs => {
+                import_entries(&mut self.supported_twin_appic_tokens, entries)
+            }
+            StateSection::DexActions => import_entries(&mut self.dex_actions_list, entries),
+            StateSection::DexSources => import_entries(&mut self.dex_sources, entries),
+        }
+    }
+
+    pub fn get_unverified_tx_config(&self) -> UnverifiedTxConfig {
+        self.unverified_tx_config.get().clone()
+    }
+
+    pub fn update_unverified_tx_config(
+        &mut self,
+        expiry_timeout: Option<u64>,
+        retention_period: Option<u64>,
+    ) {
+        let config = self.unverified_tx_config.get().clone();
+        let _ = self.unverified_tx_config.set(UnverifiedTxConfig {
+            expiry_timeout: expiry_timeout.unwrap_or(config.expiry_timeout),
+            retention_period: retention_period.unwrap_or(config.retention_period),
+        });
+    }
+
+    pub fn remove_unverified_evm_to_icp(&mut self, identifier: &EvmToIcpTxIdentifier) {
+        if let Some(tx) = self.evm_to_icp_txs.remove(identifier) {
+            self.index_evm_to_icp(identifier, Some(&tx), None);
+        }
+    }
+
+    // Keeps the indexes of a deposit up to date, previous is the recorded version of the deposit
+    // and tx the new one, None if the deposit is removed
+    fn index_evm_to_icp(
+        &mut self,
+        identifier: &EvmToIcpTxIdentifier,
+        previous: Option<&EvmToIcpTx>,
+        tx: Option<&EvmToIcpTx>,
+    ) {
+        if let Some(previous) =
+            previous.filter(|previous| previous.status == EvmToIcpStatus::Minted)
+        {
+            self.minted_deposits
+                .remove(&MintedDepositKey::new(identifier.clone(), previous));
+        }
+
+        if let Some(tx) = tx.filter(|tx| tx.status == EvmToIcpStatus::Minted) {
+            self.minted_deposits
+                .insert(MintedDepositKey::new(identifier.clone(), tx), ());
+        }
+
+        let key = StuckTxKey::EvmToIcp(identifier.clone());
+        if tx.is_some_and(|tx| SlaThresholds::tracks_evm_to_icp(&tx.status)) {
+            self.sla_tracked_txs.insert(key.clone(), ());
+        } else {
+            self.sla_tracked_txs.remove(&key);
+        }
+
+        if let Some(previous) =
+            previous.filter(|previous| previous.status == EvmToIcpStatus::PendingVerification)
+        {
+            self.pending_unverified_txs.remove(&PendingUnverifiedKey {
+                owner: previous.principal,
+                tx: key.clone(),
+            });
+        }
+
+        if let Some(tx) = tx.filter(|tx| tx.status == EvmToIcpStatus::PendingVerification) {
+            self.pending_unverified_txs.insert(
+                PendingUnverifiedKey {
+                    owner: tx.principal,
+                    tx: key,
+                },
+                (),
+            );
+        }
+
+        self.queue_reconciliation(
+            ReconciliationKey::EvmToIcp(identifier.clone()),
+            tx.and_then(|tx| tx.expected_ledger_operation()),
+        );
+
+        // Frontend submitted transactions might be recorded with another principal
+        if let Some(previous) =
+            previous.filter(|previous| tx.map_or(true, |tx| tx.principal != previous.principal))
+        {
+            self.uncertify_leaf(&tx_key(
+                &previous.principal,
+                CertifiedTxKind::EvmToIcp,
+                identifier,
+            ));
+        }
+
+        if let Some(tx) = tx {
+            self.certify_leaf(
+                tx_key(&tx.principal, CertifiedTxKind::EvmToIcp, identifier),
+                certified_hash(&Transaction::from(CandidEvmToIcp::from(tx.clone()))),
+            );
+        }
+    }
+
+    // Keeps the indexes of a withdrawal up to date, previous is the recorded version of the withdrawal
+    // and tx the new one, None if the withdrawal is removed
+    fn index_icp_to_evm(
+        &mut self,
+        identifier: &IcpToEvmIdentifier,
+        previous: Option<&IcpToEvmTx>,
+        tx: Option<&IcpToEvmTx>,
+    ) {
+        if let Some(previous) = previous.filter(|previous| is_finalized_withdrawal(previous)) {
+            self.recent_withdrawal_fees
+                .remove(&WithdrawalFeeKey::new(identifier.clone(), previous));
+        }
+
+        if let Some(tx) = tx.filter(|tx| is_finalized_withdrawal(tx)) {
+            let key = WithdrawalFeeKey::new(identifier.clone(), tx);
+            let token = key.token.clone();
+            self.recent_withdrawal_fees.insert(
+                key,
+                tx.total_gas_spent
+                    .expect("BUG: finalized withdrawals have a gas fee"),
+            );
+
+            // Only the most recent fees are kept, the oldest fees of the token are removed first
+            let fees: Vec<WithdrawalFeeKey> = self.recent_withdrawal_fees_keys(&token).collect();
+            for key in fees
+                .iter()
+                .take(fees.len().saturating_sub(WITHDRAWAL_COST_SAMPLE_SIZE))
+            {
+                self.recent_withdrawal_fees.remove(key);
+            }
+        }
+
+        let key = StuckTxKey::IcpToEvm(identifier.clone());
+        if tx.is_some_and(|tx| SlaThresholds::tracks_icp_to_evm(&tx.status)) {
+            self.sla_tracked_txs.insert(key.clone(), ());
+        } else {
+            self.sla_tracked_txs.remove(&key);
+        }
+
+        if let Some(previous) =
+            previous.filter(|previous| previous.status == IcpToEvmStatus::PendingVerification)
+        {
+            self.pending_unverified_txs.remove(&PendingUnverifiedKey {
+                owner: previous.from,
+                tx: key.clone(),
+            });
+        }
+
+        if let Some(tx) = tx.filter(|tx| tx.status == IcpToEvmStatus::PendingVerification) {
+            self.pending_unverified_txs.insert(
+                PendingUnverifiedKey {
+                    owner: tx.from,
+                    tx: key,
+                },
+                (),
+            );
+        }
+
+        self.queue_reconciliation(
+            ReconciliationKey::IcpToEvm(identifier.clone()),
+            tx.and_then(|tx| tx.expected_ledger_operation()),
+        );
+
+        // Frontend submitted transactions might be recorded with another principal
+        if let Some(previous) =
+            previous.filter(|previous| tx.map_or(true, |tx| tx.from != previous.from))
+        {
+            self.uncertify_leaf(&tx_key(
+                &previous.from,
+                CertifiedTxKind::IcpToEvm,
+                identifier,
+            ));
+        }
+
+        if let Some(tx) = tx {
+            self.certify_leaf(
+                tx_key(&tx.from, CertifiedTxKind::IcpToEvm, identifier),
+                certified_hash(&Transaction::from(CandidIcpToEvm::from(tx.clone()))),
+            );
+        }
+    }
+
+    // Certifies the dex actions of a principal starting at the given index
+    fn index_dex_actions(
+        &mut self,
+        principal: Principal,
+        first_index: usize,
+        actions: &[DexAction],
+    ) {
+        for (offset, action) in actions.iter().enumerate() {
+            self.certify_leaf(
+                dex_action_key(&principal, (first_index + offset) as u64),
+                certified_hash(&Transaction::DexAction(action.clone().into())),
+            );
+        }
+    }
+
+    // Certifies a leaf and keeps its hash in stable memory,
+    // so that the tree is reloaded after upgrades without hashing the transactions again
+    fn certify_leaf(&mut self, key: Vec<u8>, hash: [u8; 32]) {
+        self.certified_leaves.insert(key.clone(), hash);
+        certify_leaf(key, hash);
+    }
+
+    fn uncertify_leaf(&mut self, key: &[u8]) {
+        self.certified_leaves.remove(&key.to_vec());
+        uncertify_leaf(key);
+    }
+
+    // Queues the expected ledger operation of a transaction unless the same operation was already
+    // checked and found, removes the transaction from the queue if it has no ledger operation
+    fn queue_reconciliation(
+        &mut self,
+        key: ReconciliationKey,
+        expected: Option<ExpectedLedgerOperation>,
+    ) {
+        let Some(expected) = expected else {
+            self.reconciliation_queue.remove(&key);
+            return;
+        };
+
+        let needs_check = match self.ledger_reconciliations.get(&key) {
+            Some(reconciliation) => {
+                reconciliation.expected != expected
+                    || reconciliation.status == ReconciliationStatus::BlockNotFound
+            }
+            None => true,
+        };
+
+        if needs_check {
+            self.reconciliation_queue.insert(key, expected);
+        } else {
+            self.reconciliation_queue.remove(&key);
+        }
+    }
+
+    // Number of transactions of the owner that are still pending verification
+    pub fn count_pending_unverified(&self, owner: Principal) -> usize {
+        self.pending_unverified_txs
+            .range(PendingUnverifiedKey::first_of(owner)..)
+            .take_while(|(key, _)| key.owner == owner)
+            .count()
+    }
+
+    // Owners with transactions pending verification and their number of pending transactions
+    pub fn get_pending_unverified_counts(&self) -> Vec<(Principal, u64)> {
+        let mut counts: Vec<(Principal, u64)> = vec![];
+        for key in self.pending_unverified_txs.keys() {
+            match counts.last_mut() {
+                Some((owner, count)) if *owner == key.owner => *count += 1,
+                _ => counts.push((key.owner, 1)),
+            }
+        }
+        counts
+    }
+
+    fn recent_withdrawal_fees_keys(
+        &self,
+        token: &Erc20Identifier,
+    ) -> impl Iterator<Item = WithdrawalFeeKey> + '_ {
+        let token = token.clone();
+        self.recent_withdrawal_fees
+            .range(WithdrawalFeeKey::first_of(token.clone())..)
+            .map(|(key, _fee)| key)
+            .take_while(move |key| key.token == token)
+    }
+
+    pub fn get_indexes_checkpoint(&self) -> IndexesCheckpoint {
+        self.indexes_checkpoint.get().clone()
+    }
+
+    // Indexes are incomplete while they are rebuilt
+    pub fn is_rebuilding_indexes(&self) -> bool {
+        self.indexes_checkpoint.get().rebuild.is_some()
+    }
+
+    // Clears and rebuilds the indexes derived from the transactions, see rebuild_indexes.rs
+    pub fn start_indexes_rebuild(&mut self, version: u32) {
+        let _ = self.indexes_checkpoint.set(IndexesCheckpoint {
+            version,
+            rebuild: Some(IndexRebuildStep::ClearIndexes),
+        });
+    }
+
+    // Runs the next step of the indexes rebuild on at most batch_size entries,
+    // returns true if the rebuild is not complete yet
+    pub fn rebuild_indexes_batch(&mut self, batch_size: usize) -> bool {
+        let checkpoint = self.get_indexes_checkpoint();
+
+        let next_step = match checkpoint.rebuild {
+            None => return false,
+            Some(IndexRebuildStep::ClearIndexes) => {
+                if self.clear_indexes(batch_size) {
+                    Some(IndexRebuildStep::EvmToIcpTxs(None))
+                } else {
+                    Some(IndexRebuildStep::ClearIndexes)
+                }
+            }
+            Some(IndexRebuildStep::EvmToIcpTxs(cursor)) => {
+                let batch: Vec<(EvmToIcpTxIdentifier, EvmToIcpTx)> = self
+                    .evm_to_icp_txs
+                    .range((
+                        cursor.map_or(Bound::Unbounded, Bound::Excluded),
+                        Bound::Unbounded,
+                    ))
+                    .take(batch_size)
+                    .collect();
+
+                for (identifier, tx) in batch.iter() {
+                    self.index_evm_to_icp(identifier, None, Some(tx));
+                }
+
+                if batch.len() < batch_size {
+                    Some(IndexRebuildStep::IcpToEvmTxs(None))
+                } else {
+                    Some(IndexRebuildStep::EvmToIcpTxs(
+                        batch.last().map(|(identifier, _tx)| identifier.clone()),
+                    ))
+                }
+            }
+            Some(IndexRebuildStep::IcpToEvmTxs(cursor)) => {
+                let batch: Vec<(IcpToEvmIdentifier, IcpToEvmTx)> = self
+                    .icp_to_evm_txs
+                    .range((
+                        cursor.map_or(Bound::Unbounded, Bound::Excluded),
+                        Bound::Unbounded,
+                    ))
+                    .take(batch_size)
+                    .collect();
+
+                for (identifier, tx) in batch.iter() {
+                    self.index_icp_to_evm(identifier, None, Some(tx));
+                }
+
+                if batch.len() < batch_size {
+                    Some(IndexRebuildStep::DexActions(None))
+                } else {
+                    Some(IndexRebuildStep::IcpToEvmTxs(
+                        batch.last().map(|(identifier, _tx)| identifier.clone()),
+                    ))
+                }
+            }
+            Some(IndexRebuildStep::DexActions(cursor)) => {
+                let batch: Vec<(Principal, UserDexActions)> = self
+                    .dex_actions_list
+                    .range((
+                        cursor.map_or(Bound::Unbounded, Bound::Excluded),
+                        Bound::Unbounded,
+                    ))
+                    .take(batch_size)
+                    .collect();
+
+                for (principal, actions) in batch.iter() {
+                    self.index_dex_actions(*principal, 0, &actions.0);
+                }
+
+                if batch.len() < batch_size {
+                    None
+                } else {
+                    Some(IndexRebuildStep::DexActions(
+                        batch.last().map(|(principal, _actions)| *principal),
+                    ))
+                }
+            }
+        };
+
+        let is_rebuilding = next_step.is_some();
+        let _ = self.indexes_checkpoint.set(IndexesCheckpoint {
+            version: checkpoint.version,
+            rebuild: next_step,
+        });
+        is_rebuilding
+    }
+
+    // Removes at most batch_size entries of the indexes, returns true once they are empty
+    fn clear_indexes(&mut self, batch_size: usize) -> bool {
+        let mut remaining = batch_size;
+        remaining -= clear_entries(&mut self.minted_deposits, remaining);
+        remaining -= clear_entries(&mut self.recent_withdrawal_fees, remaining);
+        remaining -= clear_entries(&mut self.sla_tracked_txs, remaining);
+        remaining -= clear_entries(&mut self.pending_unverified_txs, remaining);
+        remaining -= clear_entries(&mut self.reconciliation_queue, remaining);
+
+        // Certified leaves are also removed from the heap tree
+        let certified_leaves: Vec<Vec<u8>> = self.certified_leaves.keys().take(remaining).collect();
+        for key in certified_leaves.iter() {
+            self.uncertify_leaf(key);
+        }
+        remaining -= certified_leaves.len();
+
+        remaining > 0
+    }
+
+    pub fn get_sla_thresholds(&self, chain_id: &ChainId) -> SlaThresholds {
+        self.sla_thresholds.get(chain_id).unwrap_or_default()
+    }
+
+    pub fn update_sla_thresholds(
+        &mut self,
+        chain_id: ChainId,
+        icp_to_evm_accepted: Option<u64>,
+        icp_to_evm_created: Option<u64>,
+        icp_to_evm_signed: Option<u64>,
+        evm_to_icp_accepted: Option<u64>,
+    ) {
+        let thresholds = self.get_sla_thresholds(&chain_id);
+        self.sla_thresholds.insert(
+            chain_id,
+            SlaThresholds {
+                icp_to_evm_accepted: icp_to_evm_accepted.unwrap_or(thresholds.icp_to_evm_accepted),
+                icp_to_evm_created: icp_to_evm_created.unwrap_or(thresholds.icp_to_evm_created),
+                icp_to_evm_signed: icp_to_evm_signed.unwrap_or(thresholds.icp_to_evm_signed),
+                evm_to_icp_accepted: evm_to_icp_accepted.unwrap_or(thresholds.evm_to_icp_accepted),
+            },
+        );
+    }
+
+    // Compares the time every transaction spent in its current non-terminal status against the
+    // sla of its chain, rebuilds the stuck set and returns the newly stuck transactions.
+    // Only the transactions of the sla tracked index are checked, terminal ones are never scanned.
+    pub fn update_stuck_transactions(&mut self, now: u64) -> Vec<StuckTxKey> {
+        let custom_thresholds: STDBTreeMap<ChainId, SlaThresholds> =
+            self.sla_thresholds.iter().collect();
+        let thresholds_for =
+            |chain_id: &ChainId| custom_thresholds.get(chain_id).copied().unwrap_or_default();
+
+        let mut stuck = STDBTreeMap::new();
+
+        for key in self.sla_tracked_txs.keys() {
+            let (status_since, threshold) = match &key {
+                StuckTxKey::IcpToEvm(identifier) => {
+                    let Some(tx) = self.icp_to_evm_txs.get(identifier) else {
+                        continue;
+                    };
+                    (
+                        tx.status_updated_at.unwrap_or(tx.time),
+                        thresholds_for(&tx.chain_id).icp_to_evm_threshold(&tx.status),
+                    )
+                }
+                StuckTxKey::EvmToIcp(identifier) => {
+                    let Some(tx) = self.evm_to_icp_txs.get(identifier) else {
+                        continue;
+                    };
+                    (
+                        tx.status_updated_at.unwrap_or(tx.time),
+                        thresholds_for(&tx.chain_id).evm_to_icp_threshold(&tx.status),
+                    )
+                }
+            };
+
+            if let Some(threshold) =
+                threshold.filter(|threshold| exceeds_threshold(status_since, *threshold, now))
+            {
+                stuck.insert(key, (status_since, threshold));
+            }
+        }
+
+        // Transactions that moved on or were removed are not stuck anymore
+        let resolved: Vec<StuckTxKey> = self
+            .stuck_txs
+            .keys()
+            .filter(|key| !stuck.contains_key(key))
+            .collect();
+        for key in resolved {
+            self.stuck_txs.remove(&key);
+        }
+
+        let mut newly_stuck = vec![];
+        for (key, (status_since, threshold)) in stuck {
+            let previous = self.stuck_txs.get(&key);
+            let stuck_tx = stuck_tx(previous.as_ref(), status_since, threshold, now);
+            if previous.map_or(true, |previous| {
+                previous.detected_at != stuck_tx.detected_at
+            }) {
+                newly_stuck.push(key.clone());
+            }
+
+            self.stuck_txs.insert(key, stuck_tx);
+        }
+
+        newly_stuck
+    }
+
+    pub fn get_stuck_transactions(&self, now: u64) -> Vec<CandidStuckTransaction> {
+        self.stuck_txs
+            .iter()
+            .filter_map(|(key, stuck_tx)| {
+                let transaction = match key {
+                    StuckTxKey::IcpToEvm(identifier) => self
+                        .icp_to_evm_txs
+                        .get(&identifier)
+                        .map(|tx| Transaction::from(CandidIcpToEvm::from(tx))),
+                    StuckTxKey::EvmToIcp(identifier) => self
+                        .evm_to_icp_txs
+                        .get(&identifier)
+                        .map(|tx| Transaction::from(CandidEvmToIcp::from(tx))),
+                }?;
+
+                Some(CandidStuckTransaction {
+                    transaction,
+                    status_since: stuck_tx.status_since,
+                    time_in_state: now.saturating_sub(stuck_tx.status_since),
+                    threshold: stuck_tx.threshold,
+                    detected_at: stuck_tx.detected_at,
+                })
+            })
+            .collect()
+    }
+
+    // Transactions whose ledger block was never checked, was not found in the last check
+    // or changed since the last check, matched and mismatched blocks are final
+    pub fn pending_reconciliations(
+        &self,
+        limit: usize,
+    ) -> Vec<(ReconciliationKey, ExpectedLedgerOperation)> {
+        self.reconciliation_queue.iter().take(limit).collect()
+    }
+
+    pub fn record_reconciliation(
+        &mut self,
+        key: ReconciliationKey,
+        expected: ExpectedLedgerOperation,
+        status: ReconciliationStatus,
+        checked_at: u64,
+    ) {
+        // The transaction stays queued if its block was not found, or if it changed during the check
+        if status != ReconciliationStatus::BlockNotFound
+            && self.reconciliation_queue.get(&key).as_ref() == Some(&expected)
+        {
+            self.reconciliation_queue.remove(&key);
+        }
+
+        self.ledger_reconciliations.insert(
+            key,
+            LedgerReconciliation {
+                expected,
+                status,
+                checked_at,
+            },
+        );
+    }
+
+    pub fn get_reconciliation_report(&self) -> ReconciliationReport {
+        let mut report = ReconciliationReport {
+            pending: self.reconciliation_queue.len(),
+            matched: 0,
+            mismatched: 0,
+            block_not_found: 0,
+            flagged: vec![],
+        };
+
+        for (key, reconciliation) in self.ledger_reconciliations.iter() {
+            match reconciliation.status {
+                ReconciliationStatus::Matched => {
+                    report.matched += 1;
+                    continue;
+                }
+                ReconciliationStatus::Mismatch(_) => report.mismatched += 1,
+                ReconciliationStatus::BlockNotFound => report.block_not_found += 1,
+            }
+
+            let transaction = match key {
+                ReconciliationKey::EvmToIcp(identifier) => self
+                    .evm_to_icp_txs
+                    .get(&identifier)
+                    .map(|tx| Transaction::from(CandidEvmToIcp::from(tx))),
+                ReconciliationKey::IcpToEvm(identifier) => self
+                    .icp_to_evm_txs
+                    .get(&identifier)
+                    .map(|tx| Transaction::from(CandidIcpToEvm::from(tx))),
+            };
+
+            if let Some(transaction) = transaction {
+                report
+                    .flagged
+                    .push(CandidLedgerReconciliation::new(transaction, reconciliation));
+            }
+        }
+
+        report
+    }
+
+    // Appends a block to the block log and certifies the new tip
+    pub fn append_block(&mut self, transition: StateTransition, now: u64) {
+        let parent_hash = self
+            .blocks
+            .last_key_value()
+            .map(|(_index, block)| block.hash);
+        let index = self.blocks.len();
+        let block = LoggedBlock::new(now, transition, parent_hash);
+        let hash = block.hash;
+
+        self.blocks.insert(index, block);
+        certify_tip(Some((index, hash)));
+    }
+
+    // Index and hash of the last block
+    pub fn block_log_tip(&self) -> Option<(u64, [u8; 32])> {
+        self.blocks
+            .last_key_value()
+            .map(|(index, block)| (index, block.hash))
+    }
+
+    pub fn block_log_length(&self) -> u64 {
+        self.blocks.len()
+    }
+
+    pub fn get_blocks(&self, start: u64, length: u64) -> Vec<(u64, LoggedBlock)> {
+        self.blocks
+            .range(start..start.saturating_add(length))
+            .collect()
+    }
+
+    // Gets all the transaction history for an evm address
+    pub fn get_transaction_for_address(&self, address: Address) -> Vec<Transaction> {
+        let result: Vec<Transaction> = self
+            .evm_to_icp_txs
+            .iter()
+            .filter(|(_id, tx)| tx.from_address == address)
+            .map(|(_id, tx)| Transaction::from(CandidEvmToIcp::from(tx)))
+            .chain(
+                self.icp_to_evm_txs
+                    .iter()
+                    .filter(|(_id, tx)| tx.destination == address)
+                    .map(|(_id, tx)| Transaction::from(CandidIcpToEvm::from(tx))),
+            )
+            .collect();
+
+        result
+    }
+
+    // Gets all the transaction history for a principal
+    pub fn get_transaction_for_principal(&self, principal_id: Principal) -> Vec<Transaction> {
+        let result: Vec<Transaction> = self
+            .evm_to_icp_txs
+            .iter()
+            .filter(|(_id, tx)| tx.principal == principal_id)
+            .map(|(_id, tx)| Transaction::from(CandidEvmToIcp::from(tx)))
+            .chain(
+                self.icp_to_evm_txs
+                    .iter()
+                    .filter(|(_id, tx)| tx.from == principal_id)
+                    .map(|(_id, tx)| Transaction::from(CandidIcpToEvm::from(tx))),
+            )
+            .chain(
+                self.get_dex_actions_for_principal(principal_id)
+                    .into_iter()
+                    .map(|action| Transaction::DexAction(action.into())),
+            )
+            .collect();
+
+        result
+    }
+
+    // Gets supported twin token pairs for both Appic and Dfinity NNS Twin tokens
+    pub fn get_supported_bridge_pairs(&self) -> Vec<TokenPair> {
+        self.supported_ckerc20_tokens
+            .values()
+            .filter(|bridge_pair| !bridge_pair.is_removed())
+            .map(|bridge_pair| {
+                // Update usd price
+                let icp_token_with_new_usd_price: IcpToken = IcpToken {
+                    usd_price: self
+                        .get_icp_token_price(&bridge_pair.icp_token.ledger_id)
+                        .unwrap_or("0".to_string()),
+                    ..bridge_pair.icp_token
+                };
+                TokenPair {
+                    evm_token: CandidEvmToken::from(bridge_pair.evm_token),
+                    icp_token: CandidIcpToken::from(icp_token_with_new_usd_price),
+                    operator: Operator::DfinityCkEthMinter,
+                }
+            })
+            .chain(
+                self.supported_twin_appic_tokens
+                    .values()
+                    .filter(|bridge_pair| !bridge_pair.is_removed())
+                    .map(|bridge_pair| {
+                        // Update usd price
+                        let icp_token_with_new_usd_price = IcpToken {
+                            usd_price: self
+                                .get_icp_token_price(&bridge_pair.icp_token.ledger_id)
+                                .unwrap_or("0".to_string()),
+                            ..bridge_pair.icp_token
+                        };
+                        TokenPair {
+                            evm_token: CandidEvmToken::from(bridge_pair.evm_token),
+                            icp_token: CandidIcpToken::from(icp_token_with_new_usd_price),
+                            operator: Operator::AppicMinter,
+                        }
+                    }),
+            )
+            .collect()
+    }
+
+    // All the routes (operators) that can bridge the given erc20 token
+    pub fn get_bridge_routes(&self, erc20_identifier: &Erc20Identifier) -> Vec<BridgeRoute> {
+        [
+            (
+                self.supported_ckerc20_tokens.get(erc20_identifier),
+                Operator::DfinityCkEthMinter,
+            ),
+            (
+                self.supported_twin_appic_tokens.get(erc20_identifier),
+                Operator::AppicMinter,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(bridge_pair, operator)| {
+            bridge_pair
+                .filter(|bridge_pair| !bridge_pair.is_removed())
+                .map(|bridge_pair| self.to_bridge_route(bridge_pair, operator))
+        })
+        .collect()
+    }
+
+    // All the routes (operators and evm tokens) that can bridge the given icrc token
+    pub fn get_bridge_routes_for_icrc(&self, ledger_id: &Principal) -> Vec<BridgeRoute> {
+        self.supported_ckerc20_tokens
+            .values()
+            .filter(|bridge_pair| {
+                bridge_pair.icp_token.ledger_id == *ledger_id && !bridge_pair.is_removed()
+            })
+            .map(|bridge_pair| self.to_bridge_route(bridge_pair, Operator::DfinityCkEthMinter))
+            .chain(
+                self.supported_twin_appic_tokens
+                    .values()
+                    .filter(|bridge_pair| {
+                        bridge_pair.icp_token.ledger_id == *ledger_id && !bridge_pair.is_removed()
+                    })
+                    .map(|bridge_pair| self.to_bridge_route(bridge_pair, Operator::AppicMinter)),
+            )
+            .collect()
+    }
+
+    // All the routes of the listed bridge pairs, with the erc20 token they bridge
+    pub fn get_all_bridge_routes(&self) -> Vec<(Erc20Identifier, BridgeRoute)> {
+        [
+            (&self.supported_ckerc20_tokens, Operator::DfinityCkEthMinter),
+            (&self.supported_twin_appic_tokens, Operator::AppicMinter),
+        ]
+        .into_iter()
+        .flat_map(|(bridge_pairs, operator)| {
+            bridge_pairs
+                .iter()
+                .filter(|(_erc20_identifier, bridge_pair)| !bridge_pair.is_removed())
+                .map(move |(erc20_identifier, bridge_pair)| {
+                    (
+                        erc20_identifier,
+                        self.to_bridge_route(bridge_pair, operator),
+                    )
+                })
+        })
+        .collect()
+    }
+
+    fn bridge_pairs_mut(
+        &mut self,
+        operator: Operator,
+    ) -> &mut BTreeMap<Erc20Identifier, BridgePair, StableMemory> {
+        match operator {
+            Operator::DfinityCkEthMinter => &mut self.supported_ckerc20_tokens,
+            Operator::AppicMinter => &mut self.supported_twin_appic_tokens,
+        }
+    }
+
+    // Records a listed bridge pair and refreshes its tokens,
+    // returns true if the pair is new, was removed before or now points to another icp ledger
+    pub fn record_bridge_pair(
+        &mut self,
+        operator: Operator,
+        erc20_identifier: Erc20Identifier,
+        bridge_pair: BridgePair,
+        now: u64,
+    ) -> bool {
+        let previous = self.bridge_pairs_mut(operator).get(&erc20_identifier);
+        let ledger_id = bridge_pair.icp_token.ledger_id;
+
+        let is_new = match previous {
+            Some(previous) if !previous.is_removed() => {
+                let previous_ledger_id = previous.icp_token.ledger_id;
+                if previous_ledger_id != ledger_id {
+                    self.record_bridge_pair_change(
+                        BridgePairChangeKind::Removed,
+                        operator,
+                        erc20_identifier.clone(),
+                        previous_ledger_id,
+                        now,
+                    );
+                }
+                previous_ledger_id != ledger_id
+            }
+            _ => true,
+        };
+
+        self.bridge_pairs_mut(operator)
+            .insert(erc20_identifier.clone(), bridge_pair);
+
+        if is_new {
+            self.record_bridge_pair_change(
+                BridgePairChangeKind::Added,
+                operator,
+                erc20_identifier,
+                ledger_id,
+                now,
+            );
+        }
+        self.certify_bridge_pairs();
+
+        is_new
+    }
+
+    // Soft deletes the pairs that are not listed anymore, wrapped icrc pairs are not listed
+    // by the ledger suite manager so they are kept.
+    // Returns the identifiers of the removed pairs
+    pub fn remove_unlisted_bridge_pairs(
+        &mut self,
+        operator: Operator,
+        listed: &BTreeSet<Erc20Identifier>,
+        now: u64,
+    ) -> Vec<Erc20Identifier> {
+        let unlisted: Vec<(Erc20Identifier, BridgePair)> = self
+            .bridge_pairs_mut(operator)
+            .iter()
+            .filter(|(erc20_identifier, bridge_pair)| {
+                !bridge_pair.is_removed()
+                    && !bridge_pair.evm_token.is_wrapped_icrc
+                    && !listed.contains(erc20_identifier)
+            })
+            .collect();
+
+        for (erc20_identifier, bridge_pair) in unlisted.iter() {
+            self.record_bridge_pair_change(
+                BridgePairChangeKind::Removed,
+                operator,
+                erc20_identifier.clone(),
+                bridge_pair.icp_token.ledger_id,
+                now,
+            );
+
+            self.bridge_pairs_mut(operator).insert(
+                erc20_identifier.clone(),
+                BridgePair {
+                    removed_at: Some(now),
+                    ..bridge_pair.clone()
+                },
+            );
+        }
+
+        if !unlisted.is_empty() {
+            self.certify_bridge_pairs();
+        }
+
+        unlisted
+            .into_iter()
+            .map(|(erc20_identifier, _)| erc20_identifier)
+            .collect()
+    }
+
+    fn record_bridge_pair_change(
+        &mut self,
+        kind: BridgePairChangeKind,
+        operator: Operator,
+        erc20_identifier: Erc20Identifier,
+        icp_ledger_id: Principal,
+        now: u64,
+    ) {
+        let id = BridgePairChangeId(now, self.bridge_pair_changes.len());
+
+        self.bridge_pair_changes.insert(
+            id,
+            BridgePairChange {
+                kind,
+                operator,
+                erc20_identifier,
+                icp_ledger_id,
+                timestamp: now,
+            },
+        );
+    }
+
+    // Returns true if the pair was not pending before
+    pub fn record_pending_bridge_pair(
+        &mut self,
+        key: PendingBridgePairKey,
+        icp_ledger_id: Principal,
+        reason: PendingBridgePairReason,
+        now: u64,
+    ) -> bool {
+        let previous = self.pending_bridge_pairs.get(&key);
+        let is_new = previous.is_none();
+
+        let pending_pair = match previous {
+            Some(previous) => PendingBridgePair {
+                icp_ledger_id,
+                reason,
+                last_seen_at: now,
+                ..previous
+            },
+            None => PendingBridgePair {
+                icp_ledger_id,
+                reason,
+                first_seen_at: now,
+                last_seen_at: now,
+                last_error: None,
+            },
+        };
+
+        self.pending_bridge_pairs.insert(key, pending_pair);
+
+        is_new
+    }
+
+    pub fn remove_pending_bridge_pair(&mut self, key: &PendingBridgePairKey) {
+        self.pending_bridge_pairs.remove(key);
+    }
+
+    // Drops the pending pairs of an operator that are not listed anymore
+    pub fn remove_unlisted_pending_bridge_pairs(
+        &mut self,
+        operator: Operator,
+        listed: &BTreeSet<Erc20Identifier>,
+    ) {
+        let unlisted: Vec<PendingBridgePairKey> = self
+            .pending_bridge_pairs
+            .keys()
+            .filter(|key| key.0 == operator && !listed.contains(&key.1))
+            .collect();
+
+        for key in unlisted.iter() {
+            self.pending_bridge_pairs.remove(key);
+        }
+    }
+
+    // Records the error of fetching the metadata of a missing icp token
+    pub fn record_pending_bridge_pair_error(&mut self, icp_ledger_id: &Principal, error: String) {
+        let pending_pairs: Vec<(PendingBridgePairKey, PendingBridgePair)> = self
+            .pending_bridge_pairs
+            .iter()
+            .filter(|(_, pending_pair)| pending_pair.icp_ledger_id == *icp_ledger_id)
+            .collect();
+
+        for (key, pending_pair) in pending_pairs {
+            self.pending_bridge_pairs.insert(
+                key,
+                PendingBridgePair {
+                    last_error: Some(error.clone()),
+                    ..pending_pair
+                },
+            );
+        }
+    }
+
+    pub fn get_pending_bridge_pairs(&self) -> Vec<(PendingBridgePairKey, PendingBridgePair)> {
+        self.pending_bridge_pairs.iter().collect()
+    }
+
+    // Icp tokens that block at least one pending pair
+    pub fn get_pending_bridge_pairs_missing_icp_tokens(&self) -> BTreeSet<Principal> {
+        self.pending_bridge_pairs
+            .values()
+            .filter(|pending_pair| pending_pair.reason.is_icp_token_missing())
+            .map(|pending_pair| pending_pair.icp_ledger_id)
+            .collect()
+    }
+
+    // Changes recorded at or after the given timestamp, oldest first
+    pub fn get_bridge_pair_changes(
+        &self,
+        since: u64,
+    ) -> Vec<(BridgePairChangeId, BridgePairChange)> {
+        self.bridge_pair_changes
+            .range(BridgePairChangeId(since, 0)..)
+            .collect()
+    }
+
+    // Estimates the cost of withdrawing the given token based on the gas fees paid by
+    // the most recent finalized withdrawals and the current minter fees
+    pub fn estimate_withdrawal_cost(
+        &self,
+        erc20_identifier: &Erc20Identifier,
+    ) -> WithdrawalCostEstimate {
+        let chain_id = erc20_identifier.chain_id();
+
+        let fees: Vec<Erc20TokenAmount> = self
+            .recent_withdrawal_fees
+            .range(WithdrawalFeeKey::first_of(erc20_identifier.clone())..)
+            .take_while(|(key, _fee)| key.token == *erc20_identifier)
+            .map(|(_key, fee)| fee)
+            .collect();
+
+        let stats = gas_fee_stats(fees);
+
+        // All fees are paid in the native token
+        let native_token =
+            self.get_evm_token_by_identifier(&Erc20Identifier(Address::ZERO, chain_id));
+        let to_fee_amount = |amount: Erc20TokenAmount| CandidFeeAmount {
+            amount: amount.into(),
+            usd: native_token
+                .as_ref()
+                .and_then(|token| to_usd(amount, token.decimals, token.usd_price.as_deref()?)),
+        };
+
+        // Fees of the minters that can bridge this token, or of every minter on the chain
+        // if the token has no bridge pair (e.g. native tokens)
+        let mut operators: Vec<Operator> = self
+            .get_bridge_routes(erc20_identifier)
+            .into_iter()
+            .map(|route| route.operator)
+            .collect();
+        if operators.is_empty() {
+            operators = vec![Operator::DfinityCkEthMinter, Operator::AppicMinter];
+        }
+
+        let minter_fees = operators
+            .into_iter()
+            .filter_map(|operator| self.minters.get(&MinterKey(chain_id, operator)))
+            .map(|minter| CandidMinterFee {
+                operator: minter.operator,
+                minter_id: minter.id,
+                enabled: minter.enabled,
+                icp_to_evm_fee: to_fee_amount(minter.icp_to_evm_fee),
+            })
+            .collect();
+
+        WithdrawalCostEstimate {
+            sample_size: stats.as_ref().map_or(0, |stats| stats.sample_size as u64),
+            median_gas_fee: stats.as_ref().map(|stats| to_fee_amount(stats.median)),
+            p90_gas_fee: stats.as_ref().map(|stats| to_fee_amount(stats.p90)),
+            max_gas_fee: stats.as_ref().map(|stats| to_fee_amount(stats.max)),
+            minter_fees,
+        }
+    }
+
+    fn to_bridge_route(&self, bridge_pair: BridgePair, operator: Operator) -> BridgeRoute {
+        let minter = self
+            .minters
+            .get(&MinterKey(bridge_pair.evm_token.chain_id, operator));
+
+        // Update usd price
+        let icp_token = IcpToken {
+            usd_price: self
+                .get_icp_token_price(&bridge_pair.icp_token.ledger_id)
+                .unwrap_or("0".to_string()),
+            ..bridge_pair.icp_token
+        };
+
+        BridgeRoute {
+            operator,
+            is_wrapped_icrc: bridge_pair.evm_token.is_wrapped_icrc,
+            evm_token: CandidEvmToken::from(bridge_pair.evm_token),
+            icp_token: CandidIcpToken::from(icp_token),
+            minter_id: minter.as_ref().map(|minter| minter.id),
+            minter_enabled: minter.as_ref().is_some_and(|minter| minter.enabled),
+            icp_to_evm_fee: minter.map(|minter| minter.icp_to_evm_fee.into()),
+        }
+    }
+
+    // Searches for a transaction by hash in both evm_to_icp and icp_to_evm
+    fn get_transaction_by_hash(&self, tx_hash: &String, chain_id: ChainId) -> Option<Transaction> {
+        let evm_to_icp_id = EvmToIcpTxIdentifier::new(tx_hash, chain_id);
+
+        self.evm_to_icp_txs
+            .get(&evm_to_icp_id)
+            .map(|tx| Transaction::from(CandidEvmToIcp::from(tx)))
+            .or_else(|| {
+                self.icp_to_evm_txs
+                    .values()
+                    .find(|tx| {
+                        tx.chain_id == chain_id && tx.transaction_hash.as_ref() == Some(tx_hash)
+                    })
+                    .map(|tx| Transaction::from(CandidIcpToEvm::from(tx)))
+            })
+    }
+
+    // Searches for a transaction by burn index id in icp_to_evm_tx
+    fn get_transaction_by_burn_index(
+        &self,
+        ledger_burn_index: LedgerBurnIndex,
+        chain_id: ChainId,
+    ) -> Option<Transaction> {
+        let icp_to_evm_id = IcpToEvmIdentifier(ledger_burn_index, chain_id);
+
+        self.icp_to_evm_txs
+            .get(&icp_to_evm_id)
+            .map(|tx| Transaction::from(CandidIcpToEvm::from(tx)))
+    }
+
+    // Searches for a transaction by mint id in evm_to_icp_txs
+    fn get_transaction_by_mint_id(
+        &self,
+        ledger_mint_index: LedgerMintIndex,
+        chain_id: ChainId,
+    ) -> Option<Transaction> {
+        self.evm_to_icp_txs
+            .values()
+            .find(|tx| tx.chain_id == chain_id && tx.ledger_mint_index == Some(ledger_mint_index))
+            .map(|tx| Transaction::EvmToIcp(CandidEvmToIcp::from(tx)))
     }
 
     // Gets a single transaction by search param
@@ -696,15 +2141,79 @@ impl State {
             TransactionSearchParam::TxWithdrawalId(withdrawal_id) => self
                 .get_transaction_by_burn_index(nat_to_ledger_burn_index(&withdrawal_id), chain_id),
 
-            TransactionSearchParam::TxMintId(mint_id) => {
-                self.get_transaction_by_mint_id(nat_to_ledger_mint_index(&mint_id), chain_id)
-            }
+            TransactionSearchParam::TxMintId(mint_id) => {
+                self.get_transaction_by_mint_id(nat_to_ledger_mint_index(&mint_id), chain_id)
+            }
+        }
+    }
+
+    // Records a single evm token
+    pub fn record_evm_token(&mut self, identifier: Erc20Identifier, token: EvmToken, now: u64) {
+        self.insert_evm_token(identifier, token, now);
+    }
+
+    // Inserts or replaces an evm token, only new tokens are recorded in the block log
+    fn insert_evm_token(&mut self, identifier: Erc20Identifier, token: EvmToken, now: u64) {
+        if !self.evm_token_list.contains_key(&identifier) {
+            self.append_block(
+                StateTransition::TokenChange {
+                    token: ChangedToken::Evm(identifier.clone()),
+                    kind: TokenChangeKind::Added,
+                },
+                now,
+            );
+        }
+        self.store_evm_token(identifier, token);
+    }
+
+    // Stores an evm token and certifies its new version
+    fn store_evm_token(&mut self, identifier: Erc20Identifier, token: EvmToken) {
+        certify_token(
+            evm_token_key(&identifier),
+            Some(certified_hash(&CandidEvmToken::from(token.clone()))),
+        );
+        self.evm_token_list.insert(identifier, token);
+    }
+
+    // Returns false if the token does not exist
+    fn remove_stored_evm_token(&mut self, identifier: &Erc20Identifier) -> bool {
+        let is_removed = self.evm_token_list.remove(identifier).is_some();
+        if is_removed {
+            certify_token(evm_token_key(identifier), None);
+        }
+        is_removed
+    }
+
+    // Stores an icp token and certifies its new version
+    fn store_icp_token(&mut self, ledger_id: Principal, token: IcpToken) {
+        certify_token(
+            icp_token_key(&ledger_id),
+            Some(certified_hash(&CandidIcpToken::from(token.clone()))),
+        );
+        self.icp_token_list.insert(ledger_id, token);
+    }
+
+    // Returns false if the token does not exist
+    fn remove_stored_icp_token(&mut self, ledger_id: &Principal) -> bool {
+        let is_removed = self.icp_token_list.remove(ledger_id).is_some();
+        if is_removed {
+            certify_token(icp_token_key(ledger_id), None);
         }
+        is_removed
     }
 
-    // Records a single evm token
-    pub fn record_evm_token(&mut self, identifier: Erc20Identifier, token: EvmToken) {
-        self.evm_token_list.insert(identifier, token);
+    // Inserts or replaces an icp token, only new tokens are recorded in the block log
+    fn insert_icp_token(&mut self, ledger_id: Principal, token: IcpToken, now: u64) {
+        if !self.icp_token_list.contains_key(&ledger_id) {
+            self.append_block(
+                StateTransition::TokenChange {
+                    token: ChangedToken::Icp(ledger_id),
+                    kind: TokenChangeKind::Added,
+                },
+                now,
+            );
+        }
+        self.store_icp_token(ledger_id, token);
     }
 
     // update evm tokens price and volume based on cmc_id
@@ -734,7 +2243,7 @@ impl State {
 
         // Apply the updates
         for (key, new_token) in updates_to_apply {
-            self.evm_token_list.insert(key, new_token);
+            self.store_evm_token(key, new_token);
         }
     }
 
@@ -773,7 +2282,7 @@ impl State {
 
         // Apply the updates
         for (key, new_token) in updates_to_apply {
-            self.evm_token_list.insert(key, new_token);
+            self.store_evm_token(key, new_token);
         }
     }
 
@@ -844,23 +2353,251 @@ impl State {
         results
     }
 
+    // Searches evm and icp tokens by symbol, name or address, results are ranked by match quality
+    // and popularity and capped to `limit`
+    pub fn search_tokens(
+        &self,
+        query: &str,
+        filter: Option<TokenSearchFilter>,
+        limit: usize,
+    ) -> Vec<SearchedToken> {
+        let Some(query) = normalize_query(query) else {
+            return vec![];
+        };
+        let mut results: Vec<(MatchTier, SearchedToken)> = Vec::new();
+
+        let search_evm = !matches!(filter, Some(TokenSearchFilter::Icp));
+        let search_icp = matches!(filter, None | Some(TokenSearchFilter::Icp));
+
+        if search_evm {
+            let query_address = Address::from_str(&query).ok();
+
+            for (key, token) in self.evm_token_list.iter() {
+                if let Some(TokenSearchFilter::Evm(chain_id)) = &filter {
+                    if key.chain_id() != ChainId::from(chain_id) {
+                        continue;
+                    }
+                }
+
+                let tier = if query_address.as_ref() == Some(&token.erc20_contract_address) {
+                    Some(MatchTier::Exact)
+                } else {
+                    match_tier(
+                        &query,
+                        &token.symbol.to_lowercase(),
+                        &token.name.to_lowercase(),
+                    )
+                };
+
+                if let Some(tier) = tier {
+                    results.push((tier, SearchedToken::Evm(token)));
+                }
+            }
+        }
+
+        if search_icp {
+            let query_principal = Principal::from_text(&query).ok();
+
+            for token in self.icp_token_list.values() {
+                if token.is_disabled() {
+                    continue;
+                }
+
+                let tier = if query_principal == Some(token.ledger_id) {
+                    Some(MatchTier::Exact)
+                } else {
+                    match_tier(
+                        &query,
+                        &token.symbol.to_lowercase(),
+                        &token.name.to_lowercase(),
+                    )
+                };
+
+                if let Some(tier) = tier {
+                    results.push((tier, SearchedToken::Icp(token)));
+                }
+            }
+        }
+
+        sort_search_results(&mut results);
+
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(_tier, token)| token)
+            .collect()
+    }
+
     // Records all evm_tokens in bulk
-    pub fn record_evm_tokens_bulk(&mut self, tokens: Vec<EvmToken>) {
+    pub fn record_evm_tokens_bulk(&mut self, tokens: Vec<EvmToken>, now: u64) {
         tokens.into_iter().for_each(|token| {
-            self.evm_token_list
-                .insert(Erc20Identifier::from(&token), token);
+            self.insert_evm_token(Erc20Identifier::from(&token), token, now);
+        });
+    }
+
+    // Upserts the bundled token list, runtime managed fields of existing tokens are kept
+    // Bundled tokens that are no longer in the list are removed, runtime tokens are never removed
+    pub fn sync_bundled_evm_tokens(
+        &mut self,
+        bundled_tokens: Vec<EvmToken>,
+        version: String,
+        now: u64,
+    ) {
+        let bundled_identifiers: BTreeSet<Erc20Identifier> =
+            bundled_tokens.iter().map(Erc20Identifier::from).collect();
+
+        let removed: Vec<Erc20Identifier> = self
+            .evm_token_list
+            .iter()
+            .filter(|(identifier, token)| {
+                token.source == Some(EvmTokenSource::Bundled)
+                    && !bundled_identifiers.contains(identifier)
+            })
+            .map(|(identifier, _token)| identifier)
+            .collect();
+
+        for identifier in removed {
+            self.remove_evm_token(&identifier, now);
+        }
+
+        for token in bundled_tokens {
+            let identifier = Erc20Identifier::from(&token);
+            let merged = merge_bundled_evm_token(self.evm_token_list.get(&identifier), token);
+            self.insert_evm_token(identifier, merged, now);
+        }
+
+        let _ = self.evm_token_list_info.set(EvmTokenListInfo {
+            bundled_version: Some(version),
         });
     }
 
+    pub fn get_bundled_evm_tokens_version(&self) -> Option<String> {
+        self.evm_token_list_info.get().bundled_version.clone()
+    }
+
+    // Upserts tokens managed at runtime, existing prices and volumes are kept if not provided.
+    // Imported metadata is marked as runtime managed so that bundled list syncs do not overwrite it.
+    pub fn import_runtime_evm_tokens(&mut self, tokens: Vec<EvmToken>, now: u64) {
+        for token in tokens {
+            let identifier = Erc20Identifier::from(&token);
+            let previous = self.evm_token_list.get(&identifier);
+
+            let (usd_price, volume_usd_24h, mut managed_fields) = match previous {
+                Some(previous) => (
+                    token.usd_price.or(previous.usd_price),
+                    token.volume_usd_24h.or(previous.volume_usd_24h),
+                    previous.runtime_managed_fields.unwrap_or_default(),
+                ),
+                None => (token.usd_price, token.volume_usd_24h, vec![]),
+            };
+
+            let mut imported_fields = vec![
+                EvmTokenField::Name,
+                EvmTokenField::Symbol,
+                EvmTokenField::Decimals,
+                EvmTokenField::Logo,
+            ];
+            if token.cmc_id.is_some() {
+                imported_fields.push(EvmTokenField::CmcId);
+            }
+            for field in imported_fields {
+                if !managed_fields.contains(&field) {
+                    managed_fields.push(field);
+                }
+            }
+
+            self.insert_evm_token(
+                identifier,
+                EvmToken {
+                    usd_price,
+                    volume_usd_24h,
+                    runtime_managed_fields: Some(managed_fields),
+                    source: Some(EvmTokenSource::Runtime),
+                    ..token
+                },
+                now,
+            );
+        }
+    }
+
+    // Returns false if the token does not exist
+    pub fn remove_evm_token(&mut self, identifier: &Erc20Identifier, now: u64) -> bool {
+        if !self.remove_stored_evm_token(identifier) {
+            return false;
+        }
+
+        self.append_block(
+            StateTransition::TokenChange {
+                token: ChangedToken::Evm(identifier.clone()),
+                kind: TokenChangeKind::Removed,
+            },
+            now,
+        );
+        true
+    }
+
+    // Updates the given metadata fields and marks them as runtime managed
+    // Returns false if the token does not exist
+    pub fn update_evm_token_metadata(
+        &mut self,
+        identifier: &Erc20Identifier,
+        update: EvmTokenMetadataUpdate,
+        now: u64,
+    ) -> bool {
+        let Some(mut token) = self.evm_token_list.get(identifier) else {
+            return false;
+        };
+
+        let mut managed_fields = token.runtime_managed_fields.take().unwrap_or_default();
+        let mut mark_managed = |field: EvmTokenField| {
+            if !managed_fields.contains(&field) {
+                managed_fields.push(field);
+            }
+        };
+
+        if let Some(name) = update.name {
+            token.name = name;
+            mark_managed(EvmTokenField::Name);
+        }
+        if let Some(symbol) = update.symbol {
+            token.symbol = symbol;
+            mark_managed(EvmTokenField::Symbol);
+        }
+        if let Some(decimals) = update.decimals {
+            token.decimals = decimals;
+            mark_managed(EvmTokenField::Decimals);
+        }
+        if let Some(logo) = update.logo {
+            token.logo = logo;
+            mark_managed(EvmTokenField::Logo);
+        }
+        if let Some(cmc_id) = update.cmc_id {
+            token.cmc_id = Some(cmc_id);
+            mark_managed(EvmTokenField::CmcId);
+        }
+
+        token.runtime_managed_fields = Some(managed_fields);
+        self.store_evm_token(identifier.clone(), token);
+        self.append_block(
+            StateTransition::TokenChange {
+                token: ChangedToken::Evm(identifier.clone()),
+                kind: TokenChangeKind::Updated,
+            },
+            now,
+        );
+
+        true
+    }
+
     // Records a single icp token
-    pub fn record_icp_token(&mut self, ledger_id: Principal, token: IcpToken) {
-        self.icp_token_list.insert(ledger_id, token);
+    pub fn record_icp_token(&mut self, ledger_id: Principal, token: IcpToken, now: u64) {
+        self.insert_icp_token(ledger_id, token, now);
     }
 
     // Records all icp_tokens in bulk
-    pub fn record_icp_tokens_bulk(&mut self, tokens: Vec<IcpToken>) {
+    pub fn record_icp_tokens_bulk(&mut self, tokens: Vec<IcpToken>, now: u64) {
         tokens.into_iter().for_each(|token| {
-            self.icp_token_list.insert(token.ledger_id, token);
+            self.insert_icp_token(token.ledger_id, token, now);
         });
     }
 
@@ -876,14 +2613,228 @@ impl State {
         self.icp_token_list.values().collect()
     }
 
+    // Tokens that can be shown publicly, soft disabled tokens are excluded
+    // If verified is provided, only tokens with the same verified status are returned
+    pub fn get_enabled_icp_tokens(&self, verified: Option<bool>) -> Vec<IcpToken> {
+        self.icp_token_list
+            .values()
+            .filter(|token| !token.is_disabled())
+            .filter(|token| match verified {
+                Some(verified) => token.is_verified() == verified,
+                None => true,
+            })
+            .collect()
+    }
+
+    // Returns false if the token does not exist
+    pub fn set_icp_token_verified(
+        &mut self,
+        ledger_id: &Principal,
+        verified: bool,
+        now: u64,
+    ) -> bool {
+        match self.icp_token_list.get(ledger_id) {
+            Some(token) => {
+                self.store_icp_token(
+                    *ledger_id,
+                    IcpToken {
+                        verified: Some(verified),
+                        ..token
+                    },
+                );
+                self.append_block(
+                    StateTransition::TokenChange {
+                        token: ChangedToken::Icp(*ledger_id),
+                        kind: TokenChangeKind::Updated,
+                    },
+                    now,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Blocking a token also removes the already listed tokens it matches
+    pub fn add_to_icp_token_blocklist(
+        &mut self,
+        blocked_token: BlockedToken,
+        entry: BlocklistEntry,
+        now: u64,
+    ) {
+        match &blocked_token {
+            BlockedToken::LedgerId(ledger_id) => self.remove_icp_token(ledger_id, now),
+            BlockedToken::SymbolPattern(_) => {
+                let blocked_ledger_ids: Vec<Principal> = self
+                    .icp_token_list
+                    .iter()
+                    .filter(|(ledger_id, token)| blocked_token.blocks(ledger_id, &token.symbol))
+                    .map(|(ledger_id, _)| ledger_id)
+                    .collect();
+
+                for ledger_id in blocked_ledger_ids {
+                    self.remove_icp_token(&ledger_id, now);
+                }
+            }
+        }
+
+        self.icp_token_blocklist.insert(blocked_token, entry);
+    }
+
+    pub fn remove_from_icp_token_blocklist(&mut self, blocked_token: &BlockedToken) {
+        self.icp_token_blocklist.remove(blocked_token);
+    }
+
+    pub fn get_icp_token_blocklist(&self) -> Vec<(BlockedToken, BlocklistEntry)> {
+        self.icp_token_blocklist.iter().collect()
+    }
+
+    pub fn is_ledger_blocked(&self, ledger_id: &Principal) -> bool {
+        self.icp_token_blocklist
+            .contains_key(&BlockedToken::LedgerId(*ledger_id))
+    }
+
+    // Checks both the ledger id and symbol patterns
+    pub fn is_icp_token_blocked(&self, ledger_id: &Principal, symbol: &str) -> bool {
+        self.icp_token_blocklist
+            .keys()
+            .any(|blocked_token| blocked_token.blocks(ledger_id, symbol))
+    }
+
+    // Tokens that failed their last validation and might be disabled or removed
+    pub fn get_icp_tokens_at_risk(&self) -> Vec<IcpToken> {
+        self.icp_token_list
+            .values()
+            .filter(|token| token.validation_failures.is_some())
+            .collect()
+    }
+
+    // Records the metadata of a validated token, the token is read again since it might have changed
+    // during the validation calls: its price, rank and verification are kept from the current token.
+    // Returns false if the token was removed or blocked during the validation
+    pub fn record_validated_icp_token(&mut self, validated_token: IcpToken) -> bool {
+        let Some(current_token) = self.icp_token_list.get(&validated_token.ledger_id) else {
+            return false;
+        };
+
+        if self.is_icp_token_blocked(&validated_token.ledger_id, &validated_token.symbol) {
+            return false;
+        }
+
+        self.store_icp_token(
+            validated_token.ledger_id,
+            with_previous_standards(
+                IcpToken {
+                    usd_price: current_token.usd_price.clone(),
+                    rank: current_token.rank,
+                    listed_on_appic_dex: current_token.listed_on_appic_dex,
+                    verified: current_token.verified,
+                    ..validated_token
+                },
+                &current_token,
+            ),
+        );
+        true
+    }
+
+    // Records a failed validation for a token
+    // Returns true if the token was removed after its grace period was over
+    pub fn record_icp_token_validation_failure(
+        &mut self,
+        ledger_id: &Principal,
+        failure: TokenValidationFailure,
+        now: u64,
+    ) -> bool {
+        let Some(token) = self.icp_token_list.get(ledger_id) else {
+            return false;
+        };
+
+        let config = self.token_validation_config.get().clone();
+        let validation_failures = IcpTokenValidationFailures::record_failure(
+            token.validation_failures.clone(),
+            failure,
+            now,
+            &config,
+        );
+
+        if validation_failures.should_be_removed(now, &config) {
+            self.remove_icp_token(ledger_id, now);
+            return true;
+        }
+
+        self.store_icp_token(
+            *ledger_id,
+            IcpToken {
+                validation_failures: Some(validation_failures),
+                ..token
+            },
+        );
+
+        false
+    }
+
+    pub fn get_token_validation_config(&self) -> TokenValidationConfig {
+        self.token_validation_config.get().clone()
+    }
+
+    pub fn update_token_validation_config(
+        &mut self,
+        max_strikes: Option<u32>,
+        removal_grace_period: Option<u64>,
+    ) {
+        let config = self.token_validation_config.get().clone();
+        let _ = self.token_validation_config.set(TokenValidationConfig {
+            max_strikes: max_strikes.unwrap_or(config.max_strikes),
+            removal_grace_period: removal_grace_period.unwrap_or(config.removal_grace_period),
+        });
+    }
+
     pub fn get_icp_token_price(&self, ledger_id: &Principal) -> Option<String> {
         self.icp_token_list
             .get(ledger_id)
             .map(|token| token.usd_price)
     }
 
-    pub fn remove_icp_token(&mut self, ledger_id: &Principal) {
-        self.icp_token_list.remove(ledger_id);
+    pub fn remove_icp_token(&mut self, ledger_id: &Principal, now: u64) {
+        if self.remove_stored_icp_token(ledger_id) {
+            self.append_block(
+                StateTransition::TokenChange {
+                    token: ChangedToken::Icp(*ledger_id),
+                    kind: TokenChangeKind::Removed,
+                },
+                now,
+            );
+        }
+    }
+
+    // Returns up to `limit` icp tokens ordered by ledger id, starting after `start_after`
+    pub fn get_icp_tokens_after(
+        &self,
+        start_after: Option<Principal>,
+        limit: usize,
+    ) -> Vec<IcpToken> {
+        let lower_bound = match start_after {
+            Some(ledger_id) => Bound::Excluded(ledger_id),
+            None => Bound::Unbounded,
+        };
+
+        self.icp_token_list
+            .range((lower_bound, Bound::Unbounded))
+            .take(limit)
+            .map(|(_ledger_id, token)| token)
+            .collect()
+    }
+
+    pub fn get_token_validation_checkpoint(&self) -> Option<Principal> {
+        self.token_validation_checkpoint.get().last_validated_ledger
+    }
+
+    pub fn set_token_validation_checkpoint(&mut self, last_validated_ledger: Option<Principal>) {
+        let _ = self
+            .token_validation_checkpoint
+            .set(TokenValidationCheckpoint {
+                last_validated_ledger,
+            });
     }
 
     pub fn update_icp_token_usd_price(
@@ -899,7 +2850,7 @@ impl State {
             } else {
                 Some(false)
             };
-            self.icp_token_list.insert(
+            self.store_icp_token(
                 ledger_id,
                 IcpToken {
                     usd_price: new_usd_price,
@@ -911,15 +2862,190 @@ impl State {
         };
     }
 
-    pub fn record_dex_action_for_principal(&mut self, principal: Principal, dex_action: DexAction) {
-        if let Some(previous_actions) = self.dex_actions_list.get(&principal) {
-            let mut new_user_actions = previous_actions;
-            new_user_actions.0.push(dex_action);
-            self.dex_actions_list.insert(principal, new_user_actions);
-        } else {
-            self.dex_actions_list
-                .insert(principal, UserDexActions(vec![dex_action]));
+    pub fn record_dex_action_for_principal(
+        &mut self,
+        principal: Principal,
+        dex_action: DexAction,
+        now: u64,
+    ) {
+        self.append_block(
+            StateTransition::DexAction {
+                principal,
+                action: dex_action.clone(),
+            },
+            now,
+        );
+        self.notify_subscribers(
+            |filter| filter.matches_dex_action(&principal),
+            &Transaction::DexAction(dex_action.clone().into()),
+            now,
+        );
+
+        let mut user_actions = self
+            .dex_actions_list
+            .get(&principal)
+            .unwrap_or(UserDexActions(vec![]));
+        let index = user_actions.0.len();
+        user_actions.0.push(dex_action);
+        self.index_dex_actions(principal, index, &user_actions.0[index..]);
+        self.dex_actions_list.insert(principal, user_actions);
+    }
+
+    // Links the swaps of a principal that are not linked yet to the minted deposits that funded them.
+    // Each deposit funds at most one swap, the most recent matching deposit is preferred.
+    pub fn link_swaps_to_bridge_txs(&mut self, principal: Principal) {
+        let Some(mut user_actions) = self.dex_actions_list.get(&principal) else {
+            return;
+        };
+
+        let unlinked_swap_times: Vec<u64> = user_actions
+            .0
+            .iter()
+            .filter_map(|action| match action {
+                DexAction::Swap {
+                    timestamp,
+                    funded_by: None,
+                    ..
+                } => Some(*timestamp),
+                _ => None,
+            })
+            .collect();
+
+        let (Some(first_swap), Some(last_swap)) = (
+            unlinked_swap_times.iter().min().copied(),
+            unlinked_swap_times.iter().max().copied(),
+        ) else {
+            return;
+        };
+
+        // Only the deposits made within the funding window of the unlinked swaps can fund them
+        let first_key = MintedDepositKey::first_at(
+            principal,
+            first_swap.saturating_sub(SWAP_FUNDING_WINDOW_NS),
+        );
+        let mut minted_deposits: Vec<(EvmToIcpTxIdentifier, EvmToIcpTx)> = self
+            .minted_deposits
+            .range(first_key..)
+            .take_while(|(key, ())| key.principal == principal && key.time <= last_swap)
+            .filter_map(|(key, ())| {
+                self.evm_to_icp_txs
+                    .get(&key.identifier)
+                    .map(|tx| (key.identifier, tx))
+            })
+            .collect();
+
+        if minted_deposits.is_empty() {
+            return;
+        }
+
+        // Most recent deposits first
+        minted_deposits.sort_by(|a, b| b.1.time.cmp(&a.1.time));
+
+        let mut linked_deposits: std::collections::BTreeSet<EvmToIcpTxIdentifier> = user_actions
+            .0
+            .iter()
+            .filter_map(|action| match action {
+                DexAction::Swap { funded_by, .. } => funded_by.clone(),
+                _ => None,
+            })
+            .collect();
+
+        let mut updated = vec![];
+        for (index, action) in user_actions.0.iter_mut().enumerate() {
+            if let DexAction::Swap {
+                final_amount_in,
+                timestamp,
+                token_in,
+                funded_by,
+                ..
+            } = action
+            {
+                if funded_by.is_some() {
+                    continue;
+                }
+
+                let funding_tx = minted_deposits.iter().find(|(id, tx)| {
+                    !linked_deposits.contains(id)
+                        && is_swap_funded_by(tx, principal, *token_in, *final_amount_in, *timestamp)
+                });
+
+                if let Some((id, _tx)) = funding_tx {
+                    log!(
+                        INFO,
+                        "[Swap Correlation] Linked swap of {} at {} to bridge tx {:?}",
+                        principal,
+                        timestamp,
+                        id
+                    );
+                    linked_deposits.insert(id.clone());
+                    *funded_by = Some(id.clone());
+                    updated.push(index);
+                }
+            }
+        }
+
+        for index in updated.iter() {
+            self.index_dex_actions(
+                principal,
+                *index,
+                std::slice::from_ref(&user_actions.0[*index]),
+            );
         }
+        if !updated.is_empty() {
+            self.dex_actions_list.insert(principal, user_actions);
+        }
+    }
+
+    pub fn certify_bridge_pairs(&self) {
+        certify_bridge_pairs(
+            self.get_supported_bridge_pairs(),
+            self.get_all_bridge_routes(),
+        );
+    }
+
+    fn certify_all_tokens(&self) {
+        let evm_tokens = self.evm_token_list.iter().map(|(identifier, token)| {
+            (
+                evm_token_key(&identifier),
+                certified_hash(&CandidEvmToken::from(token)),
+            )
+        });
+        let icp_tokens = self.icp_token_list.iter().map(|(ledger_id, token)| {
+            (
+                icp_token_key(&ledger_id),
+                certified_hash(&CandidIcpToken::from(token)),
+            )
+        });
+        certify_tokens(evm_tokens.chain(icp_tokens).collect());
+    }
+
+    // The certified tree is kept in heap memory and lost on upgrades, its tip, bridge pairs and
+    // tokens are certified right away and its transactions are reloaded from stable memory in batches
+    pub fn start_certified_tree_reload(&mut self) {
+        certify_tip(self.block_log_tip());
+        self.certify_bridge_pairs();
+        self.certify_all_tokens();
+        start_leaves_reload();
+    }
+
+    // Reloads at most batch_size leaves, returns true if the reload is not complete yet
+    pub fn reload_certified_tree_batch(&mut self, batch_size: usize) -> bool {
+        let Some(cursor) = leaves_reload_cursor() else {
+            return false;
+        };
+
+        let batch: Vec<(Vec<u8>, [u8; 32])> = self
+            .certified_leaves
+            .range((
+                cursor.map_or(Bound::Unbounded, Bound::Excluded),
+                Bound::Unbounded,
+            ))
+            .take(batch_size)
+            .collect();
+
+        let is_complete = batch.len() < batch_size;
+        reload_leaves(batch, is_complete);
+        !is_complete
     }
 
     pub fn get_dex_actions_for_principal(&self, principal: Principal) -> Vec<DexAction> {
@@ -935,6 +3061,7 @@ impl State {
         ledger_id: Principal,
         symbol: String,
         chain_id: ChainId,
+        now: u64,
     ) {
         if let Some(EvmToken {
             chain_id: _,
@@ -947,6 +3074,8 @@ impl State {
             cmc_id: _,
             usd_price,
             volume_usd_24h: _,
+            runtime_managed_fields: _,
+            source: _,
         }) = self.get_evm_token_by_identifier(&Erc20Identifier(address, chain_id))
         {
             let icp_token = IcpToken {
@@ -960,11 +3089,112 @@ impl State {
                 token_type: IcpTokenType::ICRC2,
                 rank: Some(1),
                 listed_on_appic_dex: Some(false),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             };
 
-            self.icp_token_list.insert(ledger_id, icp_token);
+            self.insert_icp_token(ledger_id, icp_token, now);
         }
     }
+
+    // Records a new request submitted by the ledger suite manager,
+    // the status of an already known request is kept
+    pub fn record_twin_ledger_request(
+        &mut self,
+        identifier: Erc20Identifier,
+        creator: Principal,
+        icp_token_symbol: String,
+        fee_charged: Erc20TwinLedgerSuiteFee,
+        now: u64,
+    ) {
+        let request = match self.twin_ledger_requests.get(&identifier) {
+            Some(request) => Erc20TwinLedgerSuiteRequest {
+                creator: Some(creator),
+                icp_token_symbol: Some(icp_token_symbol),
+                fee_charged: Some(fee_charged),
+                updated_at: now,
+                ..request
+            },
+            None => Erc20TwinLedgerSuiteRequest {
+                creator: Some(creator),
+                evm_token_contract: identifier.erc20_address(),
+                evm_token_chain_id: identifier.chain_id(),
+                icp_token_symbol: Some(icp_token_symbol),
+                status: Erc20TwinLedgerSuiteStatus::PendingApproval,
+                fee_charged: Some(fee_charged),
+                icp_ledger_id: None,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+
+        self.twin_ledger_requests.insert(identifier, request);
+    }
+
+    // Updates the status of a request, requests that were not submitted through the
+    // ledger suite manager are recorded without creator and fee
+    pub fn update_twin_ledger_request_status(
+        &mut self,
+        identifier: Erc20Identifier,
+        status: Erc20TwinLedgerSuiteStatus,
+        icp_ledger_id: Option<Principal>,
+        icp_token_symbol: Option<String>,
+        now: u64,
+    ) {
+        let request = match self.twin_ledger_requests.get(&identifier) {
+            Some(mut request) => {
+                // Nothing changed, no need to touch the request
+                if request.status >= status
+                    && (icp_ledger_id.is_none() || request.icp_ledger_id == icp_ledger_id)
+                {
+                    return;
+                }
+
+                request.advance_status(status, icp_ledger_id, now);
+                if request.icp_token_symbol.is_none() {
+                    request.icp_token_symbol = icp_token_symbol;
+                }
+                request
+            }
+            None => Erc20TwinLedgerSuiteRequest {
+                creator: None,
+                evm_token_contract: identifier.erc20_address(),
+                evm_token_chain_id: identifier.chain_id(),
+                icp_token_symbol,
+                status,
+                fee_charged: None,
+                icp_ledger_id,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+
+        self.twin_ledger_requests.insert(identifier, request);
+    }
+
+    pub fn get_twin_ledger_requests(
+        &self,
+        creator: Option<Principal>,
+    ) -> Vec<Erc20TwinLedgerSuiteRequest> {
+        self.twin_ledger_requests
+            .values()
+            .filter(|request| creator.is_none() || request.creator == creator)
+            .collect()
+    }
+}
+
+// Removes at most limit entries of the map, returns the number of removed entries
+fn clear_entries<K, V>(map: &mut BTreeMap<K, V, StableMemory>, limit: usize) -> usize
+where
+    K: Storable + Ord + Clone,
+    V: Storable,
+{
+    let keys: Vec<K> = map.keys().take(limit).collect();
+    for key in keys.iter() {
+        map.remove(key);
+    }
+    keys.len()
 }
 
 pub fn is_native_token(address: &Address) -> bool {
@@ -1028,12 +3258,16 @@ pub fn mutate_state<F, R>(f: F) -> R
 where
     F: FnOnce(&mut State) -> R,
 {
-    STATE.with(|cell| {
+    let result = STATE.with(|cell| {
         f(cell
             .borrow_mut()
             .as_mut()
             .expect("BUG: state is not initialized"))
-    })
+    });
+
+    // The certified data is set once per mutation instead of once per certified leaf
+    certify_if_changed();
+    result
 }
 
 // State configuration
@@ -1052,6 +3286,30 @@ thread_local! {
                 evm_token_list:BTreeMap::init(evm_token_list_id()),
                 icp_token_list:BTreeMap::init(icp_token_list_id()),
                 dex_actions_list:BTreeMap::init(dex_actions_list()),
-                dex_info:Cell::init(dex_info_id(),DexInfo{ id: Principal::from_text(DEX_CANISTER_ID).unwrap(), last_observed_event: 0, last_scraped_event: 0 }).expect("DEX_INFO initiaion failed")}),
+                dex_info:Cell::init(dex_info_id(),DexInfo{ id: Principal::from_text(DEX_CANISTER_ID).unwrap(), last_observed_event: 0, last_scraped_event: 0 }).expect("DEX_INFO initiaion failed"),
+                token_validation_checkpoint:Cell::init(token_validation_checkpoint_id(),TokenValidationCheckpoint::default()).expect("TOKEN_VALIDATION_CHECKPOINT initiaion failed"),
+                token_validation_config:Cell::init(token_validation_config_id(),TokenValidationConfig::default()).expect("TOKEN_VALIDATION_CONFIG initiaion failed"),
+                icp_token_blocklist:BTreeMap::init(icp_token_blocklist_id()),
+                evm_token_list_info:Cell::init(evm_token_list_info_id(),EvmTokenListInfo::default()).expect("EVM_TOKEN_LIST_INFO initiaion failed"),
+                twin_ledger_requests:BTreeMap::init(erc20_twin_ledger_requests_id()),
+                bridge_pair_changes:BTreeMap::init(bridge_pair_changes_id()),
+                pending_bridge_pairs:BTreeMap::init(pending_bridge_pairs_id()),
+                dex_sources:BTreeMap::init(dex_sources_id()),
+                sla_thresholds:BTreeMap::init(sla_thresholds_id()),
+                stuck_txs:BTreeMap::init(stuck_txs_id()),
+                unverified_tx_config:Cell::init(unverified_tx_config_id(),UnverifiedTxConfig::default()).expect("UNVERIFIED_TX_CONFIG initiaion failed"),
+                allowed_relayers:BTreeMap::init(allowed_relayers_id()),
+                ledger_reconciliations:BTreeMap::init(ledger_reconciliations_id()),
+                blocks:BTreeMap::init(block_log_id()),
+                subscriptions:BTreeMap::init(subscriptions_id()),
+                maintenance_mode:Cell::init(maintenance_mode_id(),MaintenanceMode::default()).expect("MAINTENANCE_MODE initiaion failed"),
+                minted_deposits:BTreeMap::init(minted_deposits_id()),
+                indexes_checkpoint:Cell::init(indexes_checkpoint_id(),IndexesCheckpoint::default()).expect("INDEXES_CHECKPOINT initiaion failed"),
+                recent_withdrawal_fees:BTreeMap::init(recent_withdrawal_fees_id()),
+                sla_tracked_txs:BTreeMap::init(sla_tracked_txs_id()),
+                pending_unverified_txs:BTreeMap::init(pending_unverified_txs_id()),
+                reconciliation_queue:BTreeMap::init(reconciliation_queue_id()),
+                certified_leaves:BTreeMap::init(certified_leaves_id()),
+                allowed_subscribers:BTreeMap::init(allowed_subscribers_id())}),
     );
 }
@@ -0,0 +1,464 @@
+// Append only log of the state transitions recorded by the logger, exposed as ICRC-3 blocks.
+// Every block holds the hash of its parent (phash), the time it was appended (ts), its block type (btype)
+// and the transition (tx), the hash of the last block is certified.
+//
+// Block types:
+// - evm_to_icp: new evm to icp transaction or status change, tx = {tx_hash, chain_id, status, principal, value, ledger_id?, ledger_mint_index?}
+// - icp_to_evm: new icp to evm transaction or status change, tx = {burn_index, chain_id, status, from, destination, withdrawal_amount, ledger_id?, tx_hash?}
+// - dex_action: new dex action, tx = {principal, kind, dex_id?, ...amounts and tokens of the action}
+// - token_change: listed token added, updated or removed, tx = {op, ledger_id} for icp tokens, {op, chain_id, address} for evm tokens
+
+use std::collections::BTreeMap;
+
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc3::blocks::SupportedBlockType;
+use minicbor::{Decode, Encode};
+use serde_bytes::ByteBuf;
+
+use crate::address::Address;
+use crate::numeric::{Erc20TokenAmount, LedgerBurnIndex, LedgerMintIndex};
+use crate::state::dex::types::DexAction;
+use crate::state::types::{
+    ChainId, Erc20Identifier, EvmToIcpStatus, EvmToIcpTx, EvmToIcpTxIdentifier, IcpToEvmIdentifier,
+    IcpToEvmStatus, IcpToEvmTx, TransactionHash,
+};
+
+// Maximum number of blocks returned by a single icrc3_get_blocks call
+pub const MAX_BLOCKS_PER_RESPONSE: u64 = 100;
+
+pub const BLOCK_TYPES_URL: &str =
+    "https://github.com/Appic-Solutions/chain-fusion-helper/blob/main/transaction_logger/src/state/block_log.rs";
+
+pub const EVM_TO_ICP_BLOCK_TYPE: &str = "evm_to_icp";
+pub const ICP_TO_EVM_BLOCK_TYPE: &str = "icp_to_evm";
+pub const DEX_ACTION_BLOCK_TYPE: &str = "dex_action";
+pub const TOKEN_CHANGE_BLOCK_TYPE: &str = "token_change";
+
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    [
+        EVM_TO_ICP_BLOCK_TYPE,
+        ICP_TO_EVM_BLOCK_TYPE,
+        DEX_ACTION_BLOCK_TYPE,
+        TOKEN_CHANGE_BLOCK_TYPE,
+    ]
+    .into_iter()
+    .map(|block_type| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: BLOCK_TYPES_URL.to_string(),
+    })
+    .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum TokenChangeKind {
+    #[n(0)]
+    Added,
+    #[n(1)]
+    Updated,
+    #[n(2)]
+    Removed,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum ChangedToken {
+    #[n(0)]
+    Evm(#[n(0)] Erc20Identifier),
+    #[n(1)]
+    Icp(#[cbor(n(0), with = "crate::cbor::principal")] Principal),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum StateTransition {
+    #[n(0)]
+    EvmToIcp {
+        #[n(0)]
+        identifier: EvmToIcpTxIdentifier,
+        #[n(1)]
+        status: EvmToIcpStatus,
+        #[cbor(n(2), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(3)]
+        value: Erc20TokenAmount,
+        #[cbor(n(4), with = "crate::cbor::principal::option")]
+        ledger_id: Option<Principal>,
+        #[n(5)]
+        ledger_mint_index: Option<LedgerMintIndex>,
+    },
+    #[n(1)]
+    IcpToEvm {
+        #[n(0)]
+        identifier: IcpToEvmIdentifier,
+        #[n(1)]
+        status: IcpToEvmStatus,
+        #[cbor(n(2), with = "crate::cbor::principal")]
+        from: Principal,
+        #[n(3)]
+        destination: Address,
+        #[n(4)]
+        withdrawal_amount: Erc20TokenAmount,
+        #[cbor(n(5), with = "crate::cbor::principal::option")]
+        ledger_id: Option<Principal>,
+        #[n(6)]
+        transaction_hash: Option<TransactionHash>,
+    },
+    #[n(2)]
+    DexAction {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(1)]
+        action: DexAction,
+    },
+    #[n(3)]
+    TokenChange {
+        #[n(0)]
+        token: ChangedToken,
+        #[n(1)]
+        kind: TokenChangeKind,
+    },
+}
+
+impl StateTransition {
+    pub fn evm_to_icp(identifier: EvmToIcpTxIdentifier, tx: &EvmToIcpTx) -> Self {
+        Self::EvmToIcp {
+            identifier,
+            status: tx.status.clone(),
+            principal: tx.principal,
+            value: tx.value,
+            ledger_id: tx.icrc_ledger_id,
+            ledger_mint_index: tx.ledger_mint_index,
+        }
+    }
+
+    pub fn icp_to_evm(identifier: IcpToEvmIdentifier, tx: &IcpToEvmTx) -> Self {
+        Self::IcpToEvm {
+            identifier,
+            status: tx.status.clone(),
+            from: tx.from,
+            destination: tx.destination,
+            withdrawal_amount: tx.withdrawal_amount,
+            ledger_id: tx.icrc_ledger_id,
+            transaction_hash: tx.transaction_hash.clone(),
+        }
+    }
+
+    pub fn block_type(&self) -> &'static str {
+        match self {
+            StateTransition::EvmToIcp { .. } => EVM_TO_ICP_BLOCK_TYPE,
+            StateTransition::IcpToEvm { .. } => ICP_TO_EVM_BLOCK_TYPE,
+            StateTransition::DexAction { .. } => DEX_ACTION_BLOCK_TYPE,
+            StateTransition::TokenChange { .. } => TOKEN_CHANGE_BLOCK_TYPE,
+        }
+    }
+
+    pub fn to_icrc3_value(&self) -> ICRC3Value {
+        let mut tx = BTreeMap::new();
+
+        match self {
+            StateTransition::EvmToIcp {
+                identifier,
+                status,
+                principal,
+                value,
+                ledger_id,
+                ledger_mint_index,
+            } => {
+                tx.insert("tx_hash".to_string(), text(&identifier.0));
+                tx.insert("chain_id".to_string(), chain_id(identifier.1));
+                tx.insert("status".to_string(), text(evm_to_icp_status(status)));
+                if let EvmToIcpStatus::Invalid(reason) = status {
+                    tx.insert("reason".to_string(), text(reason));
+                }
+                tx.insert("principal".to_string(), principal_blob(principal));
+                tx.insert("value".to_string(), amount(value));
+                if let Some(ledger_id) = ledger_id {
+                    tx.insert("ledger_id".to_string(), principal_blob(ledger_id));
+                }
+                if let Some(ledger_mint_index) = ledger_mint_index {
+                    tx.insert(
+                        "ledger_mint_index".to_string(),
+                        ICRC3Value::Nat(Nat::from(*ledger_mint_index)),
+                    );
+                }
+            }
+            StateTransition::IcpToEvm {
+                identifier,
+                status,
+                from,
+                destination,
+                withdrawal_amount,
+                ledger_id,
+                transaction_hash,
+            } => {
+                tx.insert("burn_index".to_string(), burn_index(identifier.0));
+                tx.insert("chain_id".to_string(), chain_id(identifier.1));
+                tx.insert("status".to_string(), text(icp_to_evm_status(status)));
+                tx.insert("from".to_string(), principal_blob(from));
+                tx.insert("destination".to_string(), text(&destination.to_string()));
+                tx.insert("withdrawal_amount".to_string(), amount(withdrawal_amount));
+                if let Some(ledger_id) = ledger_id {
+                    tx.insert("ledger_id".to_string(), principal_blob(ledger_id));
+                }
+                if let Some(transaction_hash) = transaction_hash {
+                    tx.insert("tx_hash".to_string(), text(transaction_hash));
+                }
+            }
+            StateTransition::DexAction { principal, action } => {
+                tx.insert("principal".to_string(), principal_blob(principal));
+                insert_dex_action(&mut tx, action);
+            }
+            StateTransition::TokenChange { token, kind } => {
+                let op = match kind {
+                    TokenChangeKind::Added => "added",
+                    TokenChangeKind::Updated => "updated",
+                    TokenChangeKind::Removed => "removed",
+                };
+                tx.insert("op".to_string(), text(op));
+                match token {
+                    ChangedToken::Evm(identifier) => {
+                        tx.insert("chain_id".to_string(), chain_id(identifier.chain_id()));
+                        tx.insert(
+                            "address".to_string(),
+                            text(&identifier.erc20_address().to_string()),
+                        );
+                    }
+                    ChangedToken::Icp(ledger_id) => {
+                        tx.insert("ledger_id".to_string(), principal_blob(ledger_id));
+                    }
+                }
+            }
+        }
+
+        ICRC3Value::Map(tx)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct LoggedBlock {
+    #[n(0)]
+    pub timestamp: u64,
+    #[n(1)]
+    pub transition: StateTransition,
+    #[cbor(n(2), with = "minicbor::bytes")]
+    pub parent_hash: Option<[u8; 32]>,
+    // Hash of the icrc3 value of the block, stored to avoid hashing the parent on every append
+    #[cbor(n(3), with = "minicbor::bytes")]
+    pub hash: [u8; 32],
+}
+
+impl LoggedBlock {
+    pub fn new(timestamp: u64, transition: StateTransition, parent_hash: Option<[u8; 32]>) -> Self {
+        let hash = block_value(timestamp, &transition, parent_hash).hash();
+        Self {
+            timestamp,
+            transition,
+            parent_hash,
+            hash,
+        }
+    }
+
+    pub fn to_icrc3_value(&self) -> ICRC3Value {
+        block_value(self.timestamp, &self.transition, self.parent_hash)
+    }
+}
+
+fn block_value(
+    timestamp: u64,
+    transition: &StateTransition,
+    parent_hash: Option<[u8; 32]>,
+) -> ICRC3Value {
+    let mut block = BTreeMap::new();
+    if let Some(parent_hash) = parent_hash {
+        block.insert(
+            "phash".to_string(),
+            ICRC3Value::Blob(ByteBuf::from(parent_hash.to_vec())),
+        );
+    }
+    block.insert("ts".to_string(), ICRC3Value::Nat(Nat::from(timestamp)));
+    block.insert("btype".to_string(), text(transition.block_type()));
+    block.insert("tx".to_string(), transition.to_icrc3_value());
+    ICRC3Value::Map(block)
+}
+
+fn insert_dex_action(tx: &mut BTreeMap<String, ICRC3Value>, action: &DexAction) {
+    let (kind, dex_id) = match action {
+        DexAction::CreatedPool {
+            token0,
+            token1,
+            pool_fee,
+            dex_id,
+            ..
+        } => {
+            tx.insert("token0".to_string(), principal_blob(token0));
+            tx.insert("token1".to_string(), principal_blob(token1));
+            tx.insert(
+                "pool_fee".to_string(),
+                ICRC3Value::Nat(Nat::from(*pool_fee)),
+            );
+            ("created_pool", dex_id)
+        }
+        DexAction::MintedPosition {
+            created_position: position,
+            amount0_paid: amount0,
+            amount1_paid: amount1,
+            dex_id,
+            ..
+        } => {
+            insert_position(tx, &position.pool_id.token0, &position.pool_id.token1);
+            insert_amounts(tx, amount0, amount1);
+            ("minted_position", dex_id)
+        }
+        DexAction::IncreasedLiquidity {
+            modified_position: position,
+            amount0_paid: amount0,
+            amount1_paid: amount1,
+            dex_id,
+            ..
+        } => {
+            insert_position(tx, &position.pool_id.token0, &position.pool_id.token1);
+            insert_amounts(tx, amount0, amount1);
+            ("increased_liquidity", dex_id)
+        }
+        DexAction::BurntPosition {
+            burnt_position: position,
+            amount0_received: amount0,
+            amount1_received: amount1,
+            dex_id,
+            ..
+        } => {
+            insert_position(tx, &position.pool_id.token0, &position.pool_id.token1);
+            insert_amounts(tx, amount0, amount1);
+            ("burnt_position", dex_id)
+        }
+        DexAction::DecreasedLiquidity {
+            modified_position: position,
+            amount0_received: amount0,
+            amount1_received: amount1,
+            dex_id,
+            ..
+        } => {
+            insert_position(tx, &position.pool_id.token0, &position.pool_id.token1);
+            insert_amounts(tx, amount0, amount1);
+            ("decreased_liquidity", dex_id)
+        }
+        DexAction::CollectedFees {
+            position,
+            amount0_collected: amount0,
+            amount1_collected: amount1,
+            dex_id,
+            ..
+        } => {
+            insert_position(tx, &position.pool_id.token0, &position.pool_id.token1);
+            insert_amounts(tx, amount0, amount1);
+            ("collected_fees", dex_id)
+        }
+        DexAction::Swap {
+            final_amount_in,
+            final_amount_out,
+            token_in,
+            token_out,
+            dex_id,
+            ..
+        } => {
+            tx.insert("token_in".to_string(), principal_blob(token_in));
+            tx.insert("token_out".to_string(), principal_blob(token_out));
+            tx.insert("amount_in".to_string(), amount(final_amount_in));
+            tx.insert("amount_out".to_string(), amount(final_amount_out));
+            ("swap", dex_id)
+        }
+    };
+
+    tx.insert("kind".to_string(), text(kind));
+    if let Some(dex_id) = dex_id {
+        tx.insert("dex_id".to_string(), principal_blob(dex_id));
+    }
+}
+
+fn insert_position(tx: &mut BTreeMap<String, ICRC3Value>, token0: &Principal, token1: &Principal) {
+    tx.insert("token0".to_string(), principal_blob(token0));
+    tx.insert("token1".to_string(), principal_blob(token1));
+}
+
+fn insert_amounts(
+    tx: &mut BTreeMap<String, ICRC3Value>,
+    amount0: &Erc20TokenAmount,
+    amount1: &Erc20TokenAmount,
+) {
+    tx.insert("amount0".to_string(), amount(amount0));
+    tx.insert("amount1".to_string(), amount(amount1));
+}
+
+fn evm_to_icp_status(status: &EvmToIcpStatus) -> &'static str {
+    match status {
+        EvmToIcpStatus::PendingVerification => "pending_verification",
+        EvmToIcpStatus::Accepted => "accepted",
+        EvmToIcpStatus::Minted => "minted",
+        EvmToIcpStatus::Invalid(_) => "invalid",
+        EvmToIcpStatus::Quarantined => "quarantined",
+        EvmToIcpStatus::Expired => "expired",
+    }
+}
+
+fn icp_to_evm_status(status: &IcpToEvmStatus) -> &'static str {
+    match status {
+        IcpToEvmStatus::PendingVerification => "pending_verification",
+        IcpToEvmStatus::Accepted => "accepted",
+        IcpToEvmStatus::Created => "created",
+        IcpToEvmStatus::SignedTransaction => "signed_transaction",
+        IcpToEvmStatus::ReplacedTransaction => "replaced_transaction",
+        IcpToEvmStatus::Reimbursed => "reimbursed",
+        IcpToEvmStatus::QuarantinedReimbursement => "quarantined_reimbursement",
+        IcpToEvmStatus::Successful => "successful",
+        IcpToEvmStatus::Failed => "failed",
+        IcpToEvmStatus::Expired => "expired",
+    }
+}
+
+fn text(value: &str) -> ICRC3Value {
+    ICRC3Value::Text(value.to_string())
+}
+
+fn principal_blob(principal: &Principal) -> ICRC3Value {
+    ICRC3Value::Blob(ByteBuf::from(principal.as_slice().to_vec()))
+}
+
+fn amount(amount: &Erc20TokenAmount) -> ICRC3Value {
+    ICRC3Value::Nat((*amount).into())
+}
+
+fn chain_id(chain_id: ChainId) -> ICRC3Value {
+    ICRC3Value::Nat(Nat::from(chain_id.0))
+}
+
+fn burn_index(burn_index: LedgerBurnIndex) -> ICRC3Value {
+    ICRC3Value::Nat(Nat::from(burn_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_are_hash_chained() {
+        let transition = StateTransition::TokenChange {
+            token: ChangedToken::Icp(Principal::anonymous()),
+            kind: TokenChangeKind::Added,
+        };
+
+        let first = LoggedBlock::new(1, transition.clone(), None);
+        let second = LoggedBlock::new(2, transition, Some(first.hash));
+
+        assert_eq!(first.to_icrc3_value().hash(), first.hash);
+        assert_eq!(second.to_icrc3_value().hash(), second.hash);
+        assert_ne!(first.hash, second.hash);
+
+        let ICRC3Value::Map(block) = second.to_icrc3_value() else {
+            panic!("Blocks should be maps");
+        };
+        assert_eq!(
+            block.get("phash"),
+            Some(&ICRC3Value::Blob(ByteBuf::from(first.hash.to_vec())))
+        );
+        assert_eq!(block.get("btype"), Some(&text(TOKEN_CHANGE_BLOCK_TYPE)));
+    }
+}
@@ -0,0 +1,101 @@
+// Blocklist for the icp token registry.
+// Scam tokens usually copy well known symbols, so besides ledger ids, symbol patterns can be blocked as well.
+// Patterns are case insensitive and support `*` as a wildcard, e.g. "ckUSD*" or "*USDT".
+
+use candid::{CandidType, Deserialize, Principal};
+use minicbor::{Decode, Encode};
+use serde::Serialize;
+
+#[derive(
+    Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode, CandidType, Deserialize, Serialize,
+)]
+pub enum BlockedToken {
+    #[n(0)]
+    LedgerId(#[cbor(n(0), with = "crate::cbor::principal")] Principal),
+    #[n(1)]
+    SymbolPattern(#[n(0)] String),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct BlocklistEntry {
+    #[n(0)]
+    pub reason: Option<String>,
+    #[n(1)]
+    pub added_at: u64,
+}
+
+impl BlockedToken {
+    pub fn blocks(&self, ledger_id: &Principal, symbol: &str) -> bool {
+        match self {
+            BlockedToken::LedgerId(blocked_ledger_id) => blocked_ledger_id == ledger_id,
+            BlockedToken::SymbolPattern(pattern) => symbol_matches_pattern(pattern, symbol),
+        }
+    }
+}
+
+// Case insensitive glob matching, `*` matches any sequence of characters
+pub fn symbol_matches_pattern(pattern: &str, symbol: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let symbol = symbol.trim().to_lowercase();
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    // No wildcard, exact match
+    if parts.len() == 1 {
+        return pattern == symbol;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+
+    if !symbol.starts_with(first) || symbol.len() < first.len() + last.len() {
+        return false;
+    }
+
+    let mut remaining = &symbol[first.len()..symbol.len()];
+
+    if !remaining.ends_with(last) {
+        return false;
+    }
+    remaining = &remaining[..remaining.len() - last.len()];
+
+    // Middle parts should appear in order
+    for part in &parts[1..parts.len() - 1] {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_matches_pattern() {
+        assert!(symbol_matches_pattern("ckUSDC", "CKUSDC"));
+        assert!(!symbol_matches_pattern("ckUSDC", "ckUSDC2"));
+
+        assert!(symbol_matches_pattern("ckUSD*", "ckUSDT"));
+        assert!(symbol_matches_pattern("*USDT", "fakeUSDT"));
+        assert!(symbol_matches_pattern("*USD*", "xUSDx"));
+        assert!(symbol_matches_pattern("ck*C", "ckUSDC"));
+        assert!(symbol_matches_pattern("*", "ANY"));
+
+        assert!(!symbol_matches_pattern("ck*C", "ckUSDT"));
+        assert!(!symbol_matches_pattern("ckUSD*", "USDC"));
+        assert!(!symbol_matches_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_blocked_token() {
+        let ledger_id = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+
+        assert!(BlockedToken::LedgerId(ledger_id).blocks(&ledger_id, "ckUSDC"));
+        assert!(!BlockedToken::LedgerId(Principal::anonymous()).blocks(&ledger_id, "ckUSDC"));
+        assert!(BlockedToken::SymbolPattern("ckusd*".to_string()).blocks(&ledger_id, "ckUSDC"));
+    }
+}
@@ -66,3 +66,141 @@ const DEX_INFO: MemoryId = MemoryId::new(9);
 pub fn dex_info_id() -> StableMemory {
     MEMORY_MANAGER.with(|m| m.borrow().get(DEX_INFO))
 }
+
+const TOKEN_VALIDATION_CHECKPOINT: MemoryId = MemoryId::new(10);
+
+pub fn token_validation_checkpoint_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(TOKEN_VALIDATION_CHECKPOINT))
+}
+
+const TOKEN_VALIDATION_CONFIG: MemoryId = MemoryId::new(11);
+
+pub fn token_validation_config_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(TOKEN_VALIDATION_CONFIG))
+}
+
+const ICP_TOKEN_BLOCKLIST: MemoryId = MemoryId::new(12);
+
+pub fn icp_token_blocklist_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(ICP_TOKEN_BLOCKLIST))
+}
+
+const EVM_TOKEN_LIST_INFO: MemoryId = MemoryId::new(13);
+
+pub fn evm_token_list_info_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(EVM_TOKEN_LIST_INFO))
+}
+
+const BRIDGE_PAIR_CHANGES: MemoryId = MemoryId::new(14);
+
+pub fn bridge_pair_changes_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(BRIDGE_PAIR_CHANGES))
+}
+
+const PENDING_BRIDGE_PAIRS: MemoryId = MemoryId::new(15);
+
+pub fn pending_bridge_pairs_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PENDING_BRIDGE_PAIRS))
+}
+
+const DEX_SOURCES: MemoryId = MemoryId::new(16);
+
+pub fn dex_sources_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(DEX_SOURCES))
+}
+
+const SLA_THRESHOLDS: MemoryId = MemoryId::new(17);
+
+pub fn sla_thresholds_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SLA_THRESHOLDS))
+}
+
+const STUCK_TXS: MemoryId = MemoryId::new(18);
+
+pub fn stuck_txs_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(STUCK_TXS))
+}
+
+const UNVERIFIED_TX_CONFIG: MemoryId = MemoryId::new(19);
+
+pub fn unverified_tx_config_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(UNVERIFIED_TX_CONFIG))
+}
+
+const ALLOWED_RELAYERS: MemoryId = MemoryId::new(20);
+
+pub fn allowed_relayers_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(ALLOWED_RELAYERS))
+}
+
+const LEDGER_RECONCILIATIONS: MemoryId = MemoryId::new(21);
+
+pub fn ledger_reconciliations_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(LEDGER_RECONCILIATIONS))
+}
+
+const BLOCK_LOG: MemoryId = MemoryId::new(22);
+
+pub fn block_log_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(BLOCK_LOG))
+}
+
+const SUBSCRIPTIONS: MemoryId = MemoryId::new(23);
+
+pub fn subscriptions_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SUBSCRIPTIONS))
+}
+
+const MAINTENANCE_MODE: MemoryId = MemoryId::new(24);
+
+pub fn maintenance_mode_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(MAINTENANCE_MODE))
+}
+
+const MINTED_DEPOSITS: MemoryId = MemoryId::new(25);
+
+pub fn minted_deposits_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(MINTED_DEPOSITS))
+}
+
+const INDEXES_CHECKPOINT: MemoryId = MemoryId::new(26);
+
+pub fn indexes_checkpoint_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(INDEXES_CHECKPOINT))
+}
+
+const RECENT_WITHDRAWAL_FEES: MemoryId = MemoryId::new(27);
+
+pub fn recent_withdrawal_fees_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(RECENT_WITHDRAWAL_FEES))
+}
+
+const SLA_TRACKED_TXS: MemoryId = MemoryId::new(28);
+
+pub fn sla_tracked_txs_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SLA_TRACKED_TXS))
+}
+
+const PENDING_UNVERIFIED_TXS: MemoryId = MemoryId::new(29);
+
+pub fn pending_unverified_txs_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PENDING_UNVERIFIED_TXS))
+}
+
+const RECONCILIATION_QUEUE: MemoryId = MemoryId::new(30);
+
+pub fn reconciliation_queue_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(RECONCILIATION_QUEUE))
+}
+
+const CERTIFIED_LEAVES: MemoryId = MemoryId::new(31);
+
+pub fn certified_leaves_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(CERTIFIED_LEAVES))
+}
+
+const ALLOWED_SUBSCRIBERS: MemoryId = MemoryId::new(32);
+
+pub fn allowed_subscribers_id() -> StableMemory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(ALLOWED_SUBSCRIBERS))
+}
@@ -0,0 +1,183 @@
+// Correlates dex swaps with the bridge deposits that funded them.
+// A common flow is bridging a token in (EvmToIcp) and swapping it right after on AppicDEX,
+// linking both lets the frontend show them as a single journey.
+
+use candid::Principal;
+
+use crate::numeric::Erc20TokenAmount;
+use crate::state::types::{EvmToIcpStatus, EvmToIcpTx};
+
+// A swap can only be funded by a deposit that was accepted at most 1 hour before the swap
+pub const SWAP_FUNDING_WINDOW_NS: u64 = 3_600_000_000_000;
+
+// The swapped amount can be lower than the minted amount (ledger fees, dust left behind)
+// but not by more than this percentage
+pub const SWAP_FUNDING_AMOUNT_TOLERANCE_PERCENT: u8 = 5;
+
+// Checks if a minted deposit could have funded a swap with the given parameters
+pub fn is_swap_funded_by(
+    tx: &EvmToIcpTx,
+    principal: Principal,
+    token_in: Principal,
+    amount_in: Erc20TokenAmount,
+    swap_timestamp: u64,
+) -> bool {
+    if tx.status != EvmToIcpStatus::Minted
+        || tx.principal != principal
+        || tx.icrc_ledger_id != Some(token_in)
+    {
+        return false;
+    }
+
+    // The swap should happen after the deposit and within the funding window
+    if swap_timestamp < tx.time || swap_timestamp - tx.time > SWAP_FUNDING_WINDOW_NS {
+        return false;
+    }
+
+    let received = tx.actual_received.unwrap_or(tx.value);
+    if amount_in > received {
+        return false;
+    }
+
+    let tolerance = received
+        .checked_mul(Erc20TokenAmount::from(
+            SWAP_FUNDING_AMOUNT_TOLERANCE_PERCENT,
+        ))
+        .and_then(|amount| amount.checked_div_floor(100_u8))
+        .unwrap_or(Erc20TokenAmount::ZERO);
+
+    let min_amount_in = received
+        .checked_sub(tolerance)
+        .unwrap_or(Erc20TokenAmount::ZERO);
+
+    amount_in >= min_amount_in
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::address::Address;
+    use crate::state::types::{ChainId, Operator};
+
+    const ONE_MINUTE_IN_NS: u64 = 60_000_000_000;
+
+    fn minted_tx(principal: Principal, token: Principal, received: u64, time: u64) -> EvmToIcpTx {
+        EvmToIcpTx {
+            from_address: Address::from_str("0x3bcE376777eCFeb93953cc6C1bB957fbAcb1A261").unwrap(),
+            transaction_hash: "0x1".to_string(),
+            value: Erc20TokenAmount::from(received),
+            ledger_mint_index: Some(1),
+            block_number: None,
+            actual_received: Some(Erc20TokenAmount::from(received)),
+            principal,
+            subaccount: None,
+            chain_id: ChainId(1),
+            total_gas_spent: None,
+            erc20_contract_address: Address::ZERO,
+            icrc_ledger_id: Some(token),
+            status: EvmToIcpStatus::Minted,
+            verified: true,
+            time,
+            operator: Operator::AppicMinter,
+            status_updated_at: Some(time),
+        }
+    }
+
+    #[test]
+    fn should_link_swap_within_window_and_tolerance() {
+        let principal =
+            Principal::from_text("tb3vi-54bcb-4oudm-fmp2s-nntjp-rmhd3-ukvnq-lawfq-vk5vy-mnlc7-pae")
+                .unwrap();
+        let token = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+        let tx = minted_tx(principal, token, 1_000_000, ONE_MINUTE_IN_NS);
+
+        // Exact amount
+        assert!(is_swap_funded_by(
+            &tx,
+            principal,
+            token,
+            Erc20TokenAmount::from(1_000_000_u64),
+            2 * ONE_MINUTE_IN_NS
+        ));
+
+        // Slightly lower amount
+        assert!(is_swap_funded_by(
+            &tx,
+            principal,
+            token,
+            Erc20TokenAmount::from(960_000_u64),
+            2 * ONE_MINUTE_IN_NS
+        ));
+    }
+
+    #[test]
+    fn should_not_link_unrelated_swaps() {
+        let principal =
+            Principal::from_text("tb3vi-54bcb-4oudm-fmp2s-nntjp-rmhd3-ukvnq-lawfq-vk5vy-mnlc7-pae")
+                .unwrap();
+        let token = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+        let tx = minted_tx(principal, token, 1_000_000, ONE_MINUTE_IN_NS);
+        let amount = Erc20TokenAmount::from(1_000_000_u64);
+
+        // Different principal
+        assert!(!is_swap_funded_by(
+            &tx,
+            Principal::anonymous(),
+            token,
+            amount,
+            2 * ONE_MINUTE_IN_NS
+        ));
+
+        // Different token
+        assert!(!is_swap_funded_by(
+            &tx,
+            principal,
+            Principal::anonymous(),
+            amount,
+            2 * ONE_MINUTE_IN_NS
+        ));
+
+        // Swap before the deposit
+        assert!(!is_swap_funded_by(&tx, principal, token, amount, 0));
+
+        // Swap outside of the window
+        assert!(!is_swap_funded_by(
+            &tx,
+            principal,
+            token,
+            amount,
+            ONE_MINUTE_IN_NS + SWAP_FUNDING_WINDOW_NS + 1
+        ));
+
+        // Amount out of tolerance
+        assert!(!is_swap_funded_by(
+            &tx,
+            principal,
+            token,
+            Erc20TokenAmount::from(900_000_u64),
+            2 * ONE_MINUTE_IN_NS
+        ));
+        assert!(!is_swap_funded_by(
+            &tx,
+            principal,
+            token,
+            Erc20TokenAmount::from(1_000_001_u64),
+            2 * ONE_MINUTE_IN_NS
+        ));
+
+        // Deposit not minted yet
+        let accepted_tx = EvmToIcpTx {
+            status: EvmToIcpStatus::Accepted,
+            ..tx
+        };
+        assert!(!is_swap_funded_by(
+            &accepted_tx,
+            principal,
+            token,
+            amount,
+            2 * ONE_MINUTE_IN_NS
+        ));
+    }
+}
@@ -29,6 +29,8 @@ impl From<CandidEvent> for DexAction {
                 timestamp,
                 token_in,
                 token_out,
+                funded_by: None,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::CreatedPool {
                 token0,
@@ -39,6 +41,7 @@ impl From<CandidEvent> for DexAction {
                 token1,
                 pool_fee: pool_fee.0.try_into().unwrap(),
                 timestamp,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::BurntPosition {
                 amount0_received,
@@ -52,6 +55,7 @@ impl From<CandidEvent> for DexAction {
                 amount0_received: nat_to_erc20_amount(amount0_received),
                 amount1_received: nat_to_erc20_amount(amount1_received),
                 timestamp,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::IncreasedLiquidity {
                 principal: _,
@@ -65,6 +69,7 @@ impl From<CandidEvent> for DexAction {
                 amount0_paid: nat_to_erc20_amount(amount0_paid),
                 amount1_paid: nat_to_erc20_amount(amount1_paid),
                 timestamp,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::CollectedFees {
                 principal: _,
@@ -76,6 +81,7 @@ impl From<CandidEvent> for DexAction {
                 amount0_collected: nat_to_erc20_amount(amount0_collected),
                 amount1_collected: nat_to_erc20_amount(amount1_collected),
                 timestamp,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::DecreasedLiquidity {
                 amount0_received,
@@ -89,6 +95,7 @@ impl From<CandidEvent> for DexAction {
                 amount0_received: nat_to_erc20_amount(amount0_received),
                 amount1_received: nat_to_erc20_amount(amount1_received),
                 timestamp,
+                dex_id: None,
             },
             crate::appic_dex_types::CandidEventType::MintedPosition {
                 principal: _,
@@ -102,6 +109,7 @@ impl From<CandidEvent> for DexAction {
                 amount0_paid: nat_to_erc20_amount(amount0_paid),
                 amount1_paid: nat_to_erc20_amount(amount1_paid),
                 timestamp,
+                dex_id: None,
             },
         }
     }
@@ -115,6 +123,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 token1,
                 pool_fee,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::CreatedPool {
@@ -129,6 +138,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 amount0_paid,
                 amount1_paid,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::MintedPosition {
@@ -145,6 +155,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 amount0_paid,
                 amount1_paid,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::IncreasedLiquidity {
@@ -161,6 +172,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 amount0_received,
                 amount1_received,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::BurntPosition {
@@ -177,6 +189,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 amount0_received,
                 amount1_received,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::DecreasedLiquidity {
@@ -192,6 +205,7 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 amount0_collected,
                 amount1_collected,
                 timestamp,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::CollectedFees {
@@ -208,6 +222,8 @@ impl From<(Principal, DexAction)> for CandidEvent {
                 timestamp,
                 token_in,
                 token_out,
+                funded_by: _,
+                dex_id: _,
             } => CandidEvent {
                 timestamp,
                 payload: CandidEventType::Swap {
@@ -1,2 +1,3 @@
+pub mod correlation;
 pub mod event_conversion;
 pub mod types;
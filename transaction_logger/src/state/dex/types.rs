@@ -2,6 +2,7 @@ use candid::Principal;
 use minicbor::{Decode, Encode};
 
 use crate::numeric::Erc20TokenAmount;
+use crate::state::types::EvmToIcpTxIdentifier;
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub struct PoolId {
@@ -41,6 +42,8 @@ pub enum SwapType {
 pub struct UserDexActions(#[n(0)] pub Vec<DexAction>);
 
 /// The event describing the  minter state transition.
+/// dex_id is the dex source that emitted the action, None for the actions recorded
+/// before multiple dex sources were supported (AppicDEX).
 #[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
 pub enum DexAction {
     #[n(0)]
@@ -53,6 +56,8 @@ pub enum DexAction {
         pool_fee: u32,
         #[n(3)]
         timestamp: u64,
+        #[cbor(n(4), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(1)]
     MintedPosition {
@@ -66,6 +71,8 @@ pub enum DexAction {
         amount1_paid: Erc20TokenAmount,
         #[n(4)]
         timestamp: u64,
+        #[cbor(n(5), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(2)]
     IncreasedLiquidity {
@@ -79,6 +86,8 @@ pub enum DexAction {
         amount1_paid: Erc20TokenAmount,
         #[n(4)]
         timestamp: u64,
+        #[cbor(n(5), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(3)]
     BurntPosition {
@@ -92,6 +101,8 @@ pub enum DexAction {
         amount1_received: Erc20TokenAmount,
         #[n(4)]
         timestamp: u64,
+        #[cbor(n(5), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(4)]
     DecreasedLiquidity {
@@ -105,6 +116,8 @@ pub enum DexAction {
         amount1_received: Erc20TokenAmount,
         #[n(4)]
         timestamp: u64,
+        #[cbor(n(5), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(5)]
     CollectedFees {
@@ -116,6 +129,8 @@ pub enum DexAction {
         amount1_collected: Erc20TokenAmount,
         #[n(3)]
         timestamp: u64,
+        #[cbor(n(4), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
     #[n(6)]
     Swap {
@@ -131,5 +146,25 @@ pub enum DexAction {
         token_in: Principal,
         #[cbor(n(5), with = "crate::cbor::principal")]
         token_out: Principal,
+        // The bridge deposit that funded this swap, if any
+        #[n(6)]
+        funded_by: Option<EvmToIcpTxIdentifier>,
+        #[cbor(n(7), with = "crate::cbor::principal::option")]
+        dex_id: Option<Principal>,
     },
 }
+
+impl DexAction {
+    pub fn with_dex_id(mut self, id: Principal) -> Self {
+        match &mut self {
+            DexAction::CreatedPool { dex_id, .. }
+            | DexAction::MintedPosition { dex_id, .. }
+            | DexAction::IncreasedLiquidity { dex_id, .. }
+            | DexAction::BurntPosition { dex_id, .. }
+            | DexAction::DecreasedLiquidity { dex_id, .. }
+            | DexAction::CollectedFees { dex_id, .. }
+            | DexAction::Swap { dex_id, .. } => *dex_id = Some(id),
+        }
+        self
+    }
+}
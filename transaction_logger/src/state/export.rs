@@ -0,0 +1,205 @@
+// Chunked export and import of the stable maps of the state, used to move the logger
+// to a new canister or to seed a staging copy.
+// Keys and values are exported with their stable memory encoding (minicbor, raw bytes for principals)
+// and every chunk is a minicbor encoded list of entries. Chunks can only be imported in maintenance mode.
+
+use std::borrow::Cow;
+use std::ops::Bound;
+
+use candid::{CandidType, Deserialize};
+use ic_stable_structures::{BTreeMap, Storable};
+use minicbor::{Decode, Encode};
+use serde::Serialize;
+
+use crate::state::StableMemory;
+
+pub const MAX_ENTRIES_PER_CHUNK: usize = 500;
+
+// Encoded size after which a chunk is closed, below the 2MiB response limit
+pub const MAX_CHUNK_SIZE: usize = 1_500_000;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateSection {
+    Minters,
+    EvmToIcpTxs,
+    IcpToEvmTxs,
+    EvmTokens,
+    IcpTokens,
+    Ckerc20BridgePairs,
+    TwinAppicBridgePairs,
+    DexActions,
+    DexSources,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct StateEntry {
+    #[cbor(n(0), with = "minicbor::bytes")]
+    pub key: Vec<u8>,
+    #[cbor(n(1), with = "minicbor::bytes")]
+    pub value: Vec<u8>,
+}
+
+// Entry that does not fit in a chunk on its own, the export of its section can not go past it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EntryTooLarge {
+    pub key: Vec<u8>,
+    pub size: usize,
+}
+
+// Entries of a map after the cursor, the cursor is the key of the last exported entry.
+// Returns the entries and the cursor of the next chunk, None once the map is exported.
+// Every entry is measured before it is added, so a chunk never exceeds MAX_CHUNK_SIZE.
+pub fn export_entries<K, V>(
+    map: &BTreeMap<K, V, StableMemory>,
+    cursor: Option<&[u8]>,
+) -> Result<(Vec<StateEntry>, Option<Vec<u8>>), EntryTooLarge>
+where
+    K: Storable + Ord + Clone,
+    V: Storable,
+{
+    let start = match cursor {
+        Some(cursor) => Bound::Excluded(K::from_bytes(Cow::Borrowed(cursor))),
+        None => Bound::Unbounded,
+    };
+
+    let mut entries: Vec<StateEntry> = vec![];
+    let mut size = 0;
+    for (key, value) in map.range((start, Bound::Unbounded)) {
+        let entry = StateEntry {
+            key: key.to_bytes().into_owned(),
+            value: value.to_bytes().into_owned(),
+        };
+        let entry_size = entry.key.len() + entry.value.len();
+
+        if entry_size > MAX_CHUNK_SIZE {
+            return Err(EntryTooLarge {
+                key: entry.key,
+                size: entry_size,
+            });
+        }
+
+        // The entry starts the next chunk
+        if entries.len() >= MAX_ENTRIES_PER_CHUNK || size + entry_size > MAX_CHUNK_SIZE {
+            let next_cursor = entries.last().map(|entry| entry.key.clone());
+            return Ok((entries, next_cursor));
+        }
+
+        size += entry_size;
+        entries.push(entry);
+    }
+
+    Ok((entries, None))
+}
+
+// Inserts the entries, existing keys are overwritten.
+// Entries that can not be decoded trap, so a chunk is either imported entirely or not at all.
+pub fn import_entries<K, V>(map: &mut BTreeMap<K, V, StableMemory>, entries: Vec<StateEntry>)
+where
+    K: Storable + Ord + Clone,
+    V: Storable,
+{
+    for entry in entries {
+        map.insert(
+            K::from_bytes(Cow::Owned(entry.key)),
+            V::from_bytes(Cow::Owned(entry.value)),
+        );
+    }
+}
+
+pub fn encode_chunk(entries: &[StateEntry]) -> Vec<u8> {
+    let mut buf = vec![];
+    minicbor::encode(entries, &mut buf).expect("minicbor encoding should always succeed");
+    buf
+}
+
+pub fn decode_chunk(chunk: &[u8]) -> Result<Vec<StateEntry>, String> {
+    minicbor::decode(chunk).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_stable_structures::memory_manager::{MemoryId, MemoryManager};
+    use ic_stable_structures::DefaultMemoryImpl;
+
+    #[test]
+    fn test_chunk_roundtrip() {
+        let entries = vec![
+            StateEntry {
+                key: vec![1, 2, 3],
+                value: vec![4, 5],
+            },
+            StateEntry {
+                key: vec![6],
+                value: vec![],
+            },
+        ];
+
+        assert_eq!(decode_chunk(&encode_chunk(&entries)), Ok(entries));
+        assert!(decode_chunk(&[0xff, 0x00]).is_err());
+    }
+
+    fn memory(id: u8) -> StableMemory {
+        MemoryManager::init(DefaultMemoryImpl::default()).get(MemoryId::new(id))
+    }
+
+    #[test]
+    fn test_export_and_import_map() {
+        let mut source: BTreeMap<u64, String, StableMemory> = BTreeMap::init(memory(0));
+        for i in 0..(MAX_ENTRIES_PER_CHUNK as u64 + 10) {
+            source.insert(i, format!("value {}", i));
+        }
+
+        let mut target: BTreeMap<u64, String, StableMemory> = BTreeMap::init(memory(1));
+        target.insert(0, "overwritten".to_string());
+
+        let mut cursor: Option<Vec<u8>> = None;
+        let mut chunks = 0;
+        loop {
+            let (entries, next_cursor) = export_entries(&source, cursor.as_deref()).unwrap();
+            let chunk = encode_chunk(&entries);
+            import_entries(&mut target, decode_chunk(&chunk).unwrap());
+            chunks += 1;
+
+            match next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(chunks, 2);
+        assert_eq!(
+            target.iter().collect::<Vec<_>>(),
+            source.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_chunks_stay_below_max_size() {
+        let mut map: BTreeMap<u64, Vec<u8>, StableMemory> = BTreeMap::init(memory(0));
+        let value_size = MAX_CHUNK_SIZE / 3;
+        for i in 0..4 {
+            map.insert(i, vec![0; value_size]);
+        }
+
+        // Only two entries fit with their keys, the third one starts the next chunk
+        let (entries, next_cursor) = export_entries(&map, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(next_cursor, Some(1_u64.to_bytes().into_owned()));
+
+        let (entries, next_cursor) = export_entries(&map, next_cursor.as_deref()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(next_cursor, None);
+
+        // An entry larger than a chunk fails instead of producing an oversized chunk
+        map.insert(2, vec![0; MAX_CHUNK_SIZE]);
+        let cursor = 1_u64.to_bytes().into_owned();
+        assert_eq!(
+            export_entries(&map, Some(&cursor)),
+            Err(EntryTooLarge {
+                key: 2_u64.to_bytes().into_owned(),
+                size: 8 + MAX_CHUNK_SIZE,
+            })
+        );
+    }
+}
@@ -0,0 +1,210 @@
+// Ranked search over evm and icp tokens.
+// Results are ordered by match quality (exact symbol > prefix > substring > typo),
+// then by popularity (24h volume for evm tokens, rank for icp tokens).
+
+use std::cmp::Ordering;
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use crate::state::types::{EvmToken, IcpToken};
+
+pub const DEFAULT_SEARCH_RESULTS: usize = 20;
+pub const MAX_SEARCH_RESULTS: usize = 100;
+
+// Longer queries can not match any symbol, name, address or ledger id,
+// and are rejected before the typo scoring which is quadratic in the query length
+pub const MAX_SEARCH_QUERY_LENGTH: usize = 64;
+
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum TokenSearchFilter {
+    // Chain id
+    Evm(Nat),
+    Icp,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum MatchTier {
+    // Exact symbol or address match
+    Exact,
+    // Symbol or name starts with the query
+    Prefix,
+    // Symbol or name contains the query
+    Substring,
+    // Symbol or name is within the allowed number of typos
+    Typo,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SearchedToken {
+    Evm(EvmToken),
+    Icp(IcpToken),
+}
+
+impl SearchedToken {
+    fn symbol(&self) -> &str {
+        match self {
+            SearchedToken::Evm(token) => &token.symbol,
+            SearchedToken::Icp(token) => &token.symbol,
+        }
+    }
+
+    fn volume(&self) -> f64 {
+        match self {
+            SearchedToken::Evm(token) => token
+                .volume_usd_24h
+                .as_ref()
+                .and_then(|volume| volume.parse::<f64>().ok())
+                .unwrap_or(0.0),
+            SearchedToken::Icp(_) => 0.0,
+        }
+    }
+
+    fn rank(&self) -> u32 {
+        match self {
+            SearchedToken::Evm(_) => u32::MAX,
+            SearchedToken::Icp(token) => token.rank.unwrap_or(u32::MAX),
+        }
+    }
+}
+
+// Trimmed and lowercased query, None if it is empty or longer than MAX_SEARCH_QUERY_LENGTH
+pub fn normalize_query(query: &str) -> Option<String> {
+    let query = query.trim();
+    if query.is_empty() || query.chars().count() > MAX_SEARCH_QUERY_LENGTH {
+        return None;
+    }
+    Some(query.to_lowercase())
+}
+
+// Number of typos allowed for a query, short queries have to match without typos
+pub fn allowed_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+// Both query and target should already be lowercase
+pub fn match_tier(query: &str, symbol: &str, name: &str) -> Option<MatchTier> {
+    if query.is_empty() {
+        return None;
+    }
+
+    if symbol == query {
+        return Some(MatchTier::Exact);
+    }
+
+    if symbol.starts_with(query) || name.starts_with(query) {
+        return Some(MatchTier::Prefix);
+    }
+
+    if symbol.contains(query) || name.contains(query) {
+        return Some(MatchTier::Substring);
+    }
+
+    let max_typos = allowed_typos(query);
+    if max_typos > 0
+        && (levenshtein_distance(query, symbol) <= max_typos
+            || levenshtein_distance(query, name) <= max_typos)
+    {
+        return Some(MatchTier::Typo);
+    }
+
+    None
+}
+
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = usize::from(a_char != *b_char);
+            current[j + 1] = (previous[j] + substitution_cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
+// Sorts by match tier, then by volume (evm), then by rank (icp), then alphabetically
+pub fn sort_search_results(results: &mut [(MatchTier, SearchedToken)]) {
+    results.sort_by(|(tier_a, token_a), (tier_b, token_b)| {
+        tier_a
+            .cmp(tier_b)
+            .then_with(|| {
+                token_b
+                    .volume()
+                    .partial_cmp(&token_a.volume())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| token_a.rank().cmp(&token_b.rank()))
+            .then_with(|| token_a.symbol().cmp(token_b.symbol()))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("usdc", "usdc"), 0);
+        assert_eq!(levenshtein_distance("usdc", "usdt"), 1);
+        assert_eq!(levenshtein_distance("usc", "usdc"), 1);
+        assert_eq!(levenshtein_distance("etherum", "ethereum"), 1);
+        assert_eq!(levenshtein_distance("", "eth"), 3);
+    }
+
+    #[test]
+    fn test_match_tier() {
+        assert_eq!(
+            match_tier("usdc", "usdc", "usd coin"),
+            Some(MatchTier::Exact)
+        );
+        assert_eq!(
+            match_tier("usd", "usdc", "usd coin"),
+            Some(MatchTier::Prefix)
+        );
+        assert_eq!(
+            match_tier("coin", "usdc", "usd coin"),
+            Some(MatchTier::Substring)
+        );
+        assert_eq!(
+            match_tier("usdd", "usdc", "usd coin"),
+            Some(MatchTier::Typo)
+        );
+        assert_eq!(
+            match_tier("etherum", "eth", "ethereum"),
+            Some(MatchTier::Typo)
+        );
+
+        // Short queries do not allow typos
+        assert_eq!(match_tier("bt", "eth", "ethereum"), None);
+        assert_eq!(match_tier("", "eth", "ethereum"), None);
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(normalize_query("  USDC "), Some("usdc".to_string()));
+        assert_eq!(normalize_query("   "), None);
+
+        let longest_query = "a".repeat(MAX_SEARCH_QUERY_LENGTH);
+        assert_eq!(normalize_query(&longest_query), Some(longest_query.clone()));
+        assert_eq!(normalize_query(&format!("{longest_query}a")), None);
+    }
+
+    #[test]
+    fn test_tier_ordering() {
+        assert!(MatchTier::Exact < MatchTier::Prefix);
+        assert!(MatchTier::Prefix < MatchTier::Substring);
+        assert!(MatchTier::Substring < MatchTier::Typo);
+    }
+}
@@ -1,7 +1,10 @@
 use ic_stable_structures::{storable::Bound, storable::Storable};
 use std::borrow::Cow;
 
+use crate::numeric::Erc20TokenAmount;
 use crate::state::{
+    block_log::LoggedBlock,
+    blocklist::{BlockedToken, BlocklistEntry},
     dex::types::{DexAction, SwapType, UserDexActions},
     types::*,
 };
@@ -44,3 +47,30 @@ impl_storable_minicbor!(DexAction);
 impl_storable_minicbor!(SwapType);
 impl_storable_minicbor!(UserDexActions);
 impl_storable_minicbor!(DexInfo);
+impl_storable_minicbor!(TokenValidationCheckpoint);
+impl_storable_minicbor!(TokenValidationConfig);
+impl_storable_minicbor!(BlockedToken);
+impl_storable_minicbor!(BlocklistEntry);
+impl_storable_minicbor!(EvmTokenListInfo);
+impl_storable_minicbor!(Erc20TwinLedgerSuiteRequest);
+impl_storable_minicbor!(BridgePairChangeId);
+impl_storable_minicbor!(BridgePairChange);
+impl_storable_minicbor!(PendingBridgePairKey);
+impl_storable_minicbor!(PendingBridgePair);
+impl_storable_minicbor!(DexSource);
+impl_storable_minicbor!(ChainId);
+impl_storable_minicbor!(SlaThresholds);
+impl_storable_minicbor!(StuckTxKey);
+impl_storable_minicbor!(StuckTx);
+impl_storable_minicbor!(UnverifiedTxConfig);
+impl_storable_minicbor!(ReconciliationKey);
+impl_storable_minicbor!(LedgerReconciliation);
+impl_storable_minicbor!(LoggedBlock);
+impl_storable_minicbor!(Subscription);
+impl_storable_minicbor!(MaintenanceMode);
+impl_storable_minicbor!(MintedDepositKey);
+impl_storable_minicbor!(IndexesCheckpoint);
+impl_storable_minicbor!(WithdrawalFeeKey);
+impl_storable_minicbor!(Erc20TokenAmount);
+impl_storable_minicbor!(PendingUnverifiedKey);
+impl_storable_minicbor!(ExpectedLedgerOperation);
@@ -0,0 +1,595 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use candid::{Nat, Principal};
+
+use crate::address::Address;
+use crate::numeric::Erc20TokenAmount;
+
+use super::{
+    block_log::{ChangedToken, StateTransition, TokenChangeKind},
+    export::StateSection,
+    types::{
+        BridgePair, BridgePairChange, BridgePairChangeKind, ChainId, DexAdapterKind, DexInfo,
+        DexSource, Erc20Identifier, Erc20TwinLedgerSuiteFee, Erc20TwinLedgerSuiteRequest,
+        Erc20TwinLedgerSuiteStatus, EvmToken, EvmTokenSource, IcpToken, IcpTokenType, Minter,
+        Operator,
+    },
+    State, STATE,
+};
+
+// Mutates the state without setting the certified data, which is only available inside canisters
+pub fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
+    STATE.with(|cell| {
+        f(cell
+            .borrow_mut()
+            .as_mut()
+            .expect("BUG: state is not initialized"))
+    })
+}
+
+pub fn icp_token(ledger_id: Principal, symbol: &str) -> IcpToken {
+    IcpToken {
+        ledger_id,
+        name: format!("{} token", symbol),
+        decimals: 8,
+        symbol: symbol.to_string(),
+        token_type: IcpTokenType::ICRC2,
+        fee: Erc20TokenAmount::from(10_000_u64),
+        rank: Some(1),
+        usd_price: "1".to_string(),
+        logo: "".to_string(),
+        listed_on_appic_dex: None,
+        supported_standards: None,
+        validation_failures: None,
+        verified: None,
+    }
+}
+
+pub fn evm_token(erc20_identifier: &Erc20Identifier, symbol: &str) -> EvmToken {
+    EvmToken {
+        chain_id: erc20_identifier.chain_id(),
+        erc20_contract_address: erc20_identifier.erc20_address(),
+        name: format!("{} token", symbol),
+        decimals: 18,
+        symbol: symbol.to_string(),
+        logo: "".to_string(),
+        is_wrapped_icrc: false,
+        cmc_id: None,
+        usd_price: None,
+        volume_usd_24h: None,
+        runtime_managed_fields: None,
+        source: Some(EvmTokenSource::Runtime),
+    }
+}
+
+pub fn bridge_pair(erc20_identifier: &Erc20Identifier, ledger_id: Principal) -> BridgePair {
+    BridgePair {
+        icp_token: icp_token(ledger_id, "ckUSDC"),
+        evm_token: evm_token(erc20_identifier, "USDC"),
+        removed_at: None,
+    }
+}
+
+fn usdc(chain_id: u64) -> Erc20Identifier {
+    Erc20Identifier(
+        Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap(),
+        ChainId(chain_id),
+    )
+}
+
+#[test]
+fn test_blocks_are_logged_at_the_given_time() {
+    let ledger_id = Principal::from_slice(&[1, 1]);
+
+    with_state(|s| {
+        s.record_icp_token(ledger_id, icp_token(ledger_id, "T"), 10);
+        s.set_icp_token_verified(&ledger_id, true, 20);
+        s.remove_icp_token(&ledger_id, 30);
+    });
+
+    let blocks = with_state(|s| s.get_blocks(0, 10));
+    let logged: Vec<(u64, TokenChangeKind)> = blocks
+        .into_iter()
+        .map(|(_index, block)| match block.transition {
+            StateTransition::TokenChange {
+                token: ChangedToken::Icp(changed),
+                kind,
+            } => {
+                assert_eq!(changed, ledger_id);
+                (block.timestamp, kind)
+            }
+            transition => panic!("Unexpected transition {:?}", transition),
+        })
+        .collect();
+
+    assert_eq!(
+        logged,
+        vec![
+            (10, TokenChangeKind::Added),
+            (20, TokenChangeKind::Updated),
+            (30, TokenChangeKind::Removed),
+        ]
+    );
+}
+
+#[test]
+fn test_native_ledger_without_native_evm_token() {
+    let ledger_id = Principal::from_slice(&[2, 1]);
+    let sepolia = ChainId(11155111);
+
+    with_state(|s| {
+        assert!(s
+            .get_evm_token_by_identifier(&Erc20Identifier(Address::ZERO, sepolia))
+            .is_none());
+        s.record_native_icrc_ledger(
+            ledger_id,
+            "ckSepoliaETH".to_string(),
+            Erc20TokenAmount::from(2_000_000_000_000_u64),
+            sepolia,
+            0,
+        );
+    });
+
+    let token = with_state(|s| s.get_icp_token_by_principal(&ledger_id)).unwrap();
+    assert_eq!(token.symbol, "ckSepoliaETH");
+    assert_eq!(token.fee, Erc20TokenAmount::from(2_000_000_000_000_u64));
+    assert_eq!(token.logo, "");
+}
+
+fn dex_source(id: Principal, next_event_to_scrape: u64) -> DexSource {
+    DexSource {
+        id,
+        adapter: DexAdapterKind::AppicDexV1,
+        enabled: true,
+        last_observed_event: next_event_to_scrape,
+        next_event_to_scrape,
+        label: None,
+    }
+}
+
+#[test]
+fn test_migrate_dex_info() {
+    let dex_id = Principal::from_slice(&[3, 1]);
+
+    with_state(|s| {
+        s.dex_info
+            .set(DexInfo {
+                id: dex_id,
+                last_observed_event: 250,
+                last_scraped_event: 200,
+            })
+            .unwrap();
+        s.migrate_dex_info();
+    });
+
+    let migrated = DexSource {
+        label: Some("AppicDEX".to_string()),
+        last_observed_event: 250,
+        ..dex_source(dex_id, 200)
+    };
+    assert_eq!(with_state(|s| s.get_dex_sources()), vec![migrated.clone()]);
+
+    // Later upgrades keep the cursors of the migrated source
+    with_state(|s| {
+        s.update_next_dex_event_to_scrape(&dex_id, 300);
+        s.migrate_dex_info();
+    });
+    assert_eq!(
+        with_state(|s| s.get_dex_sources()),
+        vec![DexSource {
+            next_event_to_scrape: 300,
+            ..migrated
+        }]
+    );
+}
+
+#[test]
+fn test_export_and_import_dex_sources() {
+    let dex_id = Principal::from_slice(&[3, 2]);
+    with_state(|s| assert!(s.add_dex_source(dex_source(dex_id, 10))));
+
+    let (entries, next_cursor) =
+        with_state(|s| s.export_state_chunk(StateSection::DexSources, None))
+            .expect("dex sources should fit in a chunk");
+    assert_eq!(next_cursor, None);
+
+    with_state(|s| {
+        s.dex_sources.remove(&dex_id);
+        s.import_state_chunk(StateSection::DexSources, entries);
+    });
+    assert_eq!(
+        with_state(|s| s.get_dex_sources()),
+        vec![dex_source(dex_id, 10)]
+    );
+}
+
+#[test]
+fn test_dex_source_cursors() {
+    let first_dex = Principal::from_slice(&[3, 1]);
+    let second_dex = Principal::from_slice(&[4, 1]);
+
+    with_state(|s| {
+        assert!(s.add_dex_source(dex_source(first_dex, 10)));
+        assert!(s.add_dex_source(dex_source(second_dex, 0)));
+
+        s.update_last_observed_dex_event(&first_dex, 50);
+        s.update_next_dex_event_to_scrape(&first_dex, 20);
+
+        // An existing source is not reset
+        assert!(!s.add_dex_source(dex_source(first_dex, 0)));
+    });
+
+    assert_eq!(
+        with_state(|s| s.get_dex_sources()),
+        vec![
+            DexSource {
+                last_observed_event: 50,
+                ..dex_source(first_dex, 20)
+            },
+            dex_source(second_dex, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_record_bridge_pair_changes() {
+    let usdc = usdc(1);
+    let ckusdc = Principal::from_slice(&[5, 1]);
+    let new_ckusdc = Principal::from_slice(&[5, 2]);
+    let operator = Operator::DfinityCkEthMinter;
+
+    with_state(|s| {
+        assert!(s.record_bridge_pair(operator, usdc.clone(), bridge_pair(&usdc, ckusdc), 10));
+        // Listing the same pair again is not a change
+        assert!(!s.record_bridge_pair(operator, usdc.clone(), bridge_pair(&usdc, ckusdc), 20));
+        // The minter now points to another ledger
+        assert!(s.record_bridge_pair(operator, usdc.clone(), bridge_pair(&usdc, new_ckusdc), 30));
+    });
+
+    let changes: Vec<(BridgePairChangeKind, Principal, u64)> = with_state(|s| {
+        s.get_bridge_pair_changes(0)
+            .into_iter()
+            .map(|(id, change)| {
+                assert_eq!(id.0, change.timestamp);
+                assert_eq!(change.operator, operator);
+                assert_eq!(change.erc20_identifier, usdc);
+                (change.kind, change.icp_ledger_id, change.timestamp)
+            })
+            .collect()
+    });
+    assert_eq!(
+        changes,
+        vec![
+            (BridgePairChangeKind::Added, ckusdc, 10),
+            (BridgePairChangeKind::Removed, ckusdc, 30),
+            (BridgePairChangeKind::Added, new_ckusdc, 30),
+        ]
+    );
+}
+
+#[test]
+fn test_remove_unlisted_bridge_pairs() {
+    let usdc = usdc(1);
+    let usdt = Erc20Identifier(
+        Address::from_str("0xdAC17F958D2ee523a2206206994597C13D831ec7").unwrap(),
+        ChainId(1),
+    );
+    let wrapped = Erc20Identifier(
+        Address::from_str("0x3bcE376777eCFeb93953cc6C1bB957fbAcb1A261").unwrap(),
+        ChainId(1),
+    );
+    let ckusdc = Principal::from_slice(&[5, 1]);
+    let ckusdt = Principal::from_slice(&[6, 1]);
+    let icrc = Principal::from_slice(&[7, 1]);
+    let operator = Operator::AppicMinter;
+
+    let removed = with_state(|s| {
+        s.record_bridge_pair(operator, usdc.clone(), bridge_pair(&usdc, ckusdc), 10);
+        s.record_bridge_pair(operator, usdt.clone(), bridge_pair(&usdt, ckusdt), 10);
+        let mut wrapped_pair = bridge_pair(&wrapped, icrc);
+        wrapped_pair.evm_token.is_wrapped_icrc = true;
+        s.record_bridge_pair(operator, wrapped.clone(), wrapped_pair, 10);
+
+        s.remove_unlisted_bridge_pairs(operator, &BTreeSet::from([usdc.clone()]), 20)
+    });
+
+    // Wrapped icrc pairs are not listed by the ledger suite manager
+    assert_eq!(removed, vec![usdt.clone()]);
+
+    with_state(|s| {
+        // Removed pairs are kept for minter events but rejected for new transactions
+        assert_eq!(s.get_icrc_twin_for_erc20(&usdt, &operator), Some(ckusdt));
+        assert_eq!(s.get_listed_icrc_twin_for_erc20(&usdt, &operator), None);
+        assert_eq!(
+            s.get_listed_icrc_twin_for_erc20(&usdc, &operator),
+            Some(ckusdc)
+        );
+        assert_eq!(
+            s.get_listed_icrc_twin_for_erc20(&wrapped, &operator),
+            Some(icrc)
+        );
+
+        // Removing again records nothing new
+        assert!(s
+            .remove_unlisted_bridge_pairs(operator, &BTreeSet::from([usdc.clone()]), 30)
+            .is_empty());
+    });
+
+    let changes: Vec<BridgePairChange> = with_state(|s| {
+        s.get_bridge_pair_changes(20)
+            .into_iter()
+            .map(|(_id, change)| change)
+            .collect()
+    });
+    assert_eq!(
+        changes,
+        vec![BridgePairChange {
+            kind: BridgePairChangeKind::Removed,
+            operator,
+            erc20_identifier: usdt.clone(),
+            icp_ledger_id: ckusdt,
+            timestamp: 20,
+        }]
+    );
+
+    // Listing the pair again restores it
+    with_state(|s| {
+        assert!(s.record_bridge_pair(operator, usdt.clone(), bridge_pair(&usdt, ckusdt), 40));
+        assert_eq!(
+            s.get_listed_icrc_twin_for_erc20(&usdt, &operator),
+            Some(ckusdt)
+        );
+        assert_eq!(s.get_bridge_pair_changes(40).len(), 1);
+    });
+}
+
+fn minter(id: Principal, operator: Operator, chain_id: u64, enabled: bool) -> Minter {
+    Minter {
+        id,
+        last_observed_event: 0,
+        next_event_to_scrape: 0,
+        operator,
+        icp_to_evm_fee: Erc20TokenAmount::from(100_u64),
+        chain_id: ChainId(chain_id),
+        enabled,
+    }
+}
+
+#[test]
+fn test_bridge_routes_of_both_operators() {
+    let usdc = usdc(1);
+    let ckusdc = Principal::from_slice(&[5, 1]);
+    let twin_usdc = Principal::from_slice(&[5, 2]);
+    let ck_minter = Principal::from_slice(&[8, 1]);
+    let appic_minter = Principal::from_slice(&[8, 2]);
+
+    with_state(|s| {
+        s.record_minter(minter(ck_minter, Operator::DfinityCkEthMinter, 1, true));
+        s.record_bridge_pair(
+            Operator::DfinityCkEthMinter,
+            usdc.clone(),
+            bridge_pair(&usdc, ckusdc),
+            10,
+        );
+        s.record_bridge_pair(
+            Operator::AppicMinter,
+            usdc.clone(),
+            bridge_pair(&usdc, twin_usdc),
+            10,
+        );
+    });
+
+    let routes = with_state(|s| s.get_bridge_routes(&usdc));
+    assert_eq!(routes.len(), 2);
+
+    let ck_route = &routes[0];
+    assert_eq!(ck_route.operator, Operator::DfinityCkEthMinter);
+    assert_eq!(ck_route.icp_token.ledger_id, ckusdc);
+    assert_eq!(ck_route.minter_id, Some(ck_minter));
+    assert!(ck_route.minter_enabled);
+    assert_eq!(ck_route.icp_to_evm_fee, Some(Nat::from(100_u64)));
+
+    // No minter is registered for the appic route yet
+    let appic_route = &routes[1];
+    assert_eq!(appic_route.operator, Operator::AppicMinter);
+    assert_eq!(appic_route.icp_token.ledger_id, twin_usdc);
+    assert_eq!(appic_route.minter_id, None);
+    assert!(!appic_route.minter_enabled);
+    assert_eq!(appic_route.icp_to_evm_fee, None);
+
+    // A disabled minter is reported but the route is not usable
+    with_state(|s| s.record_minter(minter(appic_minter, Operator::AppicMinter, 1, false)));
+    let appic_route = with_state(|s| s.get_bridge_routes_for_icrc(&twin_usdc));
+    assert_eq!(appic_route.len(), 1);
+    assert_eq!(appic_route[0].minter_id, Some(appic_minter));
+    assert!(!appic_route[0].minter_enabled);
+
+    // Tokens without bridge pairs have no routes
+    assert!(with_state(|s| s.get_bridge_routes(&usdc(56))).is_empty());
+}
+
+#[test]
+fn test_bridge_routes_for_icrc_skip_removed_pairs() {
+    let mainnet_usdc = usdc(1);
+    let bsc_usdc = usdc(56);
+    let base_usdc = usdc(8453);
+    let twin_usdc = Principal::from_slice(&[5, 2]);
+    let operator = Operator::AppicMinter;
+
+    with_state(|s| {
+        for erc20_identifier in [&mainnet_usdc, &bsc_usdc, &base_usdc] {
+            s.record_bridge_pair(
+                operator,
+                erc20_identifier.clone(),
+                bridge_pair(erc20_identifier, twin_usdc),
+                10,
+            );
+        }
+        s.remove_unlisted_bridge_pairs(
+            operator,
+            &BTreeSet::from([mainnet_usdc.clone(), bsc_usdc.clone()]),
+            20,
+        );
+    });
+
+    let routes = with_state(|s| s.get_bridge_routes_for_icrc(&twin_usdc));
+    let chains: Vec<Nat> = routes
+        .into_iter()
+        .map(|route| route.evm_token.chain_id)
+        .collect();
+    assert_eq!(chains, vec![Nat::from(1_u64), Nat::from(56_u64)]);
+
+    assert!(with_state(|s| s.get_bridge_routes(&base_usdc)).is_empty());
+}
+
+#[test]
+fn test_twin_ledger_request_lifecycle() {
+    let usdc = usdc(56);
+    let creator = Principal::from_slice(&[9, 1]);
+    let twin_usdc = Principal::from_slice(&[5, 2]);
+
+    with_state(|s| {
+        s.record_twin_ledger_request(
+            usdc.clone(),
+            creator,
+            "icUSDC".to_string(),
+            Erc20TwinLedgerSuiteFee::Icp(100_000_000),
+            10,
+        );
+    });
+
+    let pending = Erc20TwinLedgerSuiteRequest {
+        creator: Some(creator),
+        evm_token_contract: usdc.erc20_address(),
+        evm_token_chain_id: ChainId(56),
+        icp_token_symbol: Some("icUSDC".to_string()),
+        status: Erc20TwinLedgerSuiteStatus::PendingApproval,
+        fee_charged: Some(Erc20TwinLedgerSuiteFee::Icp(100_000_000)),
+        icp_ledger_id: None,
+        created_at: 10,
+        updated_at: 10,
+    };
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(Some(creator))),
+        vec![pending.clone()]
+    );
+
+    with_state(|s| {
+        s.update_twin_ledger_request_status(
+            usdc.clone(),
+            Erc20TwinLedgerSuiteStatus::Created,
+            Some(twin_usdc),
+            Some("icUSDC".to_string()),
+            20,
+        );
+    });
+    let created = Erc20TwinLedgerSuiteRequest {
+        status: Erc20TwinLedgerSuiteStatus::Created,
+        icp_ledger_id: Some(twin_usdc),
+        updated_at: 20,
+        ..pending
+    };
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(None)),
+        vec![created.clone()]
+    );
+
+    with_state(|s| {
+        s.update_twin_ledger_request_status(
+            usdc.clone(),
+            Erc20TwinLedgerSuiteStatus::Installed,
+            Some(twin_usdc),
+            None,
+            30,
+        );
+    });
+    let installed = Erc20TwinLedgerSuiteRequest {
+        status: Erc20TwinLedgerSuiteStatus::Installed,
+        updated_at: 30,
+        ..created
+    };
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(None)),
+        vec![installed.clone()]
+    );
+
+    // A stale status polled from the lsm does not move the request backwards
+    with_state(|s| {
+        s.update_twin_ledger_request_status(
+            usdc.clone(),
+            Erc20TwinLedgerSuiteStatus::Created,
+            Some(twin_usdc),
+            None,
+            40,
+        );
+        s.update_twin_ledger_request_status(
+            usdc.clone(),
+            Erc20TwinLedgerSuiteStatus::PendingApproval,
+            None,
+            None,
+            40,
+        );
+    });
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(None)),
+        vec![installed]
+    );
+
+    // Requests of other creators are filtered out
+    assert!(with_state(|s| s.get_twin_ledger_requests(Some(twin_usdc))).is_empty());
+}
+
+#[test]
+fn test_twin_ledger_request_first_seen_through_minter_event() {
+    let usdc = usdc(56);
+    let creator = Principal::from_slice(&[9, 1]);
+    let twin_usdc = Principal::from_slice(&[5, 2]);
+
+    // The AddedErc20Token event is scraped before the lsm notifies the logger
+    with_state(|s| {
+        s.update_twin_ledger_request_status(
+            usdc.clone(),
+            Erc20TwinLedgerSuiteStatus::Installed,
+            Some(twin_usdc),
+            Some("icUSDC".to_string()),
+            10,
+        );
+    });
+
+    let installed = Erc20TwinLedgerSuiteRequest {
+        creator: None,
+        evm_token_contract: usdc.erc20_address(),
+        evm_token_chain_id: ChainId(56),
+        icp_token_symbol: Some("icUSDC".to_string()),
+        status: Erc20TwinLedgerSuiteStatus::Installed,
+        fee_charged: None,
+        icp_ledger_id: Some(twin_usdc),
+        created_at: 10,
+        updated_at: 10,
+    };
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(None)),
+        vec![installed.clone()]
+    );
+
+    // The late submission fills the creator and fee but keeps the status
+    with_state(|s| {
+        s.record_twin_ledger_request(
+            usdc.clone(),
+            creator,
+            "icUSDC".to_string(),
+            Erc20TwinLedgerSuiteFee::Appic(5_000_000),
+            20,
+        );
+    });
+    assert_eq!(
+        with_state(|s| s.get_twin_ledger_requests(Some(creator))),
+        vec![Erc20TwinLedgerSuiteRequest {
+            creator: Some(creator),
+            fee_charged: Some(Erc20TwinLedgerSuiteFee::Appic(5_000_000)),
+            updated_at: 20,
+            ..installed
+        }]
+    );
+}
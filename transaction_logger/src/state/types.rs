@@ -30,8 +30,10 @@ pub struct Minter {
     pub id: Principal,
     #[n(1)]
     pub last_observed_event: u64,
+    // Every event before it is already applied, stored values of the former last_scraped_event
+    // cursor are kept as is since scraping resumed from that event
     #[n(2)]
-    pub last_scraped_event: u64,
+    pub next_event_to_scrape: u64,
     #[n(3)]
     pub operator: Operator,
     #[n(4)]
@@ -47,8 +49,8 @@ impl Minter {
         self.last_observed_event = event
     }
 
-    pub fn update_last_scraped_event(&mut self, event: u64) {
-        self.last_scraped_event = event
+    pub fn update_next_event_to_scrape(&mut self, event: u64) {
+        self.next_event_to_scrape = event
     }
 
     pub fn from_minter_args(args: MinterArgs) -> Self {
@@ -62,7 +64,7 @@ impl Minter {
         Self {
             id: minter_id,
             last_observed_event: nat_to_u64(&last_observed_event),
-            last_scraped_event: nat_to_u64(&last_scraped_event),
+            next_event_to_scrape: nat_to_u64(&last_scraped_event),
             operator,
             icp_to_evm_fee: Erc20TokenAmount::ZERO,
             chain_id: ChainId::from(&chain_id),
@@ -76,7 +78,7 @@ impl Minter {
             minter_id: self.id,
             operator: self.operator,
             last_observed_event: self.last_observed_event.into(),
-            last_scraped_event: self.last_scraped_event.into(),
+            last_scraped_event: self.next_event_to_scrape.into(),
         }
     }
 }
@@ -142,6 +144,9 @@ pub enum EvmToIcpStatus {
     Invalid(#[n(0)] String),
     #[n(4)]
     Quarantined,
+    // Unverified for longer than the expiry timeout, revived by the matching minter event
+    #[n(5)]
+    Expired,
 }
 
 #[derive(Clone, PartialEq, Ord, Eq, PartialOrd, Debug, Encode, Decode)]
@@ -178,6 +183,10 @@ pub struct EvmToIcpTx {
     pub time: u64,
     #[n(15)]
     pub operator: Operator,
+    // Time of the minter event that moved the transaction to its current status
+    // or the time it expired, None for pending unverified transactions
+    #[n(16)]
+    pub status_updated_at: Option<u64>,
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode)]
@@ -230,6 +239,9 @@ pub enum IcpToEvmStatus {
     Successful,
     #[n(8)]
     Failed,
+    // Unverified for longer than the expiry timeout, revived by the matching minter event
+    #[n(9)]
+    Expired,
 }
 
 #[derive(Clone, PartialEq, Ord, Eq, PartialOrd, Debug, Encode, Decode)]
@@ -272,6 +284,65 @@ pub struct IcpToEvmTx {
     pub status: IcpToEvmStatus,
     #[n(18)]
     pub operator: Operator,
+    #[n(19)]
+    pub reimbursement: Option<IcpToEvmReimbursement>,
+    // Time of the minter event that moved the transaction to its current status
+    // or the time it expired, None for pending unverified transactions
+    #[n(20)]
+    pub status_updated_at: Option<u64>,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Hash,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum IcpToEvmReimbursementReason {
+    // The withdrawal transaction failed on the evm chain
+    #[n(0)]
+    FailedTransaction,
+    // Burning the erc20 tokens failed, the native withdrawal fee is reimbursed
+    #[n(1)]
+    FailedErc20Burn,
+    // Locking the icrc tokens failed, the native withdrawal fee is reimbursed
+    #[n(2)]
+    FailedIcrcLock,
+}
+
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct IcpToEvmReimbursement {
+    #[n(0)]
+    pub reason: IcpToEvmReimbursementReason,
+    #[n(1)]
+    pub reimbursed_amount: Erc20TokenAmount,
+    #[cbor(n(2), with = "crate::cbor::principal::option")]
+    pub ledger_id: Option<Principal>,
+    // None until the reimbursement is minted on the ledger
+    #[n(3)]
+    pub reimbursed_in_block: Option<LedgerMintIndex>,
+}
+
+impl IcpToEvmReimbursement {
+    // Failed requests schedule a reimbursement that is minted in a later event,
+    // the reason of the first event is kept
+    pub fn merge(self, newer: Self) -> Self {
+        Self {
+            reason: self.reason,
+            reimbursed_amount: newer.reimbursed_amount,
+            ledger_id: newer.ledger_id.or(self.ledger_id),
+            reimbursed_in_block: newer.reimbursed_in_block.or(self.reimbursed_in_block),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode, Hash)]
@@ -319,6 +390,81 @@ pub struct EvmToken {
     pub usd_price: Option<String>,
     #[n(9)]
     pub volume_usd_24h: Option<String>,
+    // Fields changed by admins that should not be overwritten by the bundled token list
+    #[n(10)]
+    #[serde(default)]
+    pub runtime_managed_fields: Option<Vec<EvmTokenField>>,
+    // None for tokens recorded before sources were tracked
+    #[n(11)]
+    #[serde(default)]
+    pub source: Option<EvmTokenSource>,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Ord,
+    Eq,
+    PartialOrd,
+    Debug,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum EvmTokenField {
+    #[n(0)]
+    Name,
+    #[n(1)]
+    Symbol,
+    #[n(2)]
+    Decimals,
+    #[n(3)]
+    Logo,
+    #[n(4)]
+    CmcId,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Ord,
+    Eq,
+    PartialOrd,
+    Debug,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum EvmTokenSource {
+    // Loaded from the bundled supported_tokens.json, removed if it disappears from the file
+    #[n(0)]
+    Bundled,
+    // Added by admins or discovered at runtime, never removed by the bundled list
+    #[n(1)]
+    Runtime,
+}
+
+// Metadata changes applied by admins, None fields are left untouched
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EvmTokenMetadataUpdate {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub logo: Option<String>,
+    pub cmc_id: Option<u64>,
+}
+
+// Version of the bundled evm token list that was last synced into state
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct EvmTokenListInfo {
+    #[n(0)]
+    pub bundled_version: Option<String>,
 }
 
 #[derive(
@@ -359,6 +505,147 @@ pub struct IcpToken {
     pub rank: Option<u32>,
     #[n(9)]
     pub listed_on_appic_dex: Option<bool>,
+    // Standards reported by icrc1_supported_standards, e.g. ["ICRC-1", "ICRC-2"]
+    // None if they were not fetched yet
+    #[n(10)]
+    pub supported_standards: Option<Vec<String>>,
+    // Consecutive failed validations, None if the last validation succeeded
+    #[n(11)]
+    pub validation_failures: Option<IcpTokenValidationFailures>,
+    // Set by curators for tokens that are known to be legit
+    #[n(12)]
+    pub verified: Option<bool>,
+}
+
+impl IcpToken {
+    // Soft disabled tokens are kept in state but hidden from the public token list
+    pub fn is_disabled(&self) -> bool {
+        self.validation_failures
+            .as_ref()
+            .is_some_and(|failures| failures.disabled)
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.verified.unwrap_or(false)
+    }
+}
+
+// Reason of a failed icp token validation
+#[derive(
+    Clone, PartialEq, Ord, Eq, PartialOrd, Debug, Encode, Decode, CandidType, Deserialize, Serialize,
+)]
+pub enum TokenValidationFailure {
+    // The ledger might recover (e.g. system transient errors, out of cycles)
+    #[n(0)]
+    Transient(#[n(0)] String),
+    // The ledger is not functional (e.g. wasm removed, invalid metadata, canister deleted)
+    #[n(1)]
+    Permanent(#[n(0)] String),
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode)]
+pub struct IcpTokenValidationFailures {
+    // Only permanent failures are counted as strikes
+    #[n(0)]
+    pub strikes: u32,
+    #[n(1)]
+    pub first_failed_at: u64,
+    #[n(2)]
+    pub last_failed_at: u64,
+    #[n(3)]
+    pub last_failure: TokenValidationFailure,
+    #[n(4)]
+    pub disabled: bool,
+}
+
+impl IcpTokenValidationFailures {
+    // Records a new failure on top of the previous consecutive failures
+    pub fn record_failure(
+        previous: Option<Self>,
+        failure: TokenValidationFailure,
+        now: u64,
+        config: &TokenValidationConfig,
+    ) -> Self {
+        let (strikes, first_failed_at) = match previous {
+            Some(previous) => (previous.strikes, previous.first_failed_at),
+            None => (0, now),
+        };
+
+        let strikes = match failure {
+            TokenValidationFailure::Permanent(_) => strikes.saturating_add(1),
+            TokenValidationFailure::Transient(_) => strikes,
+        };
+
+        Self {
+            strikes,
+            first_failed_at,
+            last_failed_at: now,
+            last_failure: failure,
+            disabled: strikes >= config.max_strikes,
+        }
+    }
+
+    // Time after which a disabled token gets removed
+    pub fn removal_time(&self, config: &TokenValidationConfig) -> u64 {
+        self.first_failed_at
+            .saturating_add(config.removal_grace_period)
+    }
+
+    pub fn should_be_removed(&self, now: u64, config: &TokenValidationConfig) -> bool {
+        self.disabled && now >= self.removal_time(config)
+    }
+}
+
+// 30 Days
+pub const DEFAULT_TOKEN_REMOVAL_GRACE_PERIOD: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+pub const DEFAULT_TOKEN_VALIDATION_MAX_STRIKES: u32 = 2;
+
+// 1 Hour
+pub const DEFAULT_UNVERIFIED_TX_EXPIRY_TIMEOUT: u64 = 60 * 60 * 1_000_000_000;
+
+// 7 Days
+pub const DEFAULT_EXPIRED_TX_RETENTION_PERIOD: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+// Lifetime of transactions submitted by the frontend that were never confirmed by a minter,
+// can be changed through upgrade args
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct UnverifiedTxConfig {
+    // Time in nanoseconds since submission, after which an unverified transaction expires
+    #[n(0)]
+    pub expiry_timeout: u64,
+    // Time in nanoseconds since expiry, after which an expired transaction is removed
+    #[n(1)]
+    pub retention_period: u64,
+}
+
+impl Default for UnverifiedTxConfig {
+    fn default() -> Self {
+        Self {
+            expiry_timeout: DEFAULT_UNVERIFIED_TX_EXPIRY_TIMEOUT,
+            retention_period: DEFAULT_EXPIRED_TX_RETENTION_PERIOD,
+        }
+    }
+}
+
+// Rules for disabling and removing invalid icp tokens, can be changed through upgrade args
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct TokenValidationConfig {
+    // Number of strikes after which a token is soft disabled
+    #[n(0)]
+    pub max_strikes: u32,
+    // Time in nanoseconds since the first failure, after which a disabled token is removed
+    #[n(1)]
+    pub removal_grace_period: u64,
+}
+
+impl Default for TokenValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_strikes: DEFAULT_TOKEN_VALIDATION_MAX_STRIKES,
+            removal_grace_period: DEFAULT_TOKEN_REMOVAL_GRACE_PERIOD,
+        }
+    }
 }
 
 // Custom implementation of Eq and Hash for IcpToken based only on ledger_id
@@ -380,6 +667,114 @@ pub struct BridgePair {
     pub icp_token: IcpToken,
     #[n(1)]
     pub evm_token: EvmToken,
+    // Set once the ledger suite manager/orchestrator stops listing the pair
+    #[n(2)]
+    pub removed_at: Option<u64>,
+}
+
+impl BridgePair {
+    pub fn is_removed(&self) -> bool {
+        self.removed_at.is_some()
+    }
+}
+
+// Change log key, ordered by the time the change was recorded and then by insertion order.
+// Changes can be recorded for past events, so the key does not use the event timestamp
+// to keep changes recorded after a poll at or after its `since` timestamp.
+#[derive(Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct BridgePairChangeId(#[n(0)] pub u64, #[n(1)] pub u64);
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum BridgePairChangeKind {
+    #[n(0)]
+    Added,
+    #[n(1)]
+    Removed,
+}
+
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct BridgePairChange {
+    #[n(0)]
+    pub kind: BridgePairChangeKind,
+    #[n(1)]
+    pub operator: Operator,
+    #[n(2)]
+    pub erc20_identifier: Erc20Identifier,
+    #[cbor(n(3), with = "crate::cbor::principal")]
+    pub icp_ledger_id: Principal,
+    #[n(4)]
+    pub timestamp: u64,
+}
+
+// Bridge pair listed by a ledger suite manager/orchestrator that could not be recorded
+// because the metadata of one of its tokens is missing
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct PendingBridgePairKey(#[n(0)] pub Operator, #[n(1)] pub Erc20Identifier);
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum PendingBridgePairReason {
+    #[n(0)]
+    MissingEvmToken,
+    #[n(1)]
+    MissingIcpToken,
+    #[n(2)]
+    MissingEvmAndIcpToken,
+}
+
+impl PendingBridgePairReason {
+    pub fn from_missing(evm_token_missing: bool, icp_token_missing: bool) -> Option<Self> {
+        match (evm_token_missing, icp_token_missing) {
+            (true, true) => Some(Self::MissingEvmAndIcpToken),
+            (true, false) => Some(Self::MissingEvmToken),
+            (false, true) => Some(Self::MissingIcpToken),
+            (false, false) => None,
+        }
+    }
+
+    pub fn is_icp_token_missing(&self) -> bool {
+        matches!(self, Self::MissingIcpToken | Self::MissingEvmAndIcpToken)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct PendingBridgePair {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub icp_ledger_id: Principal,
+    #[n(1)]
+    pub reason: PendingBridgePairReason,
+    #[n(2)]
+    pub first_seen_at: u64,
+    #[n(3)]
+    pub last_seen_at: u64,
+    // Error of the last attempt to fetch the missing icp token metadata
+    #[n(4)]
+    pub last_error: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
@@ -400,6 +795,51 @@ pub enum Erc20TwinLedgerSuiteFee {
     Appic(#[cbor(n(0), with = "crate::cbor::u128")] u128),
 }
 
+// Request for creating an icrc twin ledger suite of an erc20 token through the appic ledger suite manager.
+// Requests submitted by the lsm carry the creator and the paid fee, requests discovered by polling
+// the lsm or through minter events do not.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct Erc20TwinLedgerSuiteRequest {
+    #[cbor(n(0), with = "crate::cbor::principal::option")]
+    pub creator: Option<Principal>,
+    #[n(1)]
+    pub evm_token_contract: Address,
+    #[n(2)]
+    pub evm_token_chain_id: ChainId,
+    #[n(3)]
+    pub icp_token_symbol: Option<String>,
+    #[n(4)]
+    pub status: Erc20TwinLedgerSuiteStatus,
+    #[n(5)]
+    pub fee_charged: Option<Erc20TwinLedgerSuiteFee>,
+    #[cbor(n(6), with = "crate::cbor::principal::option")]
+    pub icp_ledger_id: Option<Principal>,
+    #[n(7)]
+    pub created_at: u64,
+    #[n(8)]
+    pub updated_at: u64,
+}
+
+impl Erc20TwinLedgerSuiteRequest {
+    // Status can only move forward, PendingApproval -> Created -> Installed
+    pub fn advance_status(
+        &mut self,
+        status: Erc20TwinLedgerSuiteStatus,
+        icp_ledger_id: Option<Principal>,
+        now: u64,
+    ) {
+        if status > self.status {
+            self.status = status;
+        }
+
+        if icp_ledger_id.is_some() {
+            self.icp_ledger_id = icp_ledger_id;
+        }
+
+        self.updated_at = now;
+    }
+}
+
 #[derive(
     Clone,
     Copy,
@@ -435,6 +875,17 @@ impl From<ChainId> for Nat {
     }
 }
 
+// Progress of the icp token validation, stored in stable memory so that
+// an interrupted validation resumes from the last validated batch
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct TokenValidationCheckpoint {
+    // Last ledger of the last validated batch, None if no validation is in progress
+    #[cbor(n(0), with = "crate::cbor::principal::option")]
+    pub last_validated_ledger: Option<Principal>,
+}
+
+// Kept to migrate the cursors of the single dex that was scraped before multiple dex sources
+// were supported, see State::migrate_dex_info
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Encode, Decode)]
 pub struct DexInfo {
     #[cbor(n(0), with = "crate::cbor::principal")]
@@ -444,3 +895,477 @@ pub struct DexInfo {
     #[n(2)]
     pub last_scraped_event: u64,
 }
+
+// Interface of the dex canister, decides how events are fetched and converted into dex actions
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Encode,
+    Decode,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum DexAdapterKind {
+    #[n(0)]
+    AppicDexV1,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Encode, Decode)]
+pub struct DexSource {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub id: Principal,
+    #[n(1)]
+    pub adapter: DexAdapterKind,
+    #[n(2)]
+    pub enabled: bool,
+    #[n(3)]
+    pub last_observed_event: u64,
+    // Every event before it is already applied
+    #[n(4)]
+    pub next_event_to_scrape: u64,
+    // Human readable name, e.g. AppicDEX staging
+    #[n(5)]
+    pub label: Option<String>,
+}
+
+// 30 Minutes
+pub const DEFAULT_ICP_TO_EVM_ACCEPTED_SLA: u64 = 30 * 60 * 1_000_000_000;
+
+// 30 Minutes
+pub const DEFAULT_ICP_TO_EVM_CREATED_SLA: u64 = 30 * 60 * 1_000_000_000;
+
+// 1 Hour
+pub const DEFAULT_ICP_TO_EVM_SIGNED_SLA: u64 = 60 * 60 * 1_000_000_000;
+
+// 1 Hour
+pub const DEFAULT_EVM_TO_ICP_ACCEPTED_SLA: u64 = 60 * 60 * 1_000_000_000;
+
+// Maximum time in nanoseconds a transaction is expected to spend in each non-terminal status,
+// chains without custom thresholds use the defaults
+#[derive(Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct SlaThresholds {
+    #[n(0)]
+    pub icp_to_evm_accepted: u64,
+    #[n(1)]
+    pub icp_to_evm_created: u64,
+    // Applies to signed and replaced transactions
+    #[n(2)]
+    pub icp_to_evm_signed: u64,
+    #[n(3)]
+    pub evm_to_icp_accepted: u64,
+}
+
+impl Default for SlaThresholds {
+    fn default() -> Self {
+        Self {
+            icp_to_evm_accepted: DEFAULT_ICP_TO_EVM_ACCEPTED_SLA,
+            icp_to_evm_created: DEFAULT_ICP_TO_EVM_CREATED_SLA,
+            icp_to_evm_signed: DEFAULT_ICP_TO_EVM_SIGNED_SLA,
+            evm_to_icp_accepted: DEFAULT_EVM_TO_ICP_ACCEPTED_SLA,
+        }
+    }
+}
+
+impl SlaThresholds {
+    // None for terminal statuses and unverified transactions
+    pub fn icp_to_evm_threshold(&self, status: &IcpToEvmStatus) -> Option<u64> {
+        match status {
+            IcpToEvmStatus::Accepted => Some(self.icp_to_evm_accepted),
+            IcpToEvmStatus::Created => Some(self.icp_to_evm_created),
+            IcpToEvmStatus::SignedTransaction | IcpToEvmStatus::ReplacedTransaction => {
+                Some(self.icp_to_evm_signed)
+            }
+            IcpToEvmStatus::PendingVerification
+            | IcpToEvmStatus::Expired
+            | IcpToEvmStatus::Reimbursed
+            | IcpToEvmStatus::QuarantinedReimbursement
+            | IcpToEvmStatus::Successful
+            | IcpToEvmStatus::Failed => None,
+        }
+    }
+
+    // None for terminal statuses and unverified transactions
+    pub fn evm_to_icp_threshold(&self, status: &EvmToIcpStatus) -> Option<u64> {
+        match status {
+            EvmToIcpStatus::Accepted => Some(self.evm_to_icp_accepted),
+            EvmToIcpStatus::PendingVerification
+            | EvmToIcpStatus::Expired
+            | EvmToIcpStatus::Minted
+            | EvmToIcpStatus::Invalid(_)
+            | EvmToIcpStatus::Quarantined => None,
+        }
+    }
+
+    // Statuses with a threshold on every chain, whatever its custom thresholds
+    pub fn tracks_icp_to_evm(status: &IcpToEvmStatus) -> bool {
+        Self::default().icp_to_evm_threshold(status).is_some()
+    }
+
+    pub fn tracks_evm_to_icp(status: &EvmToIcpStatus) -> bool {
+        Self::default().evm_to_icp_threshold(status).is_some()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub enum StuckTxKey {
+    #[n(0)]
+    IcpToEvm(#[n(0)] IcpToEvmIdentifier),
+    #[n(1)]
+    EvmToIcp(#[n(0)] EvmToIcpTxIdentifier),
+}
+
+// Transaction that spent more time in its current status than the sla of its chain
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub struct StuckTx {
+    // Time of the last known event of the transaction
+    #[n(0)]
+    pub status_since: u64,
+    #[n(1)]
+    pub threshold: u64,
+    #[n(2)]
+    pub detected_at: u64,
+    #[n(3)]
+    pub last_checked_at: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Encode, Decode)]
+pub enum ReconciliationKey {
+    #[n(0)]
+    EvmToIcp(#[n(0)] EvmToIcpTxIdentifier),
+    #[n(1)]
+    IcpToEvm(#[n(0)] IcpToEvmIdentifier),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum LedgerOperation {
+    #[n(0)]
+    Mint,
+    #[n(1)]
+    Burn,
+}
+
+impl LedgerOperation {
+    // Transaction kind reported by the ledger
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LedgerOperation::Mint => "mint",
+            LedgerOperation::Burn => "burn",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct LedgerAccount {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub owner: Principal,
+    #[cbor(n(1), with = "minicbor::bytes")]
+    pub subaccount: Option<[u8; 32]>,
+}
+
+impl LedgerAccount {
+    // None and the zero subaccount are the same account
+    pub fn effective_subaccount(&self) -> [u8; 32] {
+        self.subaccount.unwrap_or([0; 32])
+    }
+
+    pub fn is_same_account(&self, other: &LedgerAccount) -> bool {
+        self.owner == other.owner && self.effective_subaccount() == other.effective_subaccount()
+    }
+}
+
+// Ledger block a transaction is expected to have produced according to the minter events
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct ExpectedLedgerOperation {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub ledger_id: Principal,
+    #[n(1)]
+    pub block_index: u64,
+    #[n(2)]
+    pub operation: LedgerOperation,
+    #[n(3)]
+    pub amount: Erc20TokenAmount,
+    #[n(4)]
+    pub account: LedgerAccount,
+}
+
+impl EvmToIcpTx {
+    // Time an unverified deposit expired, None if it did not expire
+    pub fn expired_at(&self) -> Option<u64> {
+        match self.status {
+            EvmToIcpStatus::Expired => Some(self.status_updated_at.unwrap_or(self.time)),
+            _ => None,
+        }
+    }
+
+    // Mint block of a minted deposit
+    pub fn expected_ledger_operation(&self) -> Option<ExpectedLedgerOperation> {
+        if !self.verified || self.status != EvmToIcpStatus::Minted {
+            return None;
+        }
+
+        Some(ExpectedLedgerOperation {
+            ledger_id: self.icrc_ledger_id?,
+            block_index: self.ledger_mint_index?,
+            operation: LedgerOperation::Mint,
+            amount: self.actual_received.unwrap_or(self.value),
+            account: LedgerAccount {
+                owner: self.principal,
+                subaccount: self.subaccount,
+            },
+        })
+    }
+}
+
+impl IcpToEvmTx {
+    // Time an unverified withdrawal expired, None if it did not expire
+    pub fn expired_at(&self) -> Option<u64> {
+        match self.status {
+            IcpToEvmStatus::Expired => Some(self.status_updated_at.unwrap_or(self.time)),
+            _ => None,
+        }
+    }
+
+    // Burn block of a withdrawal, erc20 withdrawals burn the withdrawn tokens
+    // on the erc20 ledger and only the fee on the native ledger
+    pub fn expected_ledger_operation(&self) -> Option<ExpectedLedgerOperation> {
+        if !self.verified {
+            return None;
+        }
+
+        Some(ExpectedLedgerOperation {
+            ledger_id: self.icrc_ledger_id?,
+            block_index: self
+                .erc20_ledger_burn_index
+                .unwrap_or(self.native_ledger_burn_index),
+            operation: LedgerOperation::Burn,
+            amount: self.withdrawal_amount,
+            account: LedgerAccount {
+                owner: self.from,
+                subaccount: self.from_subaccount,
+            },
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum ReconciliationMismatch {
+    #[n(0)]
+    Operation {
+        #[n(0)]
+        expected: LedgerOperation,
+        #[n(1)]
+        found: String,
+    },
+    #[n(1)]
+    Amount {
+        #[n(0)]
+        expected: Erc20TokenAmount,
+        #[n(1)]
+        found: Erc20TokenAmount,
+    },
+    #[n(2)]
+    Account {
+        #[n(0)]
+        expected: LedgerAccount,
+        #[n(1)]
+        found: LedgerAccount,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum ReconciliationStatus {
+    #[n(0)]
+    Matched,
+    #[n(1)]
+    Mismatch(#[n(0)] Vec<ReconciliationMismatch>),
+    // The ledger does not have the block yet
+    #[n(2)]
+    BlockNotFound,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct LedgerReconciliation {
+    #[n(0)]
+    pub expected: ExpectedLedgerOperation,
+    #[n(1)]
+    pub status: ReconciliationStatus,
+    #[n(2)]
+    pub checked_at: u64,
+}
+
+// Changes a subscribed canister is notified about.
+// Empty principals or chain ids match every principal or chain, statuses are the ones notified.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct SubscriptionFilter {
+    #[cbor(n(0), with = "crate::cbor::principal::vec")]
+    pub principals: Vec<Principal>,
+    #[n(1)]
+    pub chain_ids: Vec<ChainId>,
+    #[n(2)]
+    pub evm_to_icp_statuses: Vec<EvmToIcpStatus>,
+    #[n(3)]
+    pub icp_to_evm_statuses: Vec<IcpToEvmStatus>,
+    #[n(4)]
+    pub dex_actions: bool,
+}
+
+impl SubscriptionFilter {
+    fn matches_principal(&self, principal: &Principal) -> bool {
+        self.principals.is_empty() || self.principals.contains(principal)
+    }
+
+    fn matches_chain(&self, chain_id: &ChainId) -> bool {
+        self.chain_ids.is_empty() || self.chain_ids.contains(chain_id)
+    }
+
+    // Statuses with a payload like Invalid match regardless of the payload
+    pub fn matches_evm_to_icp(&self, tx: &EvmToIcpTx) -> bool {
+        self.matches_principal(&tx.principal)
+            && self.matches_chain(&tx.chain_id)
+            && self
+                .evm_to_icp_statuses
+                .iter()
+                .any(|status| std::mem::discriminant(status) == std::mem::discriminant(&tx.status))
+    }
+
+    pub fn matches_icp_to_evm(&self, tx: &IcpToEvmTx) -> bool {
+        self.matches_principal(&tx.from)
+            && self.matches_chain(&tx.chain_id)
+            && self.icp_to_evm_statuses.contains(&tx.status)
+    }
+
+    pub fn matches_dex_action(&self, principal: &Principal) -> bool {
+        self.dex_actions && self.matches_principal(principal)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct Subscription {
+    // Update method of the subscriber called with the notification batches
+    #[n(0)]
+    pub method: String,
+    #[n(1)]
+    pub filter: SubscriptionFilter,
+    #[n(2)]
+    pub subscribed_at: u64,
+}
+
+// While enabled the timers are paused and exported state chunks can be imported
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct MaintenanceMode {
+    #[n(0)]
+    pub enabled: bool,
+    #[n(1)]
+    pub enabled_at: Option<u64>,
+}
+
+// Minted deposits of a principal ordered by time, used to find the deposits that funded a swap
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode)]
+pub struct MintedDepositKey {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub principal: Principal,
+    #[n(1)]
+    pub time: u64,
+    #[n(2)]
+    pub identifier: EvmToIcpTxIdentifier,
+}
+
+impl MintedDepositKey {
+    pub fn new(identifier: EvmToIcpTxIdentifier, tx: &EvmToIcpTx) -> Self {
+        Self {
+            principal: tx.principal,
+            time: tx.time,
+            identifier,
+        }
+    }
+
+    // Smallest key of the deposits of the principal made at or after the given time
+    pub fn first_at(principal: Principal, time: u64) -> Self {
+        Self {
+            principal,
+            time,
+            identifier: EvmToIcpTxIdentifier(TransactionHash::new(), ChainId(0)),
+        }
+    }
+}
+
+// Gas fees of the most recent finalized withdrawals of a token ordered by time
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode)]
+pub struct WithdrawalFeeKey {
+    #[n(0)]
+    pub token: Erc20Identifier,
+    #[n(1)]
+    pub time: u64,
+    #[n(2)]
+    pub identifier: IcpToEvmIdentifier,
+}
+
+impl WithdrawalFeeKey {
+    pub fn new(identifier: IcpToEvmIdentifier, tx: &IcpToEvmTx) -> Self {
+        Self {
+            token: Erc20Identifier(tx.erc20_contract_address, tx.chain_id),
+            time: tx.time,
+            identifier,
+        }
+    }
+
+    // Smallest key of the withdrawals of the token
+    pub fn first_of(token: Erc20Identifier) -> Self {
+        Self {
+            token,
+            time: 0,
+            identifier: IcpToEvmIdentifier(0, ChainId(0)),
+        }
+    }
+}
+
+// Transactions submitted by the frontend that are still pending verification, per owner
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode)]
+pub struct PendingUnverifiedKey {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    pub owner: Principal,
+    #[n(1)]
+    pub tx: StuckTxKey,
+}
+
+impl PendingUnverifiedKey {
+    // Smallest key of the transactions of the owner
+    pub fn first_of(owner: Principal) -> Self {
+        Self {
+            owner,
+            tx: StuckTxKey::IcpToEvm(IcpToEvmIdentifier(0, ChainId(0))),
+        }
+    }
+}
+
+// Progress of the rebuild of the indexes derived from the transactions
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct IndexesCheckpoint {
+    // Version of the indexes that are complete or being rebuilt
+    #[n(0)]
+    pub version: u32,
+    // Next step of the rebuild, None once the indexes are complete
+    #[n(1)]
+    pub rebuild: Option<IndexRebuildStep>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum IndexRebuildStep {
+    #[n(0)]
+    ClearIndexes,
+    // Indexes the deposits after the given one
+    #[n(1)]
+    EvmToIcpTxs(#[n(0)] Option<EvmToIcpTxIdentifier>),
+    // Indexes the withdrawals after the given one
+    #[n(2)]
+    IcpToEvmTxs(#[n(0)] Option<IcpToEvmIdentifier>),
+    // Certifies the dex actions of the principals after the given one
+    #[n(3)]
+    DexActions(#[cbor(n(0), with = "crate::cbor::principal::option")] Option<Principal>),
+}
@@ -0,0 +1,100 @@
+// Estimation of withdrawal (icp_to_evm) costs based on the gas fees paid by recent finalized withdrawals.
+// All the fees are paid in the native token of the chain.
+
+use crate::numeric::Erc20TokenAmount;
+use crate::state::types::{IcpToEvmStatus, IcpToEvmTx};
+
+// Number of most recent finalized withdrawals used for the estimation
+pub const WITHDRAWAL_COST_SAMPLE_SIZE: usize = 100;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GasFeeStats {
+    pub sample_size: usize,
+    pub median: Erc20TokenAmount,
+    pub p90: Erc20TokenAmount,
+    pub max: Erc20TokenAmount,
+}
+
+// A withdrawal is finalized once its transaction is mined, whether it succeeded or not
+pub fn is_finalized_withdrawal(tx: &IcpToEvmTx) -> bool {
+    matches!(
+        tx.status,
+        IcpToEvmStatus::Successful | IcpToEvmStatus::Failed
+    ) && tx.total_gas_spent.is_some()
+}
+
+// Nearest rank percentile of an ascending sorted list
+pub fn percentile(sorted_fees: &[Erc20TokenAmount], percent: usize) -> Option<Erc20TokenAmount> {
+    if sorted_fees.is_empty() {
+        return None;
+    }
+
+    let rank = (percent * sorted_fees.len()).div_ceil(100).max(1);
+    sorted_fees.get(rank.min(sorted_fees.len()) - 1).copied()
+}
+
+// Fees should contain the most recent finalized withdrawals
+pub fn gas_fee_stats(mut fees: Vec<Erc20TokenAmount>) -> Option<GasFeeStats> {
+    fees.sort();
+
+    Some(GasFeeStats {
+        sample_size: fees.len(),
+        median: percentile(&fees, 50)?,
+        p90: percentile(&fees, 90)?,
+        max: *fees.last()?,
+    })
+}
+
+// Converts an amount of the native token to usd
+pub fn to_usd(amount: Erc20TokenAmount, decimals: u8, usd_price: &str) -> Option<String> {
+    let usd_price = usd_price.parse::<f64>().ok()?;
+    let amount = amount.as_f64() / 10_f64.powi(decimals as i32);
+
+    Some(format!("{:.6}", amount * usd_price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amounts(values: &[u64]) -> Vec<Erc20TokenAmount> {
+        values.iter().map(|v| Erc20TokenAmount::from(*v)).collect()
+    }
+
+    #[test]
+    fn test_percentile() {
+        let fees = amounts(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        assert_eq!(percentile(&fees, 50), Some(Erc20TokenAmount::from(5_u64)));
+        assert_eq!(percentile(&fees, 90), Some(Erc20TokenAmount::from(9_u64)));
+        assert_eq!(percentile(&fees, 100), Some(Erc20TokenAmount::from(10_u64)));
+        assert_eq!(percentile(&fees, 0), Some(Erc20TokenAmount::from(1_u64)));
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn test_gas_fee_stats() {
+        let stats = gas_fee_stats(amounts(&[30, 10, 20])).unwrap();
+
+        assert_eq!(stats.sample_size, 3);
+        assert_eq!(stats.median, Erc20TokenAmount::from(20_u64));
+        assert_eq!(stats.p90, Erc20TokenAmount::from(30_u64));
+        assert_eq!(stats.max, Erc20TokenAmount::from(30_u64));
+
+        assert_eq!(gas_fee_stats(vec![]), None);
+    }
+
+    #[test]
+    fn test_to_usd() {
+        // 0.01 ETH at 2000 usd
+        assert_eq!(
+            to_usd(
+                Erc20TokenAmount::from(10_000_000_000_000_000_u64),
+                18,
+                "2000"
+            ),
+            Some("20.000000".to_string())
+        );
+        assert_eq!(to_usd(Erc20TokenAmount::from(1_u64), 18, "invalid"), None);
+    }
+}
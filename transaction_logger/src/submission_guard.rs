@@ -0,0 +1,208 @@
+// Limits for transactions submitted by the frontend through new_evm_to_icp_tx and new_icp_to_evm_tx.
+// A transaction can only be submitted by its owner or by an allowed relayer, owners are rate limited
+// and can only have a limited number of pending unverified transactions.
+// Pending transactions are counted from a stable index of the state, so the cap holds across upgrades.
+// Rate limit counters are kept in heap memory, reset on upgrade and evicted once their window expired.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use candid::{CandidType, Deserialize, Principal};
+
+use crate::state::read_state;
+
+// 1 Minute
+pub const RATE_LIMIT_WINDOW_NS: u64 = 60 * 1_000_000_000;
+
+// Maximum submissions of a caller in one rate limit window, relayers are not rate limited
+pub const MAX_SUBMISSIONS_PER_WINDOW: u32 = 10;
+
+// Maximum pending unverified transactions of a principal
+pub const MAX_PENDING_UNVERIFIED_PER_PRINCIPAL: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubmissionRejection {
+    UnauthorizedCaller,
+    RateLimited,
+    TooManyPendingTransactions,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubmissionCounters {
+    pub caller: Principal,
+    pub accepted: u64,
+    pub rejected_unauthorized: u64,
+    pub rejected_rate_limited: u64,
+    pub rejected_too_many_pending: u64,
+    pub window_start: u64,
+    pub submissions_in_window: u32,
+    pub last_submission_at: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubmissionStats {
+    pub relayers: Vec<Principal>,
+    pub callers: Vec<SubmissionCounters>,
+    // Pending unverified transactions per owner
+    pub pending_unverified: Vec<(Principal, u64)>,
+}
+
+thread_local! {
+    static SUBMISSION_COUNTERS: RefCell<BTreeMap<Principal, SubmissionCounters>> = RefCell::default();
+}
+
+fn mutate_counters<F, R>(caller: Principal, f: F) -> R
+where
+    F: FnOnce(&mut SubmissionCounters) -> R,
+{
+    SUBMISSION_COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        let caller_counters = counters
+            .entry(caller)
+            .or_insert_with(|| SubmissionCounters {
+                caller,
+                ..Default::default()
+            });
+        f(caller_counters)
+    })
+}
+
+fn record_rejection(caller: Principal, rejection: SubmissionRejection) -> SubmissionRejection {
+    mutate_counters(caller, |counters| match rejection {
+        SubmissionRejection::UnauthorizedCaller => counters.rejected_unauthorized += 1,
+        SubmissionRejection::RateLimited => counters.rejected_rate_limited += 1,
+        SubmissionRejection::TooManyPendingTransactions => counters.rejected_too_many_pending += 1,
+    });
+    rejection
+}
+
+// Consumes one submission of the current window, false if the caller exceeded the limit
+fn consume_rate_limit(caller: Principal, now: u64) -> bool {
+    mutate_counters(caller, |counters| {
+        if now.saturating_sub(counters.window_start) >= RATE_LIMIT_WINDOW_NS {
+            counters.window_start = now;
+            counters.submissions_in_window = 0;
+        }
+
+        if counters.submissions_in_window >= MAX_SUBMISSIONS_PER_WINDOW {
+            return false;
+        }
+
+        counters.submissions_in_window += 1;
+        true
+    })
+}
+
+// Removes the counters of callers whose rate limit window expired
+fn evict_expired_counters(now: u64) {
+    SUBMISSION_COUNTERS.with(|counters| {
+        counters
+            .borrow_mut()
+            .retain(|_, counters| now.saturating_sub(counters.window_start) < RATE_LIMIT_WINDOW_NS)
+    })
+}
+
+// Checks if caller can submit a new transaction owned by owner
+pub fn check_submission(
+    caller: Principal,
+    owner: Principal,
+    is_relayer: bool,
+    now: u64,
+) -> Result<(), SubmissionRejection> {
+    evict_expired_counters(now);
+
+    if caller != owner && !is_relayer {
+        return Err(record_rejection(
+            caller,
+            SubmissionRejection::UnauthorizedCaller,
+        ));
+    }
+
+    if !is_relayer && !consume_rate_limit(caller, now) {
+        return Err(record_rejection(caller, SubmissionRejection::RateLimited));
+    }
+
+    if read_state(|s| s.count_pending_unverified(owner)) >= MAX_PENDING_UNVERIFIED_PER_PRINCIPAL {
+        return Err(record_rejection(
+            caller,
+            SubmissionRejection::TooManyPendingTransactions,
+        ));
+    }
+
+    Ok(())
+}
+
+// The submitted transaction is counted as pending through the state index once it is recorded
+pub fn record_submission(caller: Principal, now: u64) {
+    mutate_counters(caller, |counters| {
+        counters.accepted += 1;
+        counters.last_submission_at = Some(now);
+    });
+}
+
+pub fn get_submission_stats() -> SubmissionStats {
+    let callers =
+        SUBMISSION_COUNTERS.with(|counters| counters.borrow().values().cloned().collect());
+
+    SubmissionStats {
+        relayers: read_state(|s| s.get_allowed_relayers()),
+        callers,
+        pending_unverified: read_state(|s| s.get_pending_unverified_counts()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_window() {
+        let caller = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+
+        for _ in 0..MAX_SUBMISSIONS_PER_WINDOW {
+            assert!(consume_rate_limit(caller, RATE_LIMIT_WINDOW_NS));
+        }
+        assert!(!consume_rate_limit(caller, RATE_LIMIT_WINDOW_NS));
+
+        // A new window starts after RATE_LIMIT_WINDOW_NS
+        assert!(consume_rate_limit(caller, 2 * RATE_LIMIT_WINDOW_NS));
+    }
+
+    #[test]
+    fn test_evict_expired_counters() {
+        let caller = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+        let other_caller = Principal::anonymous();
+
+        assert!(consume_rate_limit(caller, RATE_LIMIT_WINDOW_NS));
+        assert!(consume_rate_limit(other_caller, 2 * RATE_LIMIT_WINDOW_NS));
+
+        // Only the counters whose window is still running are kept
+        evict_expired_counters(2 * RATE_LIMIT_WINDOW_NS);
+        SUBMISSION_COUNTERS.with(|counters| {
+            let counters = counters.borrow();
+            assert!(!counters.contains_key(&caller));
+            assert!(counters.contains_key(&other_caller));
+        });
+
+        evict_expired_counters(3 * RATE_LIMIT_WINDOW_NS);
+        SUBMISSION_COUNTERS.with(|counters| assert!(counters.borrow().is_empty()));
+    }
+
+    #[test]
+    fn test_reject_submission_for_another_principal() {
+        let caller = Principal::from_text("xevnm-gaaaa-aaaar-qafnq-cai").unwrap();
+        let owner = Principal::anonymous();
+
+        assert_eq!(
+            check_submission(caller, owner, false, 0),
+            Err(SubmissionRejection::UnauthorizedCaller)
+        );
+        assert_eq!(
+            SUBMISSION_COUNTERS
+                .with(|counters| counters.borrow().get(&caller).cloned())
+                .unwrap()
+                .rejected_unauthorized,
+            1
+        );
+    }
+}
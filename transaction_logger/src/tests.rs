@@ -21,6 +21,9 @@ fn create_mock_token(logo: &str) -> IcpToken {
         token_type: IcpTokenType::ICRC1,
         rank: Some(1),
         listed_on_appic_dex: None,
+        supported_standards: None,
+        validation_failures: None,
+        verified: None,
     }
 }
 
@@ -1,13 +1,21 @@
+use std::collections::BTreeSet;
+
 use candid::Principal;
 use ic_canister_log::log;
 
 use crate::{
     guard::TimerGuard,
-    ledger_manager_client::LsClient,
+    icp_tokens_service::TokenService,
+    ledger_manager_client::{lsm_types::ManagedCanisters, EvmIcpBridgePairs, LsClient},
     logs::{DEBUG, INFO},
+    minter_client::{CallError, Runtime},
     state::{
-        mutate_state,
-        types::{BridgePair, Erc20Identifier, MinterKey, Operator},
+        mutate_state, read_state,
+        types::{
+            BridgePair, Erc20Identifier, IcpToken, MinterKey, Operator, PendingBridgePairKey,
+            PendingBridgePairReason,
+        },
+        State,
     },
 };
 
@@ -23,6 +31,13 @@ pub async fn update_bridge_pairs() {
         Err(_) => return,
     };
 
+    // Pairs that were pending because of a missing icp token can be resolved in this run
+    let fetched_tokens = fetch_missing_icp_tokens(&TokenService::new()).await;
+    if !fetched_tokens.is_empty() {
+        let now = ic_cdk::api::time();
+        mutate_state(|s| record_fetched_icp_tokens(s, fetched_tokens, now));
+    }
+
     let managers = [
         (APPIC_LEDGER_MANAGER_ID, Operator::AppicMinter, "Appic LSM"),
         (
@@ -41,7 +56,16 @@ pub async fn update_bridge_pairs() {
             source_name
         );
 
-        match client.get_erc20_list().await {
+        let result = match operator {
+            // The lsm info also carries the state of twin ledger suite requests
+            Operator::AppicMinter => client.get_lsm_info().await.map(|info| {
+                process_twin_ledger_requests(&info.managed_canisters);
+                EvmIcpBridgePairs::from(info)
+            }),
+            Operator::DfinityCkEthMinter => client.get_erc20_list().await,
+        };
+
+        match result {
             Ok(bridge_pairs) => {
                 process_bridge_pairs(bridge_pairs.get_bridge_pairs_iter(), operator, source_name)
             }
@@ -57,45 +81,318 @@ pub async fn update_bridge_pairs() {
     }
 }
 
+// Fetches the metadata of icp tokens that blocked bridge pairs in previous runs
+async fn fetch_missing_icp_tokens<R: Runtime>(
+    service: &TokenService<R>,
+) -> Vec<(Principal, Result<IcpToken, CallError>)> {
+    let missing_tokens = read_state(|s| s.get_pending_bridge_pairs_missing_icp_tokens());
+
+    if missing_tokens.is_empty() {
+        return vec![];
+    }
+
+    service
+        .validate_tokens_batch(
+            missing_tokens
+                .into_iter()
+                .map(|ledger_id| (ledger_id, None, None))
+                .collect(),
+        )
+        .await
+}
+
+// Records the fetched tokens so that their pairs are recorded by the next scrape,
+// failures are kept on the pending pairs
+fn record_fetched_icp_tokens(
+    s: &mut State,
+    results: Vec<(Principal, Result<IcpToken, CallError>)>,
+    now: u64,
+) {
+    for (ledger_id, result) in results {
+        match result {
+            Ok(token) => {
+                if s.is_icp_token_blocked(&ledger_id, &token.symbol) {
+                    s.record_pending_bridge_pair_error(
+                        &ledger_id,
+                        "Token is blocklisted".to_string(),
+                    );
+                    continue;
+                }
+
+                log!(
+                    INFO,
+                    "[Scrape new bridge pairs] Fetched missing icp token {}",
+                    ledger_id
+                );
+                s.record_icp_token(ledger_id, token, now);
+            }
+            Err(err) => s.record_pending_bridge_pair_error(&ledger_id, err.to_string()),
+        }
+    }
+}
+
+// Updates the status of twin ledger suite requests based on the canisters managed by the lsm
+fn process_twin_ledger_requests(managed_canisters: &[ManagedCanisters]) {
+    let now = ic_cdk::api::time();
+
+    mutate_state(|state| {
+        for canisters in managed_canisters {
+            if let Some(identifier) = canisters.erc20_identifier() {
+                state.update_twin_ledger_request_status(
+                    identifier,
+                    canisters.twin_ledger_suite_status(),
+                    canisters.ledger_id(),
+                    Some(canisters.twin_erc20_token_symbol.clone()),
+                    now,
+                );
+            }
+        }
+    });
+}
+
 // Processes bridge pairs
 fn process_bridge_pairs<I>(bridge_pairs: I, operator: Operator, source_name: &str)
 where
     I: Iterator<Item = (Erc20Identifier, candid::Principal)>,
 {
+    let now = ic_cdk::api::time();
+
     mutate_state(|state| {
-        for (erc20_identifier, principal_id) in bridge_pairs {
-            let chain_id = erc20_identifier.chain_id();
-            let minter_key = MinterKey(chain_id, operator);
-
-            // enable minter
-            state.enable_minter(&minter_key);
-
-            if let Some(evm_token) = state.get_evm_token_by_identifier(&erc20_identifier) {
-                if let Some(icp_token) = state.get_icp_token_by_principal(&principal_id) {
-                    let bridge_pair = BridgePair {
-                        icp_token,
-                        evm_token,
-                    };
+        record_listed_bridge_pairs(state, bridge_pairs, operator, source_name, now)
+    });
+}
+
+// Records the pairs listed by a ledger suite manager/orchestrator, pairs with missing tokens
+// are kept pending and pairs that are not listed anymore are removed
+fn record_listed_bridge_pairs<I>(
+    state: &mut State,
+    bridge_pairs: I,
+    operator: Operator,
+    source_name: &str,
+    now: u64,
+) where
+    I: Iterator<Item = (Erc20Identifier, candid::Principal)>,
+{
+    // Identifiers listed in the current snapshot, used to detect removed pairs
+    let mut listed = BTreeSet::new();
+
+    for (erc20_identifier, principal_id) in bridge_pairs {
+        listed.insert(erc20_identifier.clone());
+
+        let chain_id = erc20_identifier.chain_id();
+        let minter_key = MinterKey(chain_id, operator);
+
+        // enable minter
+        state.enable_minter(&minter_key);
+
+        let pending_key = PendingBridgePairKey(operator, erc20_identifier.clone());
+
+        match (
+            state.get_evm_token_by_identifier(&erc20_identifier),
+            state.get_icp_token_by_principal(&principal_id),
+        ) {
+            (Some(evm_token), Some(icp_token)) => {
+                state.remove_pending_bridge_pair(&pending_key);
+
+                let bridge_pair = BridgePair {
+                    icp_token,
+                    evm_token,
+                    removed_at: None,
+                };
+                if state.record_bridge_pair(operator, erc20_identifier.clone(), bridge_pair, now) {
                     log!(
                         INFO,
                         "[Scrape new bridge pairs] Recording new bridge pair {:?} from {}",
                         erc20_identifier,
                         source_name
                     );
-                    match operator {
-                        Operator::DfinityCkEthMinter => {
-                            state
-                                .supported_ckerc20_tokens
-                                .insert(erc20_identifier, bridge_pair);
-                        }
-                        Operator::AppicMinter => {
-                            state
-                                .supported_twin_appic_tokens
-                                .insert(erc20_identifier, bridge_pair);
-                        }
-                    }
+                }
+            }
+            (evm_token, icp_token) => {
+                let reason =
+                    PendingBridgePairReason::from_missing(evm_token.is_none(), icp_token.is_none())
+                        .expect("BUG: at least one of the tokens is missing");
+
+                if state.record_pending_bridge_pair(pending_key, principal_id, reason, now) {
+                    log!(
+                        INFO,
+                        "[Scrape new bridge pairs] Bridge pair {:?} from {} is pending: {:?}",
+                        erc20_identifier,
+                        source_name,
+                        reason
+                    );
                 }
             }
         }
-    });
+    }
+
+    state.remove_unlisted_pending_bridge_pairs(operator, &listed);
+
+    for erc20_identifier in state.remove_unlisted_bridge_pairs(operator, &listed, now) {
+        log!(
+            INFO,
+            "[Scrape new bridge pairs] Removing bridge pair {:?} no longer listed by {}",
+            erc20_identifier,
+            source_name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::address::Address;
+    use crate::icp_tokens_service::tests::{token_metadata, MockRuntime};
+    use crate::state::blocklist::{BlockedToken, BlocklistEntry};
+    use crate::state::tests::{evm_token, with_state};
+    use crate::state::types::{ChainId, PendingBridgePair};
+
+    fn erc20(address: &str) -> Erc20Identifier {
+        Erc20Identifier(Address::from_str(address).unwrap(), ChainId(1))
+    }
+
+    fn pending_pairs() -> Vec<(PendingBridgePairKey, PendingBridgePair)> {
+        with_state(|s| s.get_pending_bridge_pairs())
+    }
+
+    #[test]
+    fn test_pending_bridge_pairs_are_resolved() {
+        let usdc = erc20("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let usdt = erc20("0xdAC17F958D2ee523a2206206994597C13D831ec7");
+        let dai = erc20("0x6B175474E89094C44Da98b954EedeAC495271d0F");
+        let ckusdc = Principal::from_slice(&[5, 1]);
+        let scam = Principal::from_slice(&[6, 1]);
+        let unreachable = Principal::from_slice(&[7, 1]);
+        let operator = Operator::DfinityCkEthMinter;
+        let listed = || {
+            vec![
+                (usdc.clone(), ckusdc),
+                (usdt.clone(), scam),
+                (dai.clone(), unreachable),
+            ]
+            .into_iter()
+        };
+
+        with_state(|s| {
+            for erc20_identifier in [&usdc, &usdt, &dai] {
+                s.record_evm_token(
+                    erc20_identifier.clone(),
+                    evm_token(erc20_identifier, "T"),
+                    0,
+                );
+            }
+            s.icp_token_blocklist.insert(
+                BlockedToken::SymbolPattern("SCAM".to_string()),
+                BlocklistEntry {
+                    reason: None,
+                    added_at: 0,
+                },
+            );
+
+            record_listed_bridge_pairs(s, listed(), operator, "LSO", 10);
+            // Still pending in the next run
+            record_listed_bridge_pairs(s, listed(), operator, "LSO", 20);
+        });
+
+        // Ordered by operator and erc20 address
+        assert_eq!(
+            pending_pairs(),
+            vec![
+                (
+                    PendingBridgePairKey(operator, dai.clone()),
+                    PendingBridgePair {
+                        icp_ledger_id: unreachable,
+                        reason: PendingBridgePairReason::MissingIcpToken,
+                        first_seen_at: 10,
+                        last_seen_at: 20,
+                        last_error: None,
+                    },
+                ),
+                (
+                    PendingBridgePairKey(operator, usdc.clone()),
+                    PendingBridgePair {
+                        icp_ledger_id: ckusdc,
+                        reason: PendingBridgePairReason::MissingIcpToken,
+                        first_seen_at: 10,
+                        last_seen_at: 20,
+                        last_error: None,
+                    },
+                ),
+                (
+                    PendingBridgePairKey(operator, usdt.clone()),
+                    PendingBridgePair {
+                        icp_ledger_id: scam,
+                        reason: PendingBridgePairReason::MissingIcpToken,
+                        first_seen_at: 10,
+                        last_seen_at: 20,
+                        last_error: None,
+                    },
+                ),
+            ]
+        );
+
+        let mut runtime = MockRuntime::default();
+        runtime.respond(ckusdc, "icrc1_metadata", token_metadata("ckUSDC"));
+        runtime.respond(scam, "icrc1_metadata", token_metadata("SCAM"));
+        let service = TokenService::with_runtime(runtime);
+
+        let fetched = futures::executor::block_on(fetch_missing_icp_tokens(&service));
+        assert_eq!(
+            service.runtime().calls("icrc1_metadata"),
+            vec![ckusdc, scam, unreachable]
+        );
+        with_state(|s| record_fetched_icp_tokens(s, fetched, 30));
+
+        with_state(|s| {
+            assert!(s.get_icp_token_by_principal(&ckusdc).is_some());
+            assert!(s.get_icp_token_by_principal(&scam).is_none());
+            assert!(s.get_icp_token_by_principal(&unreachable).is_none());
+        });
+
+        let errors: Vec<(Erc20Identifier, Option<String>)> = pending_pairs()
+            .into_iter()
+            .map(|(key, pending_pair)| (key.1, pending_pair.last_error))
+            .collect();
+        assert_eq!(errors.len(), 3);
+        for (erc20_identifier, last_error) in errors {
+            if erc20_identifier == usdt {
+                assert_eq!(last_error, Some("Token is blocklisted".to_string()));
+            } else if erc20_identifier == dai {
+                assert!(last_error.is_some());
+            } else {
+                assert_eq!(last_error, None);
+            }
+        }
+
+        // The resolved pair is recorded and the pair of a token that is not listed anymore
+        // is cleared
+        with_state(|s| {
+            record_listed_bridge_pairs(
+                s,
+                vec![(usdc.clone(), ckusdc), (usdt.clone(), scam)].into_iter(),
+                operator,
+                "LSO",
+                40,
+            );
+            assert_eq!(s.get_icrc_twin_for_erc20(&usdc, &operator), Some(ckusdc));
+            assert_eq!(s.get_icrc_twin_for_erc20(&usdt, &operator), None);
+            assert_eq!(
+                s.get_pending_bridge_pairs_missing_icp_tokens(),
+                BTreeSet::from([scam])
+            );
+        });
+
+        let pending = pending_pairs();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, PendingBridgePairKey(operator, usdt));
+        assert_eq!(pending[0].1.first_seen_at, 10);
+        assert_eq!(pending[0].1.last_seen_at, 40);
+        // The error of the last attempt is kept until the next fetch
+        assert_eq!(
+            pending[0].1.last_error,
+            Some("Token is blocklisted".to_string())
+        );
+    }
 }
@@ -2,12 +2,19 @@ use crate::{
     guard::TimerGuard,
     icp_tokens_service::TokenService,
     logs::INFO,
-    state::{mutate_state, read_state, types::IcpToken},
+    minter_client::{CallError, Reason, Runtime},
+    state::{
+        mutate_state, read_state,
+        types::{IcpToken, TokenValidationFailure},
+    },
 };
 
 use ic_canister_log::log;
 use std::collections::HashSet;
 
+// Number of tokens validated between two checkpoints
+const TOKEN_VALIDATION_BATCH_SIZE: usize = 100;
+
 pub async fn update_icp_tokens() {
     // Issue a timer guard
     let _guard = match TimerGuard::new(crate::guard::TaskType::UpdateIcpTokens) {
@@ -45,6 +52,20 @@ pub async fn update_icp_tokens() {
         read_state(|s| s.get_icp_token_by_principal(&token.ledger_id).is_none())
     });
 
+    // Filter blocked tokens, symbol patterns can only be checked after fetching the metadata
+    unique_tokens.retain(|token: &IcpToken| {
+        let blocked = read_state(|s| s.is_icp_token_blocked(&token.ledger_id, &token.symbol));
+        if blocked {
+            log!(
+                INFO,
+                "[Update ICP Tokens] Skipping blocked token {} with ledger_id {:?}",
+                token.symbol,
+                token.ledger_id
+            );
+        }
+        !blocked
+    });
+
     log!(
         INFO,
         "[Update ICP Tokens] Called appic_dex and icp_swap to get tokens list, Received {} tokens",
@@ -61,9 +82,10 @@ pub async fn update_icp_tokens() {
         icp_tokens.len(),
     );
 
+    let now = ic_cdk::api::time();
     mutate_state(|s| {
         for token in icp_tokens {
-            s.record_icp_token(token.ledger_id, token.clone());
+            s.record_icp_token(token.ledger_id, token.clone(), now);
         }
     });
 }
@@ -100,6 +122,9 @@ pub async fn update_usd_price() {
                 );
             })
         });
+
+    // Bridge pairs are returned with the usd price of their icp token
+    mutate_state(|s| s.certify_bridge_pairs());
 }
 
 // Runs on interval basis to remove invalid tokens
@@ -110,58 +135,149 @@ pub async fn validate_tokens() {
         Err(_) => return,
     };
 
-    let tokens_service = TokenService::new();
+    validate_tokens_from_checkpoint(
+        &TokenService::new(),
+        TOKEN_VALIDATION_BATCH_SIZE,
+        ic_cdk::api::time(),
+    )
+    .await;
+}
 
-    // Get all tokens from state
-    let tokens = read_state(|s| s.get_icp_tokens());
+// Validates the listed tokens in batches of batch_size, a checkpoint is recorded after every batch
+// so that a validation interrupted by an upgrade or a trap resumes after the last validated batch
+pub async fn validate_tokens_from_checkpoint<R: Runtime>(
+    tokens_service: &TokenService<R>,
+    batch_size: usize,
+    now: u64,
+) {
+    // Resume from the last checkpoint if the previous validation was interrupted
+    let mut last_validated_ledger = read_state(|s| s.get_token_validation_checkpoint());
+
+    if let Some(ledger_id) = last_validated_ledger {
+        log!(
+            INFO,
+            "[Validate Tokens] Resuming validation after ledger_id {:?}",
+            ledger_id
+        );
+    }
 
     let mut valid_tokens = 0;
+    let mut failed_tokens = 0;
+    let mut removed_tokens = 0;
 
-    for token in tokens.iter() {
-        let validation_result = tokens_service
-            .validate_token(token.ledger_id, token.rank, token.listed_on_appic_dex)
-            .await;
+    loop {
+        let tokens = read_state(|s| s.get_icp_tokens_after(last_validated_ledger, batch_size));
 
-        if let Ok(updated_icp_token) = validation_result {
-            valid_tokens += 1;
+        let Some(last_token) = tokens.last() else {
+            break;
+        };
+        let batch_last_ledger = last_token.ledger_id;
+
+        let validation_results = tokens_service
+            .validate_tokens_batch(
+                tokens
+                    .iter()
+                    .map(|token| (token.ledger_id, token.rank, token.listed_on_appic_dex))
+                    .collect(),
+            )
+            .await;
 
-            // update token with new metadata
-            mutate_state(|s| {
-                s.icp_token_list.insert(
-                    token.ledger_id,
-                    IcpToken {
-                        usd_price: token.usd_price.clone(),
-                        token_type: token.token_type.clone(),
-                        rank: token.rank,
-                        listed_on_appic_dex: token.listed_on_appic_dex,
-                        ..updated_icp_token
-                    },
-                )
-            });
-        } else {
-            log!(
-                INFO,
-                "[Validate Tokens] Token with ledger_id {:?} is invalid and will be removed",
-                token.ledger_id
-            );
-            mutate_state(|s| s.remove_icp_token(&token.ledger_id))
+        for (token, (_ledger_id, validation_result)) in tokens.iter().zip(validation_results) {
+            match validation_result {
+                Ok(updated_icp_token) => {
+                    valid_tokens += 1;
+
+                    // update token with new metadata
+                    if !mutate_state(|s| s.record_validated_icp_token(updated_icp_token)) {
+                        log!(
+                            INFO,
+                            "[Validate Tokens] Token with ledger_id {:?} was removed or blocked during its validation",
+                            token.ledger_id
+                        );
+                    }
+                }
+                Err(e) => {
+                    failed_tokens += 1;
+
+                    let failure = classify_validation_failure(&e);
+
+                    log!(
+                        INFO,
+                        "[Validate Tokens] Token with ledger_id {:?} failed validation: {:?}",
+                        token.ledger_id,
+                        failure
+                    );
+
+                    let removed = mutate_state(|s| {
+                        s.record_icp_token_validation_failure(&token.ledger_id, failure, now)
+                    });
+
+                    if removed {
+                        removed_tokens += 1;
+
+                        log!(
+                            INFO,
+                            "[Validate Tokens] Token with ledger_id {:?} passed its grace period and is removed",
+                            token.ledger_id
+                        );
+                    }
+                }
+            }
         }
+
+        // Checkpoint after each batch
+        last_validated_ledger = Some(batch_last_ledger);
+        mutate_state(|s| s.set_token_validation_checkpoint(last_validated_ledger));
     }
 
+    // Validation finished, next run starts from the beginning
+    mutate_state(|s| s.set_token_validation_checkpoint(None));
+
     log!(
         INFO,
-        "[Validate Tokens] Validation complete. Remaining tokens: {}, removing {}",
+        "[Validate Tokens] Validation complete. Validated {} tokens, failed {}, removed {}",
         valid_tokens,
-        tokens.len() - valid_tokens
+        failed_tokens,
+        removed_tokens
     );
 }
 
+// The supported standards are None if the icrc1_supported_standards call failed,
+// in which case the previously known token type and standards are kept
+pub fn with_previous_standards(updated_token: IcpToken, previous_token: &IcpToken) -> IcpToken {
+    if updated_token.supported_standards.is_some() {
+        return updated_token;
+    }
+
+    IcpToken {
+        token_type: previous_token.token_type.clone(),
+        supported_standards: previous_token.supported_standards.clone(),
+        ..updated_token
+    }
+}
+
+// Transient failures might resolve by themselves and do not count as strikes
+pub fn classify_validation_failure(error: &CallError) -> TokenValidationFailure {
+    match &error.reason {
+        Reason::TransientInternalError(_) | Reason::OutOfCycles => {
+            TokenValidationFailure::Transient(error.to_string())
+        }
+        Reason::CanisterError(_) | Reason::Rejected(_) | Reason::InternalError(_) => {
+            TokenValidationFailure::Permanent(error.to_string())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::numeric::Erc20TokenAmount;
-    use crate::state::types::{IcpToken, IcpTokenType};
+    use crate::state::types::{
+        IcpToken, IcpTokenType, IcpTokenValidationFailures, TokenValidationConfig,
+    };
 
     use super::*;
+    use crate::icp_tokens_service::tests::{token_metadata, MockRuntime};
+    use crate::state::blocklist::{BlockedToken, BlocklistEntry};
     use candid::Principal;
 
     #[test]
@@ -177,6 +293,9 @@ mod tests {
             usd_price: "0".to_string(),
             logo: "".to_string(),
             listed_on_appic_dex: Some(true),
+            supported_standards: None,
+            validation_failures: None,
+            verified: None,
         };
 
         let token2 = IcpToken {
@@ -190,6 +309,9 @@ mod tests {
             usd_price: "0".to_string(),
             logo: "".to_string(),
             listed_on_appic_dex: Some(false),
+            supported_standards: None,
+            validation_failures: None,
+            verified: None,
         };
 
         let token3 = IcpToken {
@@ -203,6 +325,9 @@ mod tests {
             usd_price: "0".to_string(),
             logo: "".to_string(),
             listed_on_appic_dex: Some(true),
+            supported_standards: None,
+            validation_failures: None,
+            verified: None,
         };
 
         assert_eq!(token1, token2); // Same ledger_id should mean equality
@@ -223,6 +348,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
             IcpToken {
                 ledger_id: Principal::from_text("6fvyi-faaaa-aaaam-qbiga-cai").unwrap(),
@@ -235,6 +363,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
         ];
 
@@ -250,6 +381,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(false),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
             IcpToken {
                 ledger_id: Principal::from_text("sr5fw-zqaaa-aaaak-qig5q-cai").unwrap(),
@@ -262,6 +396,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
         ];
 
@@ -294,6 +431,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
             IcpToken {
                 ledger_id: Principal::from_text("dikjh-xaaaa-aaaak-afnba-cai").unwrap(), // Duplicate
@@ -306,6 +446,9 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
             IcpToken {
                 ledger_id: Principal::from_text("sr5fw-zqaaa-aaaak-qig5q-cai").unwrap(),
@@ -318,10 +461,250 @@ mod tests {
                 usd_price: "0".to_string(),
                 logo: "".to_string(),
                 listed_on_appic_dex: Some(true),
+                supported_standards: None,
+                validation_failures: None,
+                verified: None,
             },
         ];
 
         let unique: HashSet<_> = tokens.into_iter().collect();
         assert_eq!(unique.len(), 2); // Only two unique tokens based on ledger_id
     }
+
+    #[test]
+    fn test_classify_validation_failure() {
+        let transient = CallError {
+            method: "icrc1_metadata".to_string(),
+            reason: Reason::TransientInternalError("busy".to_string()),
+        };
+        let out_of_cycles = CallError {
+            method: "icrc1_metadata".to_string(),
+            reason: Reason::OutOfCycles,
+        };
+        let canister_error = CallError {
+            method: "icrc1_metadata".to_string(),
+            reason: Reason::CanisterError("no wasm module".to_string()),
+        };
+
+        assert!(matches!(
+            classify_validation_failure(&transient),
+            TokenValidationFailure::Transient(_)
+        ));
+        assert!(matches!(
+            classify_validation_failure(&out_of_cycles),
+            TokenValidationFailure::Transient(_)
+        ));
+        assert!(matches!(
+            classify_validation_failure(&canister_error),
+            TokenValidationFailure::Permanent(_)
+        ));
+    }
+
+    #[test]
+    fn test_strikes_and_grace_period() {
+        let config = TokenValidationConfig {
+            max_strikes: 2,
+            removal_grace_period: 100,
+        };
+        let permanent = TokenValidationFailure::Permanent("no wasm module".to_string());
+        let transient = TokenValidationFailure::Transient("busy".to_string());
+
+        // Transient failures do not count as strikes
+        let failures =
+            IcpTokenValidationFailures::record_failure(None, transient.clone(), 10, &config);
+        assert_eq!(failures.strikes, 0);
+        assert_eq!(failures.first_failed_at, 10);
+        assert!(!failures.disabled);
+
+        let failures = IcpTokenValidationFailures::record_failure(
+            Some(failures),
+            permanent.clone(),
+            20,
+            &config,
+        );
+        assert_eq!(failures.strikes, 1);
+        assert!(!failures.disabled);
+        assert!(!failures.should_be_removed(200, &config));
+
+        // Soft disabled after max strikes, but only removed after the grace period
+        let failures =
+            IcpTokenValidationFailures::record_failure(Some(failures), permanent, 30, &config);
+        assert_eq!(failures.strikes, 2);
+        assert_eq!(failures.first_failed_at, 10);
+        assert!(failures.disabled);
+        assert_eq!(failures.removal_time(&config), 110);
+        assert!(!failures.should_be_removed(30, &config));
+        assert!(failures.should_be_removed(110, &config));
+    }
+
+    #[test]
+    fn test_with_previous_standards() {
+        let previous_token = IcpToken {
+            ledger_id: Principal::from_text("dikjh-xaaaa-aaaak-afnba-cai").unwrap(),
+            name: String::from("TokenA"),
+            decimals: 8,
+            symbol: String::from("TKA"),
+            token_type: IcpTokenType::ICRC3,
+            fee: Erc20TokenAmount::from(500_u64),
+            rank: None,
+            usd_price: "0".to_string(),
+            logo: "".to_string(),
+            listed_on_appic_dex: None,
+            supported_standards: Some(vec!["ICRC-1".to_string(), "ICRC-3".to_string()]),
+            validation_failures: None,
+            verified: None,
+        };
+
+        // Standards call failed, the token type and standards are kept
+        let updated_token = IcpToken {
+            name: String::from("TokenA v2"),
+            token_type: IcpTokenType::ICRC2,
+            supported_standards: None,
+            ..previous_token.clone()
+        };
+        let token = with_previous_standards(updated_token, &previous_token);
+        assert_eq!(token.name, "TokenA v2");
+        assert_eq!(token.token_type, IcpTokenType::ICRC3);
+        assert_eq!(
+            token.supported_standards,
+            previous_token.supported_standards
+        );
+
+        // Standards call succeeded, the new token type and standards are taken
+        let updated_token = IcpToken {
+            token_type: IcpTokenType::ICRC1,
+            supported_standards: Some(vec!["ICRC-1".to_string()]),
+            ..previous_token.clone()
+        };
+        let token = with_previous_standards(updated_token, &previous_token);
+        assert_eq!(token.token_type, IcpTokenType::ICRC1);
+        assert_eq!(token.supported_standards, Some(vec!["ICRC-1".to_string()]));
+    }
+
+    fn listed_token(ledger_id: Principal, symbol: &str) -> IcpToken {
+        IcpToken {
+            ledger_id,
+            name: format!("{} token", symbol),
+            decimals: 8,
+            symbol: symbol.to_string(),
+            token_type: IcpTokenType::ICRC2,
+            fee: Erc20TokenAmount::from(10_000_u64),
+            rank: Some(1),
+            usd_price: "1".to_string(),
+            logo: "".to_string(),
+            listed_on_appic_dex: None,
+            supported_standards: None,
+            validation_failures: None,
+            verified: Some(true),
+        }
+    }
+
+    #[test]
+    fn test_validation_resumes_after_checkpoint() {
+        let ledger_ids: Vec<Principal> =
+            (1..=5_u8).map(|i| Principal::from_slice(&[i, 1])).collect();
+
+        let mut runtime = MockRuntime::default();
+        mutate_state(|s| {
+            for (i, ledger_id) in ledger_ids.iter().enumerate() {
+                s.icp_token_list
+                    .insert(*ledger_id, listed_token(*ledger_id, &format!("T{}", i)));
+            }
+            // The previous validation was interrupted after its first batch
+            s.set_token_validation_checkpoint(Some(ledger_ids[1]));
+        });
+        for (i, ledger_id) in ledger_ids.iter().enumerate() {
+            runtime.respond(
+                *ledger_id,
+                "icrc1_metadata",
+                token_metadata(&format!("V{}", i)),
+            );
+        }
+
+        let service = TokenService::with_runtime(runtime);
+        futures::executor::block_on(validate_tokens_from_checkpoint(&service, 2, 0));
+
+        // Only the tokens after the checkpoint are validated, in two batches
+        assert_eq!(
+            service.runtime().calls("icrc1_metadata"),
+            ledger_ids[2..].to_vec()
+        );
+
+        read_state(|s| {
+            assert_eq!(
+                s.get_icp_token_by_principal(&ledger_ids[0]).unwrap().symbol,
+                "T0"
+            );
+            assert_eq!(
+                s.get_icp_token_by_principal(&ledger_ids[1]).unwrap().symbol,
+                "T1"
+            );
+            for (i, ledger_id) in ledger_ids.iter().enumerate().skip(2) {
+                let token = s.get_icp_token_by_principal(ledger_id).unwrap();
+                assert_eq!(token.symbol, format!("V{}", i));
+                // Prices, rank and verification are kept
+                assert_eq!(token.usd_price, "1");
+                assert_eq!(token.verified, Some(true));
+            }
+
+            // The next validation starts from the beginning
+            assert_eq!(s.get_token_validation_checkpoint(), None);
+        });
+    }
+
+    #[test]
+    fn test_validated_token_is_read_again() {
+        let ledger_id = Principal::from_slice(&[1, 1]);
+        let read_token = listed_token(ledger_id, "T");
+        mutate_state(|s| s.icp_token_list.insert(ledger_id, read_token.clone()));
+
+        let validated_token = IcpToken {
+            name: "Renamed".to_string(),
+            usd_price: "0".to_string(),
+            verified: None,
+            ..read_token.clone()
+        };
+
+        // Verification changed during the validation calls
+        mutate_state(|s| {
+            s.icp_token_list.insert(
+                ledger_id,
+                IcpToken {
+                    verified: Some(false),
+                    ..read_token.clone()
+                },
+            )
+        });
+        assert!(mutate_state(
+            |s| s.record_validated_icp_token(validated_token.clone())
+        ));
+        let token = read_state(|s| s.get_icp_token_by_principal(&ledger_id)).unwrap();
+        assert_eq!(token.name, "Renamed");
+        assert_eq!(token.usd_price, "1");
+        assert_eq!(token.verified, Some(false));
+
+        // Blocked during the validation calls
+        mutate_state(|s| {
+            s.icp_token_blocklist.insert(
+                BlockedToken::SymbolPattern("T".to_string()),
+                BlocklistEntry {
+                    reason: None,
+                    added_at: 0,
+                },
+            )
+        });
+        assert!(!mutate_state(
+            |s| s.record_validated_icp_token(validated_token.clone())
+        ));
+
+        // Removed during the validation calls
+        mutate_state(|s| s.icp_token_list.remove(&ledger_id));
+        assert!(!mutate_state(
+            |s| s.record_validated_icp_token(validated_token)
+        ));
+        assert_eq!(
+            read_state(|s| s.get_icp_token_by_principal(&ledger_id)),
+            None
+        );
+    }
 }